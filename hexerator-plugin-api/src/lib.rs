@@ -51,6 +51,39 @@ pub trait HexeratorHandle {
     fn debug_log(&self, msg: &str);
     fn perspective(&self, name: &str) -> Option<PerspectiveHandle>;
     fn perspective_rows(&self, ph: &PerspectiveHandle) -> Vec<&[u8]>;
+    /// The open documents, in tab order
+    fn documents(&self) -> Vec<DocumentHandle> {
+        vec![self.active_document()]
+    }
+    /// The active document, which the methods without a document handle act on
+    ///
+    /// Hosts with a single document can rely on the default, a handle with a key of 0.
+    fn active_document(&self) -> DocumentHandle {
+        DocumentHandle { key_data: 0 }
+    }
+    fn document_data(&self, doc: &DocumentHandle, start: usize, end: usize) -> Option<&[u8]> {
+        if doc.key_data == self.active_document().key_data {
+            self.get_data(start, end)
+        } else {
+            None
+        }
+    }
+    fn document_data_mut(
+        &mut self,
+        doc: &DocumentHandle,
+        start: usize,
+        end: usize,
+    ) -> Option<&mut [u8]> {
+        if doc.key_data == self.active_document().key_data {
+            self.get_data_mut(start, end)
+        } else {
+            None
+        }
+    }
+}
+
+pub struct DocumentHandle {
+    pub key_data: u64,
 }
 
 pub struct PerspectiveHandle {
@@ -2,13 +2,17 @@ use {
     self::{
         backend_command::BackendCommandQueue,
         command::{Cmd, CommandQueue},
+        compare::CompareDoc,
+        document::Documents,
         edit_state::EditState,
     },
     crate::{
         args::{Args, SourceArgs},
+        binfmt,
         config::Config,
         damage_region::DamageRegion,
         data::Data,
+        edit_history::Splice,
         gui::{
             Gui,
             message_dialog::{Icon, MessageDialog},
@@ -48,7 +52,9 @@ use {
 
 pub mod backend_command;
 pub mod command;
+pub mod compare;
 mod debug;
+pub mod document;
 pub mod edit_state;
 pub mod interact_mode;
 pub mod presentation;
@@ -66,7 +72,8 @@ pub struct App {
     pub preferences: SessionPrefs,
     pub hex_ui: HexUi,
     pub meta_state: MetaState,
-    pub clipboard: arboard::Clipboard,
+    /// `None` in batch mode, where there might not be a clipboard to connect to
+    pub clipboard: Option<arboard::Clipboard>,
     /// Command queue for queuing up operations to perform on the application state.
     pub cmd: CommandQueue,
     pub backend_cmd: BackendCommandQueue,
@@ -75,6 +82,10 @@ pub struct App {
     pub plugins: Vec<PluginContainer>,
     /// Size of the buffer used for streaming reads
     pub stream_buffer_size: usize,
+    /// Document shown side by side with the data for comparing
+    pub compare: Option<CompareDoc>,
+    /// All open documents. The fields above hold the state of the active one.
+    pub docs: Documents,
 }
 
 const DEFAULT_STREAM_BUFFER_SIZE: usize = 65_536;
@@ -87,6 +98,18 @@ impl App {
                 SourceProvider::File(file) => {
                     self.data.reload_from_file(&self.src_args, file)?;
                 }
+                SourceProvider::Encoded {
+                    file,
+                    format,
+                    base,
+                    segments,
+                } => {
+                    file.rewind()?;
+                    let image = binfmt::decode(*format, &std::io::read_to_string(&*file)?)?;
+                    self.data = Data::clean_from_buf(image.data);
+                    *base = image.base;
+                    *segments = image.segments;
+                }
                 SourceProvider::Stdin(_) => {
                     anyhow::bail!("Can't reload streaming sources like standard input")
                 }
@@ -98,10 +121,17 @@ impl App {
                 } => unsafe {
                     crate::windows::read_proc_memory(*handle, &mut self.data, *start, *size)?;
                 },
+                // Unlike file reloads, this keeps the undo history, so poked values can be
+                // reverted in the live process
+                #[cfg(target_os = "linux")]
+                SourceProvider::LinuxProc { mem, start, .. } => {
+                    crate::linux::read_proc_memory(mem, &mut self.data, *start)?;
+                    self.data.dirty_region = None;
+                }
             },
             None => anyhow::bail!("No file to reload"),
         }
-        Ok(())
+        self.write_frozen_values()
     }
     pub(crate) fn load_file_args(
         &mut self,
@@ -159,12 +189,40 @@ impl App {
         }
     }
     pub fn save(&mut self, msg: &mut MessageDialog) -> anyhow::Result<()> {
+        self.apply_checksum_rules()?;
         let file = match &mut self.source {
             Some(src) => match &mut src.provider {
                 SourceProvider::File(file) => file,
+                // The whole file is encoded again
+                SourceProvider::Encoded {
+                    file,
+                    format,
+                    base,
+                    segments,
+                } => {
+                    let segments =
+                        binfmt::fit_segments(&self.data, segments, self.data.moved_since_clean())?;
+                    let text = binfmt::encode(*format, &self.data, *base, &segments)?;
+                    file.set_len(0)?;
+                    file.rewind()?;
+                    file.write_all(text.as_bytes())?;
+                    self.data.undirty();
+                    if let Err(e) = self.save_temp_metafile_backup() {
+                        per!("Failed to save metafile backup: {}", e);
+                    }
+                    return Ok(());
+                }
                 SourceProvider::Stdin(_) => anyhow::bail!("Standard input doesn't support saving"),
                 #[cfg(windows)]
-                SourceProvider::WinProc { handle, start, .. } => {
+                SourceProvider::WinProc {
+                    handle,
+                    start,
+                    size,
+                } => {
+                    anyhow::ensure!(
+                        self.data.len() == *size,
+                        "The data doesn't match the size of the process memory range"
+                    );
                     if let Some(region) = self.data.dirty_region {
                         let mut n_write = 0;
                         unsafe {
@@ -183,6 +241,24 @@ impl App {
                     }
                     return Ok(());
                 }
+                #[cfg(target_os = "linux")]
+                SourceProvider::LinuxProc {
+                    mem, start, size, ..
+                } => {
+                    anyhow::ensure!(
+                        self.data.len() == *size,
+                        "The data doesn't match the size of the process memory range"
+                    );
+                    if let Some(region) = self.data.dirty_region {
+                        let bytes = self
+                            .data
+                            .get(region.begin..=region.end)
+                            .context("Dirty region out of bounds")?;
+                        crate::linux::write_proc_memory(mem, bytes, *start + region.begin)?;
+                        self.data.undirty();
+                    }
+                    return Ok(());
+                }
             },
             None => anyhow::bail!("No surce opened, nothing to save"),
         };
@@ -240,12 +316,61 @@ impl App {
         let SourceProvider::File(file) = &mut source.provider else {
             anyhow::bail!("Source is not a file");
         };
-        file.set_len(self.data.len() as u64)?;
-        file.rewind()?;
+        // Only the range that was read from the file is replaced.
+        // Everything after it is moved to follow the new end of the data.
+        let hard_seek = self.src_args.hard_seek.unwrap_or(0) as u64;
+        let old_end = hard_seek + self.data.orig_data_len as u64;
+        let mut tail = Vec::new();
+        file.seek(SeekFrom::Start(old_end))?;
+        file.read_to_end(&mut tail)?;
+        file.seek(SeekFrom::Start(hard_seek))?;
         file.write_all(&self.data)?;
+        file.write_all(&tail)?;
+        file.set_len(hard_seek + self.data.len() as u64 + tail.len() as u64)?;
+        if self.src_args.take.is_some() {
+            self.src_args.take = Some(self.data.len());
+        }
         self.data.undirty();
+        if let Err(e) = self.save_temp_metafile_backup() {
+            per!("Failed to save metafile backup: {}", e);
+        }
         Ok(())
     }
+    /// Encode the data (or just `region` of it) in a text format like Intel HEX.
+    ///
+    /// Data from an encoded source keeps its addresses, and its gaps are left out.
+    pub fn encode_data(
+        &self,
+        format: binfmt::Format,
+        region: Option<Region>,
+    ) -> anyhow::Result<String> {
+        let (base, segments) = match &self.source {
+            Some(Source {
+                provider: SourceProvider::Encoded { base, segments, .. },
+                ..
+            }) => (
+                *base,
+                binfmt::fit_segments(&self.data, segments, self.data.moved_since_clean())?,
+            ),
+            _ => (0, std::iter::once(0..self.data.len()).collect()),
+        };
+        let (data, offset) = match region {
+            Some(reg) => (
+                self.data.get(reg.begin..=reg.end).context("Region out of bounds")?,
+                reg.begin,
+            ),
+            None => (&self.data[..], 0),
+        };
+        let segments: Vec<_> = segments
+            .iter()
+            .filter_map(|seg| {
+                let start = seg.start.max(offset);
+                let end = seg.end.min(offset + data.len());
+                (start < end).then(|| start - offset..end - offset)
+            })
+            .collect();
+        binfmt::encode(format, data, base + offset, &segments)
+    }
     pub(crate) fn source_file(&self) -> Option<&Path> {
         self.src_args.file.as_deref()
     }
@@ -268,6 +393,7 @@ impl App {
                 stream_buffer_size: None,
                 unsafe_mmap: None,
                 mmap_len: None,
+                raw: false,
             },
             None,
             msg,
@@ -331,10 +457,77 @@ impl App {
                 }
                 Ok(())
             }
-            SourceProvider::Stdin(_) => anyhow::bail!("Not implemented"),
+            SourceProvider::Encoded { .. } | SourceProvider::Stdin(_) => {
+                anyhow::bail!("Not implemented")
+            }
             #[cfg(windows)]
             SourceProvider::WinProc { .. } => anyhow::bail!("Not implemented"),
+            #[cfg(target_os = "linux")]
+            SourceProvider::LinuxProc { mem, start, .. } => {
+                let Some(buf) = self.data.get_mut(lo..=hi) else {
+                    anyhow::bail!("Reload range out of bounds")
+                };
+                crate::linux::read_proc_memory(mem, buf, start + lo)
+            }
+        }?;
+        self.write_frozen_values()
+    }
+    /// Recompute the checksums declared by the checksum rules, and write them into the data
+    pub fn apply_checksum_rules(&mut self) -> anyhow::Result<()> {
+        self.meta_state.meta.apply_checksum_rules(&mut self.data)
+    }
+    /// Write the values of frozen bookmarks back into the data, and into the source
+    /// if it's writable, if they changed since they were last written
+    fn write_frozen_values(&mut self) -> anyhow::Result<()> {
+        let changed = self.meta_state.meta.apply_frozen_values(&mut self.data);
+        if !self.source.as_ref().is_some_and(|src| src.attr.permissions.write) {
+            // The values stay in the data, until they can be saved
+            for range in changed {
+                self.data.widen_dirty_region(DamageRegion::Range(range));
+            }
+            return Ok(());
+        }
+        for range in changed {
+            self.write_range_to_source(range)?;
+        }
+        Ok(())
+    }
+    /// Write `range` of the data directly to the source, bypassing the dirty region
+    fn write_range_to_source(&mut self, range: std::ops::Range<usize>) -> anyhow::Result<()> {
+        let bytes = self.data.get(range.clone()).context("Range out of bounds")?;
+        let Some(src) = &mut self.source else {
+            anyhow::bail!("No source")
+        };
+        match &mut src.provider {
+            SourceProvider::File(file) => {
+                let offset = self.src_args.hard_seek.unwrap_or(0) + range.start;
+                file.seek(SeekFrom::Start(offset as u64))?;
+                file.write_all(bytes)?;
+            }
+            // These can only be written as a whole, by saving
+            SourceProvider::Encoded { .. } | SourceProvider::Stdin(_) => {}
+            #[cfg(windows)]
+            SourceProvider::WinProc { handle, start, .. } => {
+                let mut n_write = 0;
+                // Safety: `bytes` is valid for reads of its length, and the process handle
+                // is kept open by the source
+                let ok = unsafe {
+                    windows_sys::Win32::System::Diagnostics::Debug::WriteProcessMemory(
+                        *handle,
+                        (*start + range.start) as _,
+                        bytes.as_ptr() as _,
+                        bytes.len(),
+                        &mut n_write,
+                    )
+                };
+                anyhow::ensure!(ok != 0, "Failed to write process memory");
+            }
+            #[cfg(target_os = "linux")]
+            SourceProvider::LinuxProc { mem, start, .. } => {
+                crate::linux::write_proc_memory(mem, bytes, *start + range.start)?;
+            }
         }
+        Ok(())
     }
     #[allow(clippy::unnecessary_wraps, reason = "cfg shenanigans")]
     pub(crate) fn load_proc_memory(
@@ -343,24 +536,24 @@ impl App {
         start: usize,
         size: usize,
         is_write: bool,
+        #[cfg_attr(
+            target_os = "linux",
+            expect(unused_variables, reason = "Only used on other platforms")
+        )]
         msg: &mut MessageDialog,
         font_size: u16,
         line_spacing: u16,
     ) -> anyhow::Result<()> {
         #[cfg(target_os = "linux")]
-        {
-            load_proc_memory_linux(
-                self,
-                pid,
-                start,
-                size,
-                is_write,
-                msg,
-                font_size,
-                line_spacing,
-            );
-            Ok(())
-        }
+        return crate::linux::load_proc_memory(
+            self,
+            pid,
+            start,
+            size,
+            is_write,
+            font_size,
+            line_spacing,
+        );
         #[cfg(windows)]
         return crate::windows::load_proc_memory(
             self,
@@ -419,7 +612,7 @@ impl App {
         // We set the last_meta_backup first, so if save fails, we don't get
         // a never ending stream of constant save failures.
         self.meta_state.last_meta_backup.set(Instant::now());
-        self.save_meta_to_file(temp_metafile_backup_path(), true)?;
+        self.save_meta_to_file(temp_metafile_backup_path(self.source_file()), true)?;
         per!("Saved temp metafile backup");
         Ok(())
     }
@@ -624,7 +817,8 @@ impl App {
 impl App {
     // Byte offset of a pixel position in the viewport
     //
-    // Also returns the index of the view the position is from
+    // Also returns the index of the view the position is from.
+    // Positions in the compared document map to the same offset in the data.
     pub fn byte_offset_at_pos(&self, x: i16, y: i16) -> Option<(usize, ViewKey)> {
         let layout = self.meta_state.meta.layouts.get(self.hex_ui.current_layout)?;
         for view_key in layout.iter() {
@@ -632,7 +826,7 @@ impl App {
                 return Some((pos, view_key));
             }
         }
-        None
+        self.compare.as_ref()?.byte_offset_at_pos(x, y)
     }
     pub fn view_byte_offset_at_pos(&self, view_key: ViewKey, x: i16, y: i16) -> Option<usize> {
         let NamedView { view, .. } = self.meta_state.meta.views.get(view_key)?;
@@ -670,7 +864,7 @@ impl App {
                 return Some(view_key);
             }
         }
-        None
+        self.compare.as_ref()?.view_at_pos(x, y)
     }
     /// Iterator over the views in the current layout
     fn active_views(&self) -> impl Iterator<Item = &'_ NamedView> {
@@ -798,10 +992,8 @@ fn calc_perspective_row_col_offset(
 /// Editing
 impl App {
     pub(crate) fn mod_byte_at_cursor(&mut self, f: impl FnOnce(&mut u8)) {
-        if let Some(byte) = self.data.get_mut(self.edit_state.cursor) {
-            f(byte);
-            self.data.widen_dirty_region(DamageRegion::Single(self.edit_state.cursor));
-        }
+        let cursor = self.edit_state.cursor;
+        self.data.edit(cursor..cursor + 1, |data| f(&mut data[0]));
     }
 
     pub(crate) fn inc_byte_at_cursor(&mut self) {
@@ -837,6 +1029,92 @@ impl App {
             self.dec_byte_at_cursor();
         }
     }
+
+    /// Undo the last edit, and focus the cursor on it
+    pub(crate) fn undo(&mut self) {
+        self.cancel_focused_view_editing();
+        let splices = self.data.undo();
+        for &splice in &splices {
+            self.follow_splice(splice);
+        }
+        if let Some(splice) = splices.last() {
+            self.search_focus(splice.offset);
+        }
+    }
+
+    /// Redo the last undone edit, and focus the cursor on it
+    pub(crate) fn redo(&mut self) {
+        self.cancel_focused_view_editing();
+        let splices = self.data.redo();
+        for &splice in &splices {
+            self.follow_splice(splice);
+        }
+        if let Some(splice) = splices.last() {
+            self.search_focus(splice.offset);
+        }
+    }
+
+    /// Keep the metadata and selections pointing at the same data after `splice` moved it
+    pub(crate) fn follow_splice(&mut self, splice: Splice) {
+        if !splice.changes_len() {
+            return;
+        }
+        self.meta_state.meta.apply_splice(splice);
+        for sel in [&mut self.hex_ui.select_a, &mut self.hex_ui.select_b].into_iter().flatten() {
+            *sel = splice.map_offset(*sel);
+        }
+        for reg in &mut self.hex_ui.extra_selections {
+            *reg = splice.map_region(*reg);
+        }
+    }
+
+    /// Remove the selected bytes (or the byte at the cursor if nothing is selected),
+    /// shifting the following bytes back
+    pub(crate) fn delete_selection_or_cursor(&mut self) -> anyhow::Result<()> {
+        let mut regions: Vec<Region> = self.hex_ui.selected_regions().collect();
+        if regions.is_empty() {
+            regions.push(Region {
+                begin: self.edit_state.cursor,
+                end: self.edit_state.cursor,
+            });
+        }
+        // Merge overlapping selections, so no byte is removed twice
+        regions.sort_by_key(|reg| reg.begin);
+        let mut merged: Vec<Region> = Vec::new();
+        for reg in regions {
+            match merged.last_mut() {
+                Some(last) if reg.begin <= last.end + 1 => last.end = last.end.max(reg.end),
+                _ => merged.push(reg),
+            }
+        }
+        self.cancel_focused_view_editing();
+        self.hex_ui.clear_selections();
+        // Remove from the back, so removals don't move the regions that are yet to be removed
+        for reg in merged.iter().rev() {
+            let Some(splice) = self.data.remove(reg.begin..reg.end + 1) else {
+                anyhow::bail!(
+                    "Failed to remove {}..={}. Removing requires the data to be in bounds, \
+                     loaded into memory (not memory mapped), \
+                     and from a source that can change size (not process memory).",
+                    reg.begin,
+                    reg.end
+                );
+            };
+            self.follow_splice(splice);
+        }
+        if let Some(first) = merged.first() {
+            self.edit_state.cursor = first.begin.min(self.data.len().saturating_sub(1));
+        }
+        Ok(())
+    }
+
+    /// Throw away any unfinished edit in the focused view, so it doesn't get applied on top of
+    /// an undo/redo
+    fn cancel_focused_view_editing(&mut self) {
+        if let Some((_, view)) = self.focused_view_mut() {
+            view.cancel_editing();
+        }
+    }
 }
 
 /// Etc.
@@ -865,12 +1143,18 @@ impl App {
             preferences: SessionPrefs::default(),
             hex_ui: HexUi::default(),
             meta_state: MetaState::default(),
-            clipboard: arboard::Clipboard::new()?,
+            clipboard: if args.batch {
+                None
+            } else {
+                Some(arboard::Clipboard::new()?)
+            },
             cmd: Default::default(),
             backend_cmd: Default::default(),
             quit_requested: false,
             plugins: Vec::new(),
             stream_buffer_size: args.src.stream_buffer_size.unwrap_or(DEFAULT_STREAM_BUFFER_SIZE),
+            compare: None,
+            docs: Documents::default(),
         };
         for path in args.load_plugin {
             // Safety: This will cause UB on a bad plugin. Nothing we can do.
@@ -1030,13 +1314,26 @@ impl App {
     ) {
         if !self.hex_ui.current_layout.is_null() {
             let layout = &self.meta_state.meta.layouts[self.hex_ui.current_layout];
+            // When comparing, the compared document takes the right half
+            let mut rect = self.hex_ui.hex_iface_rect;
+            if self.compare.is_some() {
+                rect.w /= 2;
+            }
             do_auto_layout(
                 layout,
                 &mut self.meta_state.meta.views,
-                &self.hex_ui.hex_iface_rect,
+                &rect,
                 &self.meta_state.meta.low.perspectives,
                 &self.meta_state.meta.low.regions,
             );
+            if let Some(doc) = &mut self.compare {
+                doc.sync_layout(
+                    &self.meta_state.meta,
+                    self.hex_ui.current_layout,
+                    layout,
+                    rect.w,
+                );
+            }
         }
         if self.preferences.auto_save
             && self.data.dirty_region.is_some()
@@ -1163,6 +1460,8 @@ impl App {
             Err(format!("Plugin `{plugin_name}` not found."))
         };
         std::mem::swap(&mut self.plugins, &mut plugins);
+        // The plugin might have modified the data through `get_data_mut`
+        self.commit_pending_edits();
         result
     }
 
@@ -1213,7 +1512,18 @@ pub fn setup_empty_meta(
     meta.layouts.insert(layout)
 }
 
-pub fn get_clipboard_string(cb: &mut arboard::Clipboard, msg: &mut MessageDialog) -> String {
+pub fn get_clipboard_string(
+    cb: &mut Option<arboard::Clipboard>,
+    msg: &mut MessageDialog,
+) -> String {
+    let Some(cb) = cb else {
+        msg.open(
+            Icon::Error,
+            "Failed to get text from clipboard",
+            NO_CLIPBOARD,
+        );
+        return String::new();
+    };
     match cb.get_text() {
         Ok(text) => text,
         Err(e) => {
@@ -1227,41 +1537,21 @@ pub fn get_clipboard_string(cb: &mut arboard::Clipboard, msg: &mut MessageDialog
     }
 }
 
-pub fn set_clipboard_string(cb: &mut arboard::Clipboard, msg: &mut MessageDialog, text: &str) {
-    msg_if_fail(cb.set_text(text), "Failed to set clipboard text", msg);
-}
-
-#[cfg(target_os = "linux")]
-fn load_proc_memory_linux(
-    app: &mut App,
-    pid: sysinfo::Pid,
-    start: usize,
-    size: usize,
-    is_write: bool,
+pub fn set_clipboard_string(
+    cb: &mut Option<arboard::Clipboard>,
     msg: &mut MessageDialog,
-    font_size: u16,
-    line_spacing: u16,
+    text: &str,
 ) {
-    app.load_file_args(
-        SourceArgs {
-            file: Some(Path::new("/proc/").join(pid.to_string()).join("mem")),
-            jump: None,
-            hard_seek: Some(start),
-            take: Some(size),
-            read_only: !is_write,
-            stream: false,
-            stream_buffer_size: None,
-            unsafe_mmap: None,
-            mmap_len: None,
-        },
-        None,
-        msg,
-        font_size,
-        line_spacing,
-        None,
-    );
+    match cb {
+        Some(cb) => {
+            msg_if_fail(cb.set_text(text), "Failed to set clipboard text", msg);
+        }
+        None => msg_fail(&NO_CLIPBOARD, "Failed to set clipboard text", msg),
+    }
 }
 
+const NO_CLIPBOARD: &str = "Clipboard is not available";
+
 #[cfg(target_os = "macos")]
 fn load_proc_memory_macos(
     app: &mut App,
@@ -1311,8 +1601,24 @@ pub fn read_source_to_buf(path: &Path, args: &SourceArgs) -> Result<Vec<u8>, any
     Ok(buf)
 }
 
-pub fn temp_metafile_backup_path() -> PathBuf {
-    std::env::temp_dir().join("hexerator_meta_backup.meta")
+/// Where the metafile of the document opened from `file` is backed up
+///
+/// Files with the same name in different directories get different backups,
+/// by hashing the full path.
+pub fn temp_metafile_backup_path(file: Option<&Path>) -> PathBuf {
+    const CRC64: crc::Crc<u64> = crc::Crc::<u64>::new(&crc::CRC_64_ECMA_182);
+    let name = match file.and_then(|path| Some((path, path.file_name()?))) {
+        Some((path, name)) => {
+            let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+            let hash = CRC64.checksum(path.as_os_str().as_encoded_bytes());
+            format!(
+                "hexerator_meta_backup_{}_{hash:016x}.meta",
+                name.to_string_lossy()
+            )
+        }
+        None => "hexerator_meta_backup.meta".into(),
+    };
+    std::env::temp_dir().join(name)
 }
 
 pub fn col_change_impl_view_perspective(
@@ -1380,6 +1686,11 @@ fn load_file_from_src_args(
         } else {
             let result = try {
                 let mut file = open_file(file_arg, src_args.read_only)?;
+                let encoding = if src_args.raw || src_args.stream {
+                    None
+                } else {
+                    binfmt::Format::from_path(file_arg)
+                };
                 if let Some(path) = &mut src_args.file {
                     match path.canonicalize() {
                         Ok(canon) => *path = canon,
@@ -1396,42 +1707,71 @@ fn load_file_from_src_args(
                     }
                 }
                 cfg.recent.use_(src_args.clone());
-                if !src_args.stream {
-                    if let Some(mmap_mode) = src_args.unsafe_mmap {
-                        let mut opts = memmap2::MmapOptions::new();
-                        if let Some(len) = src_args.mmap_len {
-                            opts.len(len);
-                        }
-                        // Safety:
-                        //
-                        // Memory mapped file access cannot be made 100% safe, not much we can do here.
-                        //
-                        // The command line option is called `--unsafe-mmap` to reflect this.
-                        *data = unsafe {
-                            match mmap_mode {
-                                crate::args::MmapMode::Cow => {
-                                    Data::new_mmap_mut(opts.map_copy(&file)?)
-                                }
-                                crate::args::MmapMode::DangerousMut => {
-                                    Data::new_mmap_mut(opts.map_mut(&file)?)
-                                }
-                                crate::args::MmapMode::Ro => Data::new_mmap_immut(opts.map(&file)?),
+                if let Some(format) = encoding {
+                    if src_args.hard_seek.is_some() || src_args.take.is_some() {
+                        do yeet std::io::Error::other(
+                            "--hard-seek and --take can't be used on encoded files. \
+                             Use --raw to open the file as it is.",
+                        );
+                    }
+                    let image = binfmt::decode(format, &std::io::read_to_string(&file)?)
+                        .map_err(std::io::Error::other)?;
+                    *data = Data::clean_from_buf(image.data);
+                    *source = Some(Source {
+                        provider: SourceProvider::Encoded {
+                            file,
+                            format,
+                            base: image.base,
+                            segments: image.segments,
+                        },
+                        attr: SourceAttributes {
+                            stream: false,
+                            permissions: SourcePermissions {
+                                write: !src_args.read_only,
+                            },
+                        },
+                        state: SourceState::default(),
+                    });
+                } else {
+                    if !src_args.stream {
+                        if let Some(mmap_mode) = src_args.unsafe_mmap {
+                            let mut opts = memmap2::MmapOptions::new();
+                            if let Some(len) = src_args.mmap_len {
+                                opts.len(len);
                             }
-                        };
-                    } else {
-                        *data = Data::clean_from_buf(read_contents(&*src_args, &mut file)?);
+                            // Safety:
+                            //
+                            // Memory mapped file access cannot be made 100% safe, not much we can do here.
+                            //
+                            // The command line option is called `--unsafe-mmap` to reflect this.
+                            *data = unsafe {
+                                match mmap_mode {
+                                    crate::args::MmapMode::Cow => {
+                                        Data::new_mmap_mut(opts.map_copy(&file)?)
+                                    }
+                                    crate::args::MmapMode::DangerousMut => {
+                                        Data::new_mmap_mut(opts.map_mut(&file)?)
+                                    }
+                                    crate::args::MmapMode::Ro => {
+                                        Data::new_mmap_immut(opts.map(&file)?)
+                                    }
+                                }
+                            };
+                        } else {
+                            *data = Data::clean_from_buf(read_contents(&*src_args, &mut file)?);
+                        }
                     }
-                }
-                *source = Some(Source {
-                    provider: SourceProvider::File(file),
-                    attr: SourceAttributes {
-                        stream: src_args.stream,
-                        permissions: SourcePermissions {
-                            write: !src_args.read_only,
+                    *source = Some(Source {
+                        provider: SourceProvider::File(file),
+                        attr: SourceAttributes {
+                            stream: src_args.stream,
+                            permissions: SourcePermissions {
+                                write: !src_args.read_only,
+                            },
                         },
-                    },
-                    state: SourceState::default(),
-                });
+                        state: SourceState::default(),
+                    });
+                }
                 cmd.push(Cmd::ProcessSourceChange);
             };
             match result {
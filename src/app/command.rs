@@ -10,9 +10,8 @@
 use {
     super::{App, backend_command::BackendCmd},
     crate::{
-        damage_region::DamageRegion,
         data::Data,
-        gui::Gui,
+        gui::{Gui, message_dialog::Icon},
         meta::{NamedView, PerspectiveKey, RegionKey},
         scripting::exec_lua,
         shell::msg_if_fail,
@@ -49,6 +48,10 @@ pub enum Cmd {
     },
     /// A new source was loaded, process the changes
     ProcessSourceChange,
+    /// Another document was made active, process the changes
+    ProcessDocumentSwitch,
+    /// Stop comparing, discarding any unsaved changes of the compared document
+    StopComparing,
 }
 
 /// Application command queue.
@@ -139,13 +142,31 @@ pub fn perform_command(
             );
         }
         Cmd::ExtendDocument { new_len } => {
-            app.data.resize(new_len, 0);
+            if app.data.can_change_len() {
+                app.data.resize(new_len, 0);
+            } else {
+                gui.msg_dialog.open(
+                    Icon::Error,
+                    "Extend error",
+                    "The length of this data can't change (memory mapped, or process memory)",
+                );
+            }
         }
         Cmd::PasteBytes { at, bytes } => {
-            let range = at..at + bytes.len();
-            app.data[range.clone()].copy_from_slice(&bytes);
-            app.data.widen_dirty_region(DamageRegion::Range(range));
+            if !app.data.write(at, &bytes) {
+                gui.msg_dialog.open(
+                    Icon::Error,
+                    "Paste error",
+                    format!(
+                        "Paste out of bounds.\nOffset: {at}, length: {}\nData length: {}",
+                        bytes.len(),
+                        app.data.len()
+                    ),
+                );
+            }
         }
+        Cmd::ProcessDocumentSwitch => gui.win.clear_doc_state(),
+        Cmd::StopComparing => app.compare = None,
         Cmd::ProcessSourceChange => {
             // Allocate a clean data buffer for streaming sources
             if app.source.as_ref().is_some_and(|src| src.attr.stream) {
@@ -0,0 +1,218 @@
+//! Comparing the data side by side with another document
+
+use {
+    super::{App, read_source_to_buf},
+    crate::{
+        args::SourceArgs,
+        data::Data,
+        layout::Layout,
+        meta::{LayoutKey, Meta, MetaLow, PerspectiveKey, ViewKey, region::Region},
+        view::{View, ViewportScalar},
+    },
+    anyhow::Context as _,
+    std::{collections::HashMap, path::PathBuf},
+};
+
+/// Another document shown next to the data, scrolling together with it by offset
+pub struct CompareDoc {
+    pub path: PathBuf,
+    pub data: Data,
+    /// Regions and perspectives of this document
+    pub meta_low: MetaLow,
+    /// Views of this document, each paired with the view of the current layout
+    /// it scrolls together with
+    pub views: Vec<(ViewKey, View)>,
+    /// The layout the views were created for, and the views it had then
+    layout: (LayoutKey, Vec<ViewKey>),
+    /// Highlight the bytes that differ between the two documents
+    pub highlight_diff: bool,
+}
+
+impl CompareDoc {
+    fn new(path: PathBuf, data: Vec<u8>) -> Self {
+        Self {
+            path,
+            data: Data::clean_from_buf(data),
+            meta_low: MetaLow::default(),
+            views: Vec::new(),
+            layout: (LayoutKey::default(), Vec::new()),
+            highlight_diff: true,
+        }
+    }
+    /// Keep the views in sync with the views of `layout` they're paired with,
+    /// shifted right by `x_shift`.
+    ///
+    /// The views are only created when the layout changes. After that, just their position
+    /// and the offset they're scrolled to follow the paired views.
+    pub(crate) fn sync_layout(
+        &mut self,
+        meta: &Meta,
+        layout_key: LayoutKey,
+        layout: &Layout,
+        x_shift: ViewportScalar,
+    ) {
+        if self.layout.0 != layout_key || !self.layout.1.iter().copied().eq(layout.iter()) {
+            self.create_views(meta, layout);
+            self.layout = (layout_key, layout.iter().collect());
+        }
+        for (key, view) in &mut self.views {
+            let Some(named) = meta.views.get(*key) else {
+                continue;
+            };
+            view.viewport_rect = named.view.viewport_rect;
+            view.viewport_rect.x += x_shift;
+            let offset = named.view.offsets(&meta.low.perspectives, &meta.low.regions).byte;
+            view.scroll_to_byte_offset(
+                offset,
+                &self.meta_low.perspectives,
+                &self.meta_low.regions,
+                true,
+                true,
+            );
+        }
+    }
+    /// Create a view for each view of `layout`, with its own copy of the perspective
+    /// and region it uses, limited to the length of this document
+    fn create_views(&mut self, meta: &Meta, layout: &Layout) {
+        self.meta_low.regions.clear();
+        self.meta_low.perspectives.clear();
+        self.views.clear();
+        let mut per_keys: HashMap<PerspectiveKey, PerspectiveKey> = HashMap::new();
+        for key in layout.iter() {
+            let Some(named) = meta.views.get(key) else {
+                continue;
+            };
+            let per_key = match per_keys.get(&named.view.perspective) {
+                Some(&per_key) => per_key,
+                None => {
+                    let Some(mut per) = meta.low.perspectives.get(named.view.perspective).cloned()
+                    else {
+                        continue;
+                    };
+                    let Some(mut reg) = meta.low.regions.get(per.region).cloned() else {
+                        continue;
+                    };
+                    reg.region.end = reg.region.end.min(self.data.len().saturating_sub(1));
+                    if reg.region.begin > reg.region.end {
+                        continue;
+                    }
+                    per.region = self.meta_low.regions.insert(reg);
+                    let per_key = self.meta_low.perspectives.insert(per);
+                    per_keys.insert(named.view.perspective, per_key);
+                    per_key
+                }
+            };
+            let mut view = named.view.clone();
+            view.perspective = per_key;
+            view.reset_edit_buf();
+            self.views.push((key, view));
+        }
+    }
+    /// Byte offset at a viewport position, along with the view of the layout
+    /// that the view under the position is paired with
+    pub fn byte_offset_at_pos(
+        &self,
+        x: ViewportScalar,
+        y: ViewportScalar,
+    ) -> Option<(usize, ViewKey)> {
+        self.views.iter().find_map(|(key, view)| {
+            let [row, col] = view.row_col_offset_of_pos(
+                x,
+                y,
+                &self.meta_low.perspectives,
+                &self.meta_low.regions,
+            )?;
+            let offset = self.meta_low.perspectives[view.perspective].byte_offset_of_row_col(
+                row,
+                col,
+                &self.meta_low.regions,
+            );
+            Some((offset, *key))
+        })
+    }
+    /// The view of the layout that the view at a viewport position is paired with
+    pub fn view_at_pos(&self, x: ViewportScalar, y: ViewportScalar) -> Option<ViewKey> {
+        self.views
+            .iter()
+            .find_map(|(key, view)| view.viewport_rect.contains_pos(x, y).then_some(*key))
+    }
+    pub(crate) fn save(&mut self) -> anyhow::Result<()> {
+        std::fs::write(&self.path, &self.data[..])?;
+        self.data.undirty();
+        Ok(())
+    }
+}
+
+/// Comparing
+impl App {
+    /// Open `path` for comparing side by side with the data
+    pub(crate) fn open_compare(&mut self, path: PathBuf) -> anyhow::Result<()> {
+        // The whole file, so saving writes it back as it was
+        let data = read_source_to_buf(&path, &SourceArgs::default())?;
+        anyhow::ensure!(!data.is_empty(), "File is empty");
+        self.compare = Some(CompareDoc::new(path, data));
+        Ok(())
+    }
+    /// Copy the bytes of `region` from the data to the compared document
+    pub(crate) fn copy_to_compare(&mut self, region: Region) -> anyhow::Result<()> {
+        let doc = self.compare.as_mut().context("Not comparing")?;
+        let bytes = self.data.get(region.begin..=region.end).context("Region out of bounds")?;
+        anyhow::ensure!(
+            doc.data.write(region.begin, bytes),
+            "Region is out of bounds of the compared document"
+        );
+        Ok(())
+    }
+    /// Copy the bytes of `region` from the compared document to the data
+    pub(crate) fn copy_from_compare(&mut self, region: Region) -> anyhow::Result<()> {
+        let doc = self.compare.as_ref().context("Not comparing")?;
+        let bytes = doc
+            .data
+            .get(region.begin..=region.end)
+            .context("Region is out of bounds of the compared document")?;
+        anyhow::ensure!(self.data.write(region.begin, bytes), "Region out of bounds");
+        Ok(())
+    }
+}
+
+/// Offset of the first byte at or after `from` that differs between `a` and `b`.
+///
+/// Bytes past the end of the shorter one count as differing.
+pub fn next_difference(a: &[u8], b: &[u8], from: usize) -> Option<usize> {
+    let common = a.len().min(b.len());
+    if from < common
+        && let Some(pos) = a[from..common].iter().zip(&b[from..common]).position(|(x, y)| x != y)
+    {
+        return Some(from + pos);
+    }
+    let off = from.max(common);
+    (off < a.len().max(b.len())).then_some(off)
+}
+
+/// Offset of the last byte before `before` that differs between `a` and `b`.
+///
+/// Bytes past the end of the shorter one count as differing.
+pub fn prev_difference(a: &[u8], b: &[u8], before: usize) -> Option<usize> {
+    let end = before.min(a.len().max(b.len()));
+    if end > a.len().min(b.len()) {
+        return Some(end - 1);
+    }
+    a[..end].iter().zip(&b[..end]).rposition(|(x, y)| x != y)
+}
+
+#[test]
+fn test_difference_navigation() {
+    let a = b"hello world";
+    let b = b"hallo werld!!";
+    assert_eq!(next_difference(a, b, 0), Some(1));
+    assert_eq!(next_difference(a, b, 2), Some(7));
+    assert_eq!(next_difference(a, b, 8), Some(11));
+    assert_eq!(next_difference(a, b, 12), Some(12));
+    assert_eq!(next_difference(a, b, 13), None);
+    assert_eq!(prev_difference(a, b, 13), Some(12));
+    assert_eq!(prev_difference(a, b, 11), Some(7));
+    assert_eq!(prev_difference(a, b, 7), Some(1));
+    assert_eq!(prev_difference(a, b, 1), None);
+    assert_eq!(next_difference(a, a, 0), None);
+    assert_eq!(prev_difference(a, a, 100), None);
+}
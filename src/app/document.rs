@@ -0,0 +1,252 @@
+//! Multiple open documents
+//!
+//! The state of the active document lives directly in [`App`], so the rest of the application
+//! doesn't have to care about documents. The other documents are parked in [`Documents`],
+//! and swapped in when switching to them.
+
+use {
+    super::{
+        App, command::Cmd, compare::CompareDoc, edit_state::EditState, temp_metafile_backup_path,
+    },
+    crate::{
+        args::SourceArgs, data::Data, gui::message_dialog::MessageDialog, hex_ui::HexUi,
+        meta_state::MetaState, source::Source,
+    },
+    anyhow::Context as _,
+    slotmap::{Key as _, KeyData, SlotMap, new_key_type},
+    std::{sync::mpsc::Receiver, time::Instant},
+};
+
+new_key_type! {
+    pub struct DocKey;
+}
+
+impl DocKey {
+    /// Handle that can be passed to Lua and plugins
+    pub fn to_handle(self) -> u64 {
+        self.data().as_ffi()
+    }
+    pub fn from_handle(handle: u64) -> Self {
+        KeyData::from_ffi(handle).into()
+    }
+}
+
+/// The state of a document that isn't active
+pub struct Document {
+    data: Data,
+    edit_state: EditState,
+    src_args: SourceArgs,
+    source: Option<Source>,
+    stream_read_recv: Option<Receiver<Vec<u8>>>,
+    last_reload: Instant,
+    hex_ui: HexUi,
+    meta_state: MetaState,
+    compare: Option<CompareDoc>,
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Self {
+            data: Data::default(),
+            edit_state: EditState::default(),
+            src_args: SourceArgs::default(),
+            source: None,
+            stream_read_recv: None,
+            last_reload: Instant::now(),
+            hex_ui: HexUi::default(),
+            meta_state: MetaState::default(),
+            compare: None,
+        }
+    }
+}
+
+impl Document {
+    pub fn data(&self) -> &Data {
+        &self.data
+    }
+    pub fn data_mut(&mut self) -> &mut Data {
+        &mut self.data
+    }
+}
+
+/// The open documents, in tab order
+pub struct Documents {
+    /// The active document. Its state is in [`App`].
+    pub active: DocKey,
+    /// `None` for the active document
+    map: SlotMap<DocKey, Option<Document>>,
+    order: Vec<DocKey>,
+}
+
+impl Default for Documents {
+    fn default() -> Self {
+        let mut map = SlotMap::with_key();
+        let active = map.insert(None);
+        Self {
+            active,
+            map,
+            order: vec![active],
+        }
+    }
+}
+
+impl Documents {
+    /// Keys of the open documents, in tab order
+    pub fn keys(&self) -> &[DocKey] {
+        &self.order
+    }
+    /// An inactive document. `None` for the active one.
+    pub fn parked(&self, key: DocKey) -> Option<&Document> {
+        self.map.get(key)?.as_ref()
+    }
+    pub fn parked_mut(&mut self, key: DocKey) -> Option<&mut Document> {
+        self.map.get_mut(key)?.as_mut()
+    }
+}
+
+/// Documents
+impl App {
+    /// Swap the state of the active document with `doc`
+    fn swap_active(&mut self, doc: &mut Document) {
+        std::mem::swap(&mut self.data, &mut doc.data);
+        std::mem::swap(&mut self.edit_state, &mut doc.edit_state);
+        std::mem::swap(&mut self.src_args, &mut doc.src_args);
+        std::mem::swap(&mut self.source, &mut doc.source);
+        std::mem::swap(&mut self.stream_read_recv, &mut doc.stream_read_recv);
+        std::mem::swap(&mut self.last_reload, &mut doc.last_reload);
+        std::mem::swap(&mut self.meta_state, &mut doc.meta_state);
+        std::mem::swap(&mut self.compare, &mut doc.compare);
+        // The hex interface area belongs to the window, not the document
+        let rect = self.hex_ui.hex_iface_rect;
+        std::mem::swap(&mut self.hex_ui, &mut doc.hex_ui);
+        self.hex_ui.hex_iface_rect = rect;
+    }
+    /// Make `key` the active document
+    pub(crate) fn switch_document(&mut self, key: DocKey) -> anyhow::Result<()> {
+        let slot = self.docs.map.get_mut(key).context("No such document")?;
+        let Some(mut doc) = slot.take() else {
+            // Already active
+            return Ok(());
+        };
+        self.swap_active(&mut doc);
+        self.docs.map[self.docs.active] = Some(doc);
+        self.docs.active = key;
+        self.cmd.push(Cmd::ProcessDocumentSwitch);
+        Ok(())
+    }
+    /// Open a new empty document, and make it active
+    pub(crate) fn new_document(&mut self) -> DocKey {
+        let key = self.docs.map.insert(Some(Document::default()));
+        self.docs.order.push(key);
+        // Can't fail, we just inserted it
+        let _ = self.switch_document(key);
+        key
+    }
+    /// Load a file into a new document.
+    ///
+    /// If the active document is empty, it's loaded into that instead.
+    pub(crate) fn load_file_args_new_document(
+        &mut self,
+        src_args: SourceArgs,
+        msg: &mut MessageDialog,
+        font_size: u16,
+        line_spacing: u16,
+    ) -> Option<DocKey> {
+        let prev = self.docs.active;
+        let fresh = self.source.is_none() && self.data.is_empty();
+        if !fresh {
+            self.new_document();
+        }
+        // A new document has no meta to keep
+        let keep_meta = std::mem::replace(&mut self.preferences.keep_meta, false);
+        self.load_file_args(src_args, None, msg, font_size, line_spacing, None);
+        self.preferences.keep_meta = keep_meta;
+        if self.source.is_some() {
+            return Some(self.docs.active);
+        }
+        if !fresh {
+            let failed = self.docs.active;
+            let _ = self.switch_document(prev);
+            self.close_document(failed);
+        }
+        None
+    }
+    /// Close a document. Closing the last document just closes its file.
+    pub(crate) fn close_document(&mut self, key: DocKey) {
+        if !self.docs.map.contains_key(key) {
+            return;
+        }
+        if self.docs.order.len() == 1 {
+            self.close_file();
+            return;
+        }
+        let idx = self.docs.order.iter().position(|&k| k == key).unwrap_or(0);
+        self.docs.order.retain(|&k| k != key);
+        if key == self.docs.active {
+            let next = self.docs.order[idx.min(self.docs.order.len() - 1)];
+            let _ = self.switch_document(next);
+        }
+        if let Some(Some(mut doc)) = self.docs.map.remove(key) {
+            // We potentially had large data, free it
+            doc.data.close();
+        }
+    }
+    /// Data of a document, whether it's active or not
+    pub fn doc_data(&self, key: DocKey) -> Option<&Data> {
+        if key == self.docs.active {
+            Some(&self.data)
+        } else {
+            self.docs.parked(key).map(Document::data)
+        }
+    }
+    pub fn doc_data_mut(&mut self, key: DocKey) -> Option<&mut Data> {
+        if key == self.docs.active {
+            Some(&mut self.data)
+        } else {
+            self.docs.parked_mut(key).map(Document::data_mut)
+        }
+    }
+    /// Source arguments of a document, whether it's active or not
+    pub fn doc_src_args(&self, key: DocKey) -> Option<&SourceArgs> {
+        if key == self.docs.active {
+            Some(&self.src_args)
+        } else {
+            self.docs.parked(key).map(|doc| &doc.src_args)
+        }
+    }
+    /// Short name of a document for showing on its tab
+    pub fn doc_title(&self, key: DocKey) -> String {
+        match self.doc_src_args(key).and_then(|args| args.file.as_ref()) {
+            Some(path) => path.file_name().map_or_else(
+                || path.display().to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            ),
+            None => String::from("Untitled"),
+        }
+    }
+    /// Save a metafile backup of every open document
+    pub(crate) fn save_temp_metafile_backups(&mut self) -> anyhow::Result<()> {
+        let mut result = self.save_temp_metafile_backup();
+        for doc in self.docs.map.values().flatten() {
+            doc.meta_state.last_meta_backup.set(Instant::now());
+            let saved: anyhow::Result<()> = try {
+                let data = rmp_serde::to_vec(&doc.meta_state.meta)?;
+                std::fs::write(
+                    temp_metafile_backup_path(doc.src_args.file.as_deref()),
+                    data,
+                )?;
+            };
+            if saved.is_err() {
+                result = saved;
+            }
+        }
+        result
+    }
+    /// Record the changes plugins made through deferred slices, in every document
+    pub(crate) fn commit_pending_edits(&mut self) {
+        self.data.commit_pending_edits();
+        for doc in self.docs.map.values_mut().flatten() {
+            doc.data.commit_pending_edits();
+        }
+    }
+}
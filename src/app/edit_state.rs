@@ -2,6 +2,9 @@
 pub struct EditState {
     // The editing byte offset
     pub cursor: usize,
+    /// Whether entered bytes are inserted at the cursor, shifting the following bytes forward,
+    /// instead of overwriting
+    pub insert_mode: bool,
     cursor_history: Vec<usize>,
     cursor_history_current: usize,
 }
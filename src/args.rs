@@ -56,6 +56,20 @@ pub struct Args {
     /// Set the initial column count of the default perspective
     #[arg(short = 'c', long = "col")]
     pub column_count: Option<usize>,
+    /// Run without opening a window: load the file, run `--script` on it, save, and exit.
+    ///
+    /// Exits with a nonzero status if anything fails.
+    #[arg(long, requires = "script")]
+    #[serde(default)]
+    pub batch: bool,
+    /// The script to run in batch mode. Either the name of a script in the metafile, or a path.
+    #[arg(long, value_name = "name or path", requires = "batch")]
+    #[serde(default)]
+    pub script: Option<String>,
+    /// Arguments for the script (e.g. `mynum = 4.5, mystring = "hello"`)
+    #[arg(long, value_name = "args", default_value = "")]
+    #[serde(default)]
+    pub script_args: String,
 }
 
 /// Arguments for opening a source (file/stream/process/etc)
@@ -91,6 +105,10 @@ pub struct SourceArgs {
     #[serde(default)]
     #[arg(long, value_name = "len")]
     pub mmap_len: Option<usize>,
+    /// Open Intel HEX, S-record and base64 files as they are, instead of decoding them
+    #[serde(default)]
+    #[arg(long)]
+    pub raw: bool,
 }
 
 /// How the memory mapping should operate
@@ -116,6 +134,6 @@ pub enum MmapMode {
     /// Changes are only visible locally.
     Cow,
     /// Mutable memory map.
-    /// *WARNING*: Any edits will immediately take effect. THEY CANNOT BE UNDONE.
+    /// *WARNING*: Any edits will immediately take effect. They can only be undone during the session.
     DangerousMut,
 }
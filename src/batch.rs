@@ -0,0 +1,110 @@
+//! Headless batch mode: run a script on a file without opening a window
+
+use {
+    crate::{
+        app::App,
+        args::Args,
+        config::Config,
+        gui::{ConMsg, Gui, message_dialog::Icon},
+        scripting::exec_lua,
+        source::SourceProvider,
+    },
+    anyhow::Context as _,
+    mlua::Lua,
+};
+
+/// No font is loaded in batch mode.
+/// These only affect the layout of views, which doesn't matter without a window.
+const FONT_SIZE: u16 = 14;
+const LINE_SPACING: u16 = 17;
+
+/// Load the source, run the script, and save the result.
+///
+/// Any error (including the ones that would be shown in a message dialog) fails the run.
+pub fn run(mut args: Args, cfg: Config) -> anyhow::Result<()> {
+    let script = args.script.take().context("Batch mode requires --script")?;
+    let script_args = std::mem::take(&mut args.script_args);
+    let mut gui = Gui::default();
+    let lua = Lua::default();
+    let mut app = App::new(args, cfg, FONT_SIZE, LINE_SPACING, &mut gui)?;
+    // Finish loading the source (this also runs the onload script, if any)
+    app.flush_command_queue(&mut gui, &lua, FONT_SIZE, LINE_SPACING);
+    print_log(&mut gui);
+    report_messages(&mut gui)?;
+    anyhow::ensure!(app.source.is_some(), "No source opened");
+    let (content, key) = match app
+        .meta_state
+        .meta
+        .scripts
+        .iter()
+        .find(|(_k, scr)| scr.name == script)
+    {
+        Some((key, scr)) => (scr.content.clone(), Some(key)),
+        None => (
+            std::fs::read_to_string(&script).with_context(|| {
+                format!(
+                    "No script named '{script}' in the metafile, and failed to read it as a path"
+                )
+            })?,
+            None,
+        ),
+    };
+    let out = exec_lua(
+        &lua,
+        &content,
+        &mut app,
+        &mut gui,
+        &script_args,
+        key,
+        FONT_SIZE,
+        LINE_SPACING,
+    );
+    print_log(&mut gui);
+    let out = out?;
+    if let Some(out) = out {
+        println!("{out}");
+    }
+    app.flush_command_queue(&mut gui, &lua, FONT_SIZE, LINE_SPACING);
+    report_messages(&mut gui)?;
+    let is_file = app
+        .source
+        .as_ref()
+        .is_some_and(|src| matches!(src.provider, SourceProvider::File(_)));
+    if is_file && app.data.len() != app.data.orig_data_len {
+        // Normally this asks for confirmation, but the script asked for it
+        app.apply_checksum_rules()?;
+        app.save_truncated_file_finish()?;
+    } else if app.data.dirty_region.is_some() {
+        // Encoded sources are encoded again, whatever the length
+        app.save(&mut gui.msg_dialog)?;
+        report_messages(&mut gui)?;
+    }
+    Ok(())
+}
+
+/// Print what the scripts logged to the Lua console
+fn print_log(gui: &mut Gui) {
+    let console = &mut gui.win.lua_console;
+    let bufs = std::iter::once(std::mem::take(&mut console.default_msg_buf))
+        .chain(console.msg_bufs.drain().map(|(_key, buf)| buf));
+    for msg in bufs.flatten() {
+        match msg {
+            ConMsg::Plain(text)
+            | ConMsg::OffsetLink { text, .. }
+            | ConMsg::RangeLink { text, .. } => println!("{text}"),
+        }
+    }
+}
+
+/// Print the messages that would be shown in message dialogs, and fail if there were errors
+fn report_messages(gui: &mut Gui) -> anyhow::Result<()> {
+    let mut n_errors = 0;
+    for payload in gui.msg_dialog.take_payloads() {
+        if matches!(payload.icon, Icon::Error) {
+            n_errors += 1;
+        }
+        eprintln!("{}: {}", payload.title, payload.desc);
+    }
+    anyhow::ensure!(n_errors == 0, "{n_errors} error(s) occurred");
+    Ok(())
+}
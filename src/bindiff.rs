@@ -0,0 +1,398 @@
+//! Binary diff that aligns the data around insertions and deletions
+//!
+//! Small ranges are diffed with Myers' algorithm. Large ranges are first split up by anchoring
+//! on blocks that appear in both, found with a rolling hash.
+
+use std::{collections::HashMap, ops::Range};
+
+/// Ranges up to this size (both sides combined) are diffed with Myers' algorithm
+const MYERS_LIMIT: usize = 8192;
+/// Give up on Myers' algorithm past this many edits, and fall back to anchoring
+const MYERS_MAX_EDITS: usize = 1024;
+/// Size of the blocks used as anchors
+const BLOCK: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HunkKind {
+    /// Bytes of `a` were replaced with different bytes in `b`
+    Changed,
+    /// Bytes only in `b`
+    Inserted,
+    /// Bytes only in `a`
+    Deleted,
+}
+
+/// A difference between `a` and `b`. The data between hunks is equal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    pub kind: HunkKind,
+    pub a: Range<usize>,
+    pub b: Range<usize>,
+}
+
+/// Equal data at `a` and `b`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Run {
+    a: usize,
+    b: usize,
+    len: usize,
+}
+
+/// Diff `a` against `b`, returning the differing hunks in order
+pub fn diff(a: &[u8], b: &[u8]) -> Vec<Hunk> {
+    let mut runs = Vec::new();
+    diff_runs(a, b, 0, 0, &mut runs);
+    hunks_from_runs(a.len(), b.len(), &runs)
+}
+
+/// Map an offset in `a` to the corresponding offset in `b`
+pub fn map_offset(hunks: &[Hunk], offset: usize) -> usize {
+    let idx = hunks.partition_point(|hunk| hunk.a.start <= offset);
+    let Some(hunk) = idx.checked_sub(1).map(|i| &hunks[i]) else {
+        return offset;
+    };
+    if offset < hunk.a.end {
+        (hunk.b.start + (offset - hunk.a.start)).min(hunk.b.end)
+    } else {
+        offset - hunk.a.end + hunk.b.end
+    }
+}
+
+fn hunks_from_runs(a_len: usize, b_len: usize, runs: &[Run]) -> Vec<Hunk> {
+    let mut hunks = Vec::new();
+    let (mut a, mut b) = (0, 0);
+    let end = Run {
+        a: a_len,
+        b: b_len,
+        len: 0,
+    };
+    for run in runs.iter().chain(std::iter::once(&end)) {
+        let kind = match (a < run.a, b < run.b) {
+            (true, true) => Some(HunkKind::Changed),
+            (false, true) => Some(HunkKind::Inserted),
+            (true, false) => Some(HunkKind::Deleted),
+            (false, false) => None,
+        };
+        if let Some(kind) = kind {
+            hunks.push(Hunk {
+                kind,
+                a: a..run.a,
+                b: b..run.b,
+            });
+        }
+        a = run.a + run.len;
+        b = run.b + run.len;
+    }
+    hunks
+}
+
+fn push_run(runs: &mut Vec<Run>, run: Run) {
+    if run.len == 0 {
+        return;
+    }
+    match runs.last_mut() {
+        Some(last) if last.a + last.len == run.a && last.b + last.len == run.b => {
+            last.len += run.len;
+        }
+        _ => runs.push(run),
+    }
+}
+
+/// Find the equal runs of `a` and `b`, which are at `a_off` and `b_off` of the whole data
+fn diff_runs(a: &[u8], b: &[u8], a_off: usize, b_off: usize, runs: &mut Vec<Run>) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    push_run(
+        runs,
+        Run {
+            a: a_off,
+            b: b_off,
+            len: prefix,
+        },
+    );
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let (a_off, b_off) = (a_off + prefix, b_off + prefix);
+    let suffix = a.iter().rev().zip(b.iter().rev()).take_while(|(x, y)| x == y).count();
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+    if !a.is_empty() && !b.is_empty() {
+        let myers_runs = (a.len() + b.len() <= MYERS_LIMIT)
+            .then(|| myers(a, b, MYERS_MAX_EDITS))
+            .flatten();
+        match myers_runs {
+            Some(found) => {
+                for run in found {
+                    push_run(
+                        runs,
+                        Run {
+                            a: a_off + run.a,
+                            b: b_off + run.b,
+                            len: run.len,
+                        },
+                    );
+                }
+            }
+            None => {
+                let anchors = anchors(a, b);
+                let (mut a_pos, mut b_pos) = (0, 0);
+                for &(anchor_a, anchor_b) in &anchors {
+                    diff_runs(
+                        &a[a_pos..anchor_a],
+                        &b[b_pos..anchor_b],
+                        a_off + a_pos,
+                        b_off + b_pos,
+                        runs,
+                    );
+                    push_run(
+                        runs,
+                        Run {
+                            a: a_off + anchor_a,
+                            b: b_off + anchor_b,
+                            len: BLOCK,
+                        },
+                    );
+                    (a_pos, b_pos) = (anchor_a + BLOCK, anchor_b + BLOCK);
+                }
+                // If there are no anchors either, the whole range is changed
+                if !anchors.is_empty() {
+                    diff_runs(&a[a_pos..], &b[b_pos..], a_off + a_pos, b_off + b_pos, runs);
+                }
+            }
+        }
+    }
+    push_run(
+        runs,
+        Run {
+            a: a_off + a.len(),
+            b: b_off + b.len(),
+            len: suffix,
+        },
+    );
+}
+
+/// Myers' O(ND) diff. Returns `None` if there are more than `max_edits` edits.
+fn myers(a: &[u8], b: &[u8], max_edits: usize) -> Option<Vec<Run>> {
+    let (n, m) = (a.len(), b.len());
+    // For each number of edits `d`, the furthest x reached on each diagonal k = x - y,
+    // for k in -d..=d, stored at index k + d
+    let mut trace: Vec<Vec<usize>> = Vec::new();
+    for d in 0..=max_edits.min(n + m) {
+        let mut v = vec![0; 2 * d + 1];
+        for i in (0..=2 * d).step_by(2) {
+            let mut x = match trace.last() {
+                None => 0,
+                Some(prev) => {
+                    if came_by_insertion(prev, i, d) {
+                        prev[i]
+                    } else {
+                        prev[i - 2] + 1
+                    }
+                }
+            };
+            // y = x - k
+            let mut y = (x + d).checked_sub(i)?;
+            while x < n && y < m && a[x] == b[y] {
+                x += 1;
+                y += 1;
+            }
+            v[i] = x;
+            if x >= n && y >= m {
+                trace.push(v);
+                return Some(backtrack(&trace, i));
+            }
+        }
+        trace.push(v);
+    }
+    None
+}
+
+/// Whether the furthest path to diagonal index `i` at `d` edits ends with an insertion
+/// (a step down from diagonal k + 1), rather than a deletion (a step right from k - 1)
+fn came_by_insertion(prev: &[usize], i: usize, d: usize) -> bool {
+    i == 0 || (i != 2 * d && prev[i - 2] < prev[i])
+}
+
+fn backtrack(trace: &[Vec<usize>], mut i: usize) -> Vec<Run> {
+    let mut runs = Vec::new();
+    let mut d = trace.len() - 1;
+    let mut x = trace[d][i];
+    loop {
+        let y = x + d - i;
+        let (start_x, prev) = if d == 0 {
+            (0, None)
+        } else {
+            let prev = &trace[d - 1];
+            let (prev_i, start_x) = if came_by_insertion(prev, i, d) {
+                (i, prev[i])
+            } else {
+                (i - 2, prev[i - 2] + 1)
+            };
+            (start_x, Some((prev_i, prev[prev_i])))
+        };
+        let len = x - start_x;
+        if len != 0 {
+            runs.push(Run {
+                a: start_x,
+                b: y - len,
+                len,
+            });
+        }
+        let Some((prev_i, prev_x)) = prev else {
+            break;
+        };
+        (i, x, d) = (prev_i, prev_x, d - 1);
+    }
+    runs.reverse();
+    runs
+}
+
+const HASH_BASE: u64 = 0x100_0000_01b3;
+
+fn block_hash(block: &[u8]) -> u64 {
+    block.iter().fold(0, |h: u64, &byte| {
+        h.wrapping_mul(HASH_BASE).wrapping_add(u64::from(byte))
+    })
+}
+
+/// Blocks of `a` that also appear in `b`, as `(a offset, b offset)` pairs, increasing in both
+fn anchors(a: &[u8], b: &[u8]) -> Vec<(usize, usize)> {
+    if a.len() < BLOCK || b.len() < BLOCK {
+        return Vec::new();
+    }
+    // Blocks of `a` by hash. Blocks that appear more than once are useless as anchors.
+    let mut index: HashMap<u64, Option<usize>> = HashMap::new();
+    for (i, block) in a.chunks_exact(BLOCK).enumerate() {
+        index
+            .entry(block_hash(block))
+            .and_modify(|pos| *pos = None)
+            .or_insert(Some(i * BLOCK));
+    }
+    let top = HASH_BASE.wrapping_pow(u32::try_from(BLOCK - 1).unwrap_or(0));
+    let mut matches = Vec::new();
+    let mut b_pos = 0;
+    let mut hash = block_hash(&b[..BLOCK]);
+    loop {
+        if let Some(&Some(a_pos)) = index.get(&hash)
+            && a[a_pos..a_pos + BLOCK] == b[b_pos..b_pos + BLOCK]
+        {
+            matches.push((a_pos, b_pos));
+            b_pos += BLOCK;
+            let Some(block) = b.get(b_pos..b_pos + BLOCK) else {
+                break;
+            };
+            hash = block_hash(block);
+            continue;
+        }
+        let Some(&next) = b.get(b_pos + BLOCK) else {
+            break;
+        };
+        hash = hash
+            .wrapping_sub(u64::from(b[b_pos]).wrapping_mul(top))
+            .wrapping_mul(HASH_BASE)
+            .wrapping_add(u64::from(next));
+        b_pos += 1;
+    }
+    longest_increasing_by_a(&matches)
+}
+
+/// The longest subsequence of `matches` (which is increasing in b) that is also increasing in a
+fn longest_increasing_by_a(matches: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    // Index of the match ending the best subsequence of each length, and predecessor links
+    let mut tails: Vec<usize> = Vec::new();
+    let mut prev = vec![None; matches.len()];
+    for (i, &(a, _)) in matches.iter().enumerate() {
+        let len = tails.partition_point(|&t| matches[t].0 < a);
+        if len > 0 {
+            prev[i] = Some(tails[len - 1]);
+        }
+        if len == tails.len() {
+            tails.push(i);
+        } else {
+            tails[len] = i;
+        }
+    }
+    let mut out = Vec::with_capacity(tails.len());
+    let mut cur = tails.last().copied();
+    while let Some(i) = cur {
+        out.push(matches[i]);
+        cur = prev[i];
+    }
+    out.reverse();
+    out
+}
+
+#[cfg(test)]
+fn apply_hunks(a: &[u8], b: &[u8], hunks: &[Hunk]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut a_pos = 0;
+    for hunk in hunks {
+        out.extend(&a[a_pos..hunk.a.start]);
+        out.extend(&b[hunk.b.clone()]);
+        a_pos = hunk.a.end;
+    }
+    out.extend(&a[a_pos..]);
+    out
+}
+
+#[test]
+fn test_diff() {
+    let hunks = diff(b"hello world", b"hello, world!");
+    assert_eq!(
+        hunks,
+        [
+            Hunk {
+                kind: HunkKind::Inserted,
+                a: 5..5,
+                b: 5..6
+            },
+            Hunk {
+                kind: HunkKind::Inserted,
+                a: 11..11,
+                b: 12..13
+            },
+        ]
+    );
+    assert_eq!(map_offset(&hunks, 6), 7);
+    let hunks = diff(b"abcXdef", b"abcYYdef");
+    assert_eq!(
+        hunks,
+        [Hunk {
+            kind: HunkKind::Changed,
+            a: 3..4,
+            b: 3..5
+        }]
+    );
+    assert_eq!(
+        diff(b"abcdef", b"abef"),
+        [Hunk {
+            kind: HunkKind::Deleted,
+            a: 2..4,
+            b: 2..2
+        }]
+    );
+    assert!(diff(b"same", b"same").is_empty());
+}
+
+#[test]
+fn test_diff_large() {
+    // Deterministic pseudo random data, too large for Myers' algorithm alone
+    let mut state = 0x1234_5678_u32;
+    let a: Vec<u8> = std::iter::repeat_with(|| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state.to_le_bytes()[0]
+    })
+    .take(100_000)
+    .collect();
+    let mut b = a.clone();
+    b.splice(10_000..10_000, [1, 2, 3]);
+    b.drain(50_000..50_100);
+    b[70_000] ^= 0xFF;
+    let hunks = diff(&a, &b);
+    assert_eq!(apply_hunks(&a, &b, &hunks), b);
+    assert_eq!(hunks.len(), 3);
+    assert_eq!(hunks[0].kind, HunkKind::Inserted);
+    assert_eq!(hunks[1].kind, HunkKind::Deleted);
+    assert_eq!(hunks[1].a.len(), 100);
+    assert_eq!(hunks[2].kind, HunkKind::Changed);
+    assert_eq!(map_offset(&hunks, 90_000), 90_000 + 3 - 100);
+}
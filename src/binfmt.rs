@@ -0,0 +1,458 @@
+//! Text encodings of binary data: Intel HEX, Motorola S-record, base64 and C arrays
+
+use {
+    crate::hex_conv::merge_hex_halves,
+    anyhow::{Context as _, anyhow, bail, ensure},
+    std::{fmt::Write as _, ops::Range, path::Path},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::EnumIter)]
+pub enum Format {
+    IntelHex,
+    SRecord,
+    Base64,
+    /// `const uint8_t data[] = {...};`. Export only.
+    CArray,
+}
+
+impl Format {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::IntelHex => "Intel HEX",
+            Self::SRecord => "Motorola S-record",
+            Self::Base64 => "Base64",
+            Self::CArray => "C array",
+        }
+    }
+    /// Guess the format of a file to import from its extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "hex" | "ihex" | "ihx" => Some(Self::IntelHex),
+            "srec" | "s19" | "s28" | "s37" | "mot" => Some(Self::SRecord),
+            "b64" | "base64" => Some(Self::Base64),
+            _ => None,
+        }
+    }
+}
+
+/// Bytes that aren't present in a sparse image are filled with this (erased flash)
+const GAP_FILL: u8 = 0xFF;
+/// Refuse to decode images spanning more than this (64 MiB), the gaps would all have to be
+/// allocated
+const MAX_SPAN: usize = 1 << 26;
+
+/// Decoded data, possibly with gaps
+pub struct Image {
+    /// Address of the first byte
+    pub base: usize,
+    pub data: Vec<u8>,
+    /// Sorted ranges of `data` that are present in the input. The rest is gaps.
+    pub segments: Vec<Range<usize>>,
+}
+
+pub fn decode(format: Format, text: &str) -> anyhow::Result<Image> {
+    match format {
+        Format::IntelHex => build_image(decode_ihex(text)?),
+        Format::SRecord => build_image(decode_srec(text)?),
+        Format::Base64 => {
+            let data = decode_base64(text)?;
+            Ok(Image {
+                base: 0,
+                segments: std::iter::once(0..data.len()).collect(),
+                data,
+            })
+        }
+        Format::CArray => bail!("C arrays can't be imported"),
+    }
+}
+
+/// Encode `data`, which starts at address `base`.
+///
+/// Only the `segments` of `data` are encoded for formats that support gaps.
+pub fn encode(
+    format: Format,
+    data: &[u8],
+    base: usize,
+    segments: &[Range<usize>],
+) -> anyhow::Result<String> {
+    match format {
+        Format::IntelHex => encode_ihex(data, base, segments),
+        Format::SRecord => encode_srec(data, base, segments),
+        Format::Base64 => Ok(encode_base64(data)),
+        Format::CArray => Ok(encode_c_array(data)),
+    }
+}
+
+/// The segments to encode `data` with, which was decoded into `segments`.
+///
+/// `moved` tells whether bytes were inserted or removed since then. That's only supported
+/// without gaps, since there's no telling which side of a gap the moved bytes belong to.
+/// Bytes edited inside gaps are an error too, as they wouldn't be encoded.
+pub fn fit_segments(
+    data: &[u8],
+    segments: &[Range<usize>],
+    moved: bool,
+) -> anyhow::Result<Vec<Range<usize>>> {
+    let end = segments.last().map_or(0, |seg| seg.end);
+    let gaps = gaps(segments, end);
+    if gaps.is_empty() {
+        return Ok(std::iter::once(0..data.len()).collect());
+    }
+    ensure!(
+        !moved,
+        "Bytes were inserted or removed in data with unmapped gaps. \
+         The addresses of the moved bytes can't be determined."
+    );
+    for gap in gaps {
+        let edited = data
+            .get(gap.clone())
+            .context("Data is shorter than its segments")?
+            .iter()
+            .position(|&b| b != GAP_FILL);
+        if let Some(pos) = edited {
+            bail!(
+                "Byte at offset {:#x} was edited, but it's in an unmapped gap, \
+                 which isn't written back",
+                gap.start + pos
+            );
+        }
+    }
+    Ok(segments.to_vec())
+}
+
+/// Ranges of `0..len` that aren't covered by `segments`
+pub fn gaps(segments: &[Range<usize>], len: usize) -> Vec<Range<usize>> {
+    let mut gaps = Vec::new();
+    let mut pos = 0;
+    for seg in segments {
+        if seg.start > pos {
+            gaps.push(pos..seg.start);
+        }
+        pos = pos.max(seg.end);
+    }
+    if pos < len {
+        gaps.push(pos..len);
+    }
+    gaps
+}
+
+/// Lay out `(address, bytes)` chunks into a contiguous buffer
+fn build_image(mut chunks: Vec<(usize, Vec<u8>)>) -> anyhow::Result<Image> {
+    chunks.sort_by_key(|(addr, _)| *addr);
+    let Some(base) = chunks.first().map(|(addr, _)| *addr) else {
+        bail!("No data records");
+    };
+    let end = chunks.iter().map(|(addr, bytes)| addr + bytes.len()).max().unwrap_or(base);
+    ensure!(
+        end - base <= MAX_SPAN,
+        "Addresses span {:#x} bytes, which is too large",
+        end - base
+    );
+    let mut data = vec![GAP_FILL; end - base];
+    let mut segments: Vec<Range<usize>> = Vec::new();
+    for (addr, bytes) in chunks {
+        let range = addr - base..addr - base + bytes.len();
+        data[range.clone()].copy_from_slice(&bytes);
+        match segments.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => segments.push(range),
+        }
+    }
+    Ok(Image {
+        base,
+        data,
+        segments,
+    })
+}
+
+/// Parse a string of hex digit pairs
+fn hex_bytes(hex: &str) -> anyhow::Result<Vec<u8>> {
+    ensure!(hex.len().is_multiple_of(2), "Odd number of hex digits");
+    hex.as_bytes()
+        .chunks_exact(2)
+        .map(|pair| merge_hex_halves(pair[0], pair[1]).context("Invalid hex digit"))
+        .collect()
+}
+
+fn be_addr(bytes: &[u8]) -> usize {
+    bytes.iter().fold(0, |acc, &b| (acc << 8) | usize::from(b))
+}
+
+fn decode_ihex(text: &str) -> anyhow::Result<Vec<(usize, Vec<u8>)>> {
+    let mut chunks = Vec::new();
+    // Upper bits of the address, set by extended address records
+    let mut upper = 0;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (ty, addr, payload) = ihex_record(line).map_err(|e| anyhow!("line {}: {e}", i + 1))?;
+        match ty {
+            0x00 => chunks.push((upper + addr, payload)),
+            0x01 => break,
+            0x02 => upper = be_addr(&payload) << 4,
+            0x04 => upper = be_addr(&payload) << 16,
+            // Start addresses don't affect the data
+            0x03 | 0x05 => {}
+            ty => bail!("line {}: Unknown record type {ty:02X}", i + 1),
+        }
+    }
+    Ok(chunks)
+}
+
+/// Parse an Intel HEX record into (type, address, payload)
+fn ihex_record(line: &str) -> anyhow::Result<(u8, usize, Vec<u8>)> {
+    let hex = line.strip_prefix(':').context("Record doesn't start with ':'")?;
+    let rec = hex_bytes(hex)?;
+    ensure!(rec.len() >= 5, "Record too short");
+    let len = usize::from(rec[0]);
+    ensure!(rec.len() == len + 5, "Record length mismatch");
+    ensure!(
+        rec.iter().fold(0u8, |acc, &b| acc.wrapping_add(b)) == 0,
+        "Checksum mismatch"
+    );
+    Ok((rec[3], be_addr(&rec[1..3]), rec[4..4 + len].to_vec()))
+}
+
+fn decode_srec(text: &str) -> anyhow::Result<Vec<(usize, Vec<u8>)>> {
+    let mut chunks = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(chunk) = srec_record(line).map_err(|e| anyhow!("line {}: {e}", i + 1))? {
+            chunks.push(chunk);
+        }
+    }
+    Ok(chunks)
+}
+
+/// Parse an S-record. Returns `None` for records that don't contain data.
+fn srec_record(line: &str) -> anyhow::Result<Option<(usize, Vec<u8>)>> {
+    let rest = line.strip_prefix(['S', 's']).context("Record doesn't start with 'S'")?;
+    let (ty, hex) = rest.split_at_checked(1).context("Record too short")?;
+    let rec = hex_bytes(hex)?;
+    ensure!(!rec.is_empty(), "Record too short");
+    ensure!(
+        rec.len() == usize::from(rec[0]) + 1,
+        "Record length mismatch"
+    );
+    ensure!(
+        rec.iter().fold(0u8, |acc, &b| acc.wrapping_add(b)) == 0xFF,
+        "Checksum mismatch"
+    );
+    let addr_len = match ty {
+        "1" => 2,
+        "2" => 3,
+        "3" => 4,
+        // Header, count and termination records
+        "0" | "5" | "6" | "7" | "8" | "9" => return Ok(None),
+        _ => bail!("Unknown record type S{ty}"),
+    };
+    ensure!(rec.len() >= 2 + addr_len, "Record too short");
+    let addr = be_addr(&rec[1..1 + addr_len]);
+    Ok(Some((addr, rec[1 + addr_len..rec.len() - 1].to_vec())))
+}
+
+/// Bytes per data record when encoding
+const RECORD_LEN: usize = 16;
+
+fn push_hex(out: &mut String, bytes: &[u8]) {
+    for b in bytes {
+        let _ = write!(out, "{b:02X}");
+    }
+}
+
+fn encode_ihex(data: &[u8], base: usize, segments: &[Range<usize>]) -> anyhow::Result<String> {
+    let mut out = String::new();
+    let mut upper = 0;
+    let push_record = |out: &mut String, ty: u8, addr: u16, payload: &[u8]| {
+        let len = u8::try_from(payload.len()).unwrap_or(u8::MAX);
+        let [hi, lo] = addr.to_be_bytes();
+        let mut rec = vec![len, hi, lo, ty];
+        rec.extend_from_slice(payload);
+        rec.push(rec.iter().fold(0u8, |acc, &b| acc.wrapping_sub(b)));
+        out.push(':');
+        push_hex(out, &rec);
+        out.push('\n');
+    };
+    for seg in segments {
+        let mut pos = seg.start;
+        while pos < seg.end {
+            let addr = u32::try_from(base + pos).context("Address doesn't fit in 32 bits")?;
+            let [a, b, c, d] = addr.to_be_bytes();
+            if u32::from(u16::from_be_bytes([a, b])) != upper {
+                upper = u32::from(u16::from_be_bytes([a, b]));
+                push_record(&mut out, 0x04, 0, &[a, b]);
+            }
+            // Records can't cross a 64 KiB boundary
+            let to_boundary = 0x1_0000 - usize::from(u16::from_be_bytes([c, d]));
+            let len = RECORD_LEN.min(seg.end - pos).min(to_boundary);
+            push_record(
+                &mut out,
+                0x00,
+                u16::from_be_bytes([c, d]),
+                &data[pos..pos + len],
+            );
+            pos += len;
+        }
+    }
+    push_record(&mut out, 0x01, 0, &[]);
+    Ok(out)
+}
+
+fn encode_srec(data: &[u8], base: usize, segments: &[Range<usize>]) -> anyhow::Result<String> {
+    let max_addr = segments.last().map_or(base, |seg| base + seg.end.saturating_sub(1));
+    let max_addr = u32::try_from(max_addr).context("Address doesn't fit in 32 bits")?;
+    // Use the smallest address size that fits
+    let (data_ty, term_ty, addr_len) = match max_addr {
+        0..=0xFFFF => ('1', '9', 2),
+        0x1_0000..=0xFF_FFFF => ('2', '8', 3),
+        _ => ('3', '7', 4),
+    };
+    let push_record = |out: &mut String, ty: char, addr: u32, payload: &[u8]| {
+        let mut rec = vec![u8::try_from(addr_len + payload.len() + 1).unwrap_or(u8::MAX)];
+        rec.extend_from_slice(&addr.to_be_bytes()[4 - addr_len..]);
+        rec.extend_from_slice(payload);
+        rec.push(!rec.iter().fold(0u8, |acc, &b| acc.wrapping_add(b)));
+        out.push('S');
+        out.push(ty);
+        push_hex(out, &rec);
+        out.push('\n');
+    };
+    let mut out = String::new();
+    for seg in segments {
+        for (i, chunk) in data[seg.clone()].chunks(RECORD_LEN).enumerate() {
+            let addr = u32::try_from(base + seg.start + i * RECORD_LEN)
+                .context("Address doesn't fit in 32 bits")?;
+            push_record(&mut out, data_ty, addr, chunk);
+        }
+    }
+    push_record(&mut out, term_ty, 0, &[]);
+    Ok(out)
+}
+
+const BASE64_TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn encode_base64(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0usize, |acc, (i, &b)| acc | usize::from(b) << (16 - i * 8));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(char::from(BASE64_TABLE[(n >> (18 - i * 6)) & 0x3F]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn decode_base64(text: &str) -> anyhow::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let mut acc = 0usize;
+    let mut n_bits = 0;
+    for c in text.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        if c == b'=' {
+            break;
+        }
+        let value = BASE64_TABLE
+            .iter()
+            .position(|&t| t == c)
+            .with_context(|| format!("Invalid base64 character '{}'", char::from(c)))?;
+        acc = (acc << 6) | value;
+        n_bits += 6;
+        if n_bits >= 8 {
+            n_bits -= 8;
+            out.push((acc >> n_bits).to_le_bytes()[0]);
+            acc &= (1 << n_bits) - 1;
+        }
+    }
+    Ok(out)
+}
+
+fn encode_c_array(data: &[u8]) -> String {
+    let mut out = format!("const uint8_t data[{}] = {{\n", data.len());
+    for line in data.chunks(12) {
+        out.push_str("   ");
+        for b in line {
+            let _ = write!(out, " 0x{b:02x},");
+        }
+        out.push('\n');
+    }
+    out.push_str("};\n");
+    out
+}
+
+#[test]
+fn test_ihex_roundtrip() {
+    let data: Vec<u8> = (0..=255).collect();
+    // Gap in the middle, and crossing a 64 KiB boundary
+    let segments = [0..40, 100..256];
+    let text = encode(Format::IntelHex, &data, 0xFFF0, &segments).unwrap();
+    let img = decode(Format::IntelHex, &text).unwrap();
+    assert_eq!(img.base, 0xFFF0);
+    assert_eq!(img.segments, segments);
+    assert_eq!(img.data[..40], data[..40]);
+    assert!(img.data[40..100].iter().all(|&b| b == GAP_FILL));
+    assert_eq!(img.data[100..], data[100..]);
+}
+
+#[test]
+fn test_ihex_decode() {
+    let text = ":0300300002337A1E\n:00000001FF\n";
+    let img = decode(Format::IntelHex, text).unwrap();
+    assert_eq!(img.base, 0x30);
+    assert_eq!(img.data, [0x02, 0x33, 0x7A]);
+    assert!(decode(Format::IntelHex, ":0300300002337A1F\n").is_err());
+}
+
+#[test]
+fn test_srec_roundtrip() {
+    let data: Vec<u8> = (0..100).collect();
+    let segments = [0..10, 50..100];
+    for base in [0, 0x12_0000, 0x0800_0000] {
+        let text = encode(Format::SRecord, &data, base, &segments).unwrap();
+        let img = decode(Format::SRecord, &text).unwrap();
+        assert_eq!(img.base, base);
+        assert_eq!(img.segments, segments);
+        assert_eq!(img.data[50..], data[50..]);
+    }
+}
+
+#[test]
+fn test_base64() {
+    for (raw, enc) in [
+        (&b""[..], ""),
+        (b"f", "Zg=="),
+        (b"fo", "Zm8="),
+        (b"foo", "Zm9v"),
+        (b"foobar", "Zm9vYmFy"),
+    ] {
+        assert_eq!(encode_base64(raw), enc);
+        assert_eq!(decode_base64(enc).unwrap(), raw);
+    }
+}
+
+#[test]
+fn test_fit_segments() {
+    let mut data = [0; 12];
+    data[4..8].fill(GAP_FILL);
+    let segments = [0..4, 8..12];
+    assert_eq!(fit_segments(&data, &segments, false).unwrap(), segments);
+    assert!(fit_segments(&data, &segments, true).is_err());
+    data[5] = 0;
+    assert!(fit_segments(&data, &segments, false).is_err());
+    // Without gaps, the data can grow and shrink freely
+    let fitted = fit_segments(&data, &[0..3, 3..6], true).unwrap();
+    assert_eq!(fitted.len(), 1);
+    assert_eq!(fitted[0], 0..12);
+    assert_eq!(gaps(&[0..4, 8..12], 16), [4..8, 12..16]);
+}
@@ -0,0 +1,457 @@
+//! Finding files embedded in the data by their magic numbers
+
+use std::io::Read as _;
+
+/// A known file format
+pub struct Signature {
+    pub name: &'static str,
+    /// File extension used when extracting
+    pub ext: &'static str,
+    magic: &'static [u8],
+    /// Determine the length of the file, given the data starting at the magic
+    len: fn(&[u8]) -> Option<usize>,
+    /// The magic is short or common enough that it's only a hit if the length can be
+    /// determined, which involves validating the headers
+    weak: bool,
+}
+
+/// A file found in the data
+pub struct Carved {
+    pub offset: usize,
+    pub sig: &'static Signature,
+    /// `None` if the length couldn't be determined, e.g. because the file is truncated
+    pub len: Option<usize>,
+}
+
+pub const SIGNATURES: &[Signature] = &[
+    Signature {
+        name: "PNG",
+        ext: "png",
+        magic: b"\x89PNG\r\n\x1a\n",
+        len: png_len,
+        weak: false,
+    },
+    Signature {
+        name: "JPEG",
+        ext: "jpg",
+        magic: b"\xff\xd8\xff",
+        len: jpeg_len,
+        weak: false,
+    },
+    Signature {
+        name: "GIF",
+        ext: "gif",
+        magic: b"GIF8",
+        len: gif_len,
+        weak: false,
+    },
+    Signature {
+        name: "BMP",
+        ext: "bmp",
+        magic: b"BM",
+        len: bmp_len,
+        weak: true,
+    },
+    Signature {
+        name: "RIFF (WAV, AVI, WebP)",
+        ext: "riff",
+        magic: b"RIFF",
+        len: riff_len,
+        weak: false,
+    },
+    Signature {
+        name: "ZIP",
+        ext: "zip",
+        magic: b"PK\x03\x04",
+        len: zip_len,
+        weak: false,
+    },
+    Signature {
+        name: "7-Zip",
+        ext: "7z",
+        magic: b"7z\xbc\xaf\x27\x1c",
+        len: sevenzip_len,
+        weak: false,
+    },
+    Signature {
+        name: "gzip",
+        ext: "gz",
+        magic: b"\x1f\x8b\x08",
+        len: gzip_len,
+        weak: false,
+    },
+    Signature {
+        name: "zlib",
+        ext: "zlib",
+        magic: b"\x78",
+        len: zlib_len,
+        weak: true,
+    },
+    Signature {
+        name: "ELF",
+        ext: "elf",
+        magic: b"\x7fELF",
+        len: elf_len,
+        weak: false,
+    },
+    Signature {
+        name: "PE (exe, dll)",
+        ext: "exe",
+        magic: b"MZ",
+        len: pe_len,
+        weak: true,
+    },
+    Signature {
+        name: "SQLite",
+        ext: "sqlite",
+        magic: b"SQLite format 3\0",
+        len: sqlite_len,
+        weak: false,
+    },
+    Signature {
+        name: "PDF",
+        ext: "pdf",
+        magic: b"%PDF-",
+        len: pdf_len,
+        weak: false,
+    },
+];
+
+/// Scan `data` for embedded files.
+///
+/// If `skip_contents` is set, the contents of files with a known length aren't scanned,
+/// which avoids reporting e.g. every entry of a ZIP archive.
+pub fn scan(data: &[u8], skip_contents: bool) -> Vec<Carved> {
+    let mut first_bytes = [false; 256];
+    for sig in SIGNATURES {
+        first_bytes[usize::from(sig.magic[0])] = true;
+    }
+    let mut hits = Vec::new();
+    let mut offset = 0;
+    while let Some(&byte) = data.get(offset) {
+        if !first_bytes[usize::from(byte)] {
+            offset += 1;
+            continue;
+        }
+        let rest = &data[offset..];
+        let hit = SIGNATURES.iter().find_map(|sig| {
+            if !rest.starts_with(sig.magic) {
+                return None;
+            }
+            let len = (sig.len)(rest).filter(|&len| len != 0 && len <= rest.len());
+            if sig.weak && len.is_none() {
+                return None;
+            }
+            Some(Carved { offset, sig, len })
+        });
+        match hit {
+            Some(hit) => {
+                offset += match hit.len {
+                    Some(len) if skip_contents => len,
+                    _ => 1,
+                };
+                hits.push(hit);
+            }
+            None => offset += 1,
+        }
+    }
+    hits
+}
+
+fn bytes<const N: usize>(d: &[u8], pos: usize) -> Option<[u8; N]> {
+    d.get(pos..pos.checked_add(N)?)?.try_into().ok()
+}
+
+fn u16_le(d: &[u8], pos: usize) -> Option<usize> {
+    bytes(d, pos).map(|b| usize::from(u16::from_le_bytes(b)))
+}
+
+fn u16_be(d: &[u8], pos: usize) -> Option<usize> {
+    bytes(d, pos).map(|b| usize::from(u16::from_be_bytes(b)))
+}
+
+fn u32_le(d: &[u8], pos: usize) -> Option<usize> {
+    usize::try_from(u32::from_le_bytes(bytes(d, pos)?)).ok()
+}
+
+fn u32_be(d: &[u8], pos: usize) -> Option<usize> {
+    usize::try_from(u32::from_be_bytes(bytes(d, pos)?)).ok()
+}
+
+fn u64_le(d: &[u8], pos: usize) -> Option<usize> {
+    usize::try_from(u64::from_le_bytes(bytes(d, pos)?)).ok()
+}
+
+fn u64_be(d: &[u8], pos: usize) -> Option<usize> {
+    usize::try_from(u64::from_be_bytes(bytes(d, pos)?)).ok()
+}
+
+/// Walk the chunks up to `IEND`
+fn png_len(d: &[u8]) -> Option<usize> {
+    let mut pos = 8;
+    if d.get(pos + 4..pos + 8)? != b"IHDR" {
+        return None;
+    }
+    loop {
+        let len = u32_be(d, pos)?;
+        let ty = d.get(pos + 4..pos + 8)?;
+        // Length, type, data, CRC
+        pos = pos.checked_add(len)?.checked_add(12)?;
+        if ty == b"IEND" {
+            return Some(pos);
+        }
+    }
+}
+
+/// Walk the segments, and the entropy coded data after start of scan markers, up to the
+/// end of image marker
+fn jpeg_len(d: &[u8]) -> Option<usize> {
+    let mut pos = 2;
+    loop {
+        if *d.get(pos)? != 0xFF {
+            return None;
+        }
+        let marker = *d.get(pos + 1)?;
+        match marker {
+            0xD9 => return Some(pos + 2),
+            // Fill byte
+            0xFF => {
+                pos += 1;
+                continue;
+            }
+            // Markers without a length
+            0x01 | 0xD0..=0xD7 => {
+                pos += 2;
+                continue;
+            }
+            _ => {}
+        }
+        pos = pos.checked_add(2 + u16_be(d, pos + 2)?)?;
+        if marker == 0xDA {
+            loop {
+                pos += memchr::memchr(0xFF, d.get(pos..)?)?;
+                match *d.get(pos + 1)? {
+                    // Stuffed zero byte and restart markers are part of the data
+                    0x00 | 0xD0..=0xD7 => pos += 2,
+                    0xFF => pos += 1,
+                    _ => break,
+                }
+            }
+        }
+    }
+}
+
+/// Walk the blocks up to the trailer
+fn gif_len(d: &[u8]) -> Option<usize> {
+    if !matches!(d.get(4..6)?, b"7a" | b"9a") {
+        return None;
+    }
+    let color_table_len = |flags: u8| {
+        if flags & 0x80 != 0 {
+            3 << ((flags & 0x07) + 1)
+        } else {
+            0
+        }
+    };
+    let skip_sub_blocks = |mut pos: usize| -> Option<usize> {
+        loop {
+            let size = usize::from(*d.get(pos)?);
+            pos += 1 + size;
+            if size == 0 {
+                return Some(pos);
+            }
+        }
+    };
+    // Header and logical screen descriptor
+    let mut pos = 13 + color_table_len(*d.get(10)?);
+    loop {
+        match *d.get(pos)? {
+            // Extension
+            0x21 => pos = skip_sub_blocks(pos + 2)?,
+            // Image descriptor, then the minimum LZW code size
+            0x2C => pos = skip_sub_blocks(pos + 10 + color_table_len(*d.get(pos + 9)?) + 1)?,
+            0x3B => return Some(pos + 1),
+            _ => return None,
+        }
+    }
+}
+
+fn bmp_len(d: &[u8]) -> Option<usize> {
+    let len = u32_le(d, 2)?;
+    let reserved = u32_le(d, 6)?;
+    let pixels_offset = u32_le(d, 10)?;
+    let dib_header_size = u32_le(d, 14)?;
+    (reserved == 0
+        && pixels_offset < len
+        && matches!(dib_header_size, 12 | 40 | 52 | 56 | 108 | 124))
+    .then_some(len)
+}
+
+fn riff_len(d: &[u8]) -> Option<usize> {
+    u32_le(d, 4)?.checked_add(8)
+}
+
+/// Up to the end of the central directory record
+fn zip_len(d: &[u8]) -> Option<usize> {
+    let eocd = memchr::memmem::find(d, b"PK\x05\x06")?;
+    let comment_len = u16_le(d, eocd + 20)?;
+    Some(eocd + 22 + comment_len)
+}
+
+fn sevenzip_len(d: &[u8]) -> Option<usize> {
+    let next_header_offset = u64_le(d, 12)?;
+    let next_header_size = u64_le(d, 20)?;
+    32usize.checked_add(next_header_offset)?.checked_add(next_header_size)
+}
+
+/// Upper limit of decompressed size when finding the length of compressed streams
+const MAX_INFLATE: u64 = 1 << 30;
+
+/// Number of bytes `reader` consumed from `d` to decompress everything
+fn inflated_len<R: std::io::Read>(
+    d: &[u8],
+    mut reader: R,
+    remaining: impl Fn(R) -> usize,
+) -> Option<usize> {
+    let n = std::io::copy(&mut (&mut reader).take(MAX_INFLATE), &mut std::io::sink()).ok()?;
+    (n < MAX_INFLATE).then(|| d.len() - remaining(reader))
+}
+
+fn gzip_len(d: &[u8]) -> Option<usize> {
+    inflated_len(d, flate2::bufread::GzDecoder::new(d), |dec| {
+        dec.into_inner().len()
+    })
+}
+
+fn zlib_len(d: &[u8]) -> Option<usize> {
+    let (&cmf, &flg) = (d.first()?, d.get(1)?);
+    // Deflate with a 32K window, no preset dictionary, and a valid header checksum
+    if cmf != 0x78 || flg & 0x20 != 0 || (u16::from(cmf) << 8 | u16::from(flg)) % 31 != 0 {
+        return None;
+    }
+    inflated_len(d, flate2::bufread::ZlibDecoder::new(d), |dec| {
+        dec.into_inner().len()
+    })
+}
+
+/// The furthest end of the program headers, section headers, and the segments they describe
+fn elf_len(d: &[u8]) -> Option<usize> {
+    let big_endian = match d.get(5)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let is_64 = match d.get(4)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let u16_at = |pos| {
+        if big_endian {
+            u16_be(d, pos)
+        } else {
+            u16_le(d, pos)
+        }
+    };
+    let word = |pos| match (is_64, big_endian) {
+        (false, false) => u32_le(d, pos),
+        (false, true) => u32_be(d, pos),
+        (true, false) => u64_le(d, pos),
+        (true, true) => u64_be(d, pos),
+    };
+    // Offsets of the header fields, and of the program header fields
+    let (ph_off, sh_off, ph_ent, ph_num, sh_ent, sh_num, p_offset, p_filesz) = if is_64 {
+        (32, 40, 54, 56, 58, 60, 8, 32)
+    } else {
+        (28, 32, 42, 44, 46, 48, 4, 16)
+    };
+    let mut end = word(sh_off)?.checked_add(u16_at(sh_ent)?.checked_mul(u16_at(sh_num)?)?)?;
+    let phoff = word(ph_off)?;
+    let ph_ent = u16_at(ph_ent)?;
+    for i in 0..u16_at(ph_num)? {
+        let ph = phoff.checked_add(i.checked_mul(ph_ent)?)?;
+        end = end.max(ph.checked_add(ph_ent)?);
+        end = end.max(word(ph + p_offset)?.checked_add(word(ph + p_filesz)?)?);
+    }
+    Some(end)
+}
+
+/// The furthest end of the sections' raw data
+fn pe_len(d: &[u8]) -> Option<usize> {
+    let pe = u32_le(d, 0x3C)?;
+    if d.get(pe..pe.checked_add(4)?)? != b"PE\0\0" {
+        return None;
+    }
+    let coff = pe + 4;
+    let n_sections = u16_le(d, coff + 2)?;
+    let opt_header = coff + 20;
+    let size_of_headers = u32_le(d, opt_header + 60)?;
+    let sections = opt_header + u16_le(d, coff + 16)?;
+    let mut end = size_of_headers;
+    for i in 0..n_sections {
+        let sect = sections + i * 40;
+        let size = u32_le(d, sect + 16)?;
+        let ptr = u32_le(d, sect + 20)?;
+        end = end.max(ptr.checked_add(size)?);
+    }
+    Some(end)
+}
+
+fn sqlite_len(d: &[u8]) -> Option<usize> {
+    let page_size = match u16_be(d, 16)? {
+        1 => 65536,
+        n => n,
+    };
+    page_size.checked_mul(u32_be(d, 28)?)
+}
+
+/// Up to the first end of file marker. Incrementally updated PDFs have more than one.
+fn pdf_len(d: &[u8]) -> Option<usize> {
+    let end = memchr::memmem::find(d, b"%%EOF")? + 5;
+    let eol = match d.get(end..end + 2) {
+        Some(b"\r\n") => 2,
+        _ => usize::from(matches!(d.get(end), Some(b'\n' | b'\r'))),
+    };
+    Some(end + eol)
+}
+
+#[test]
+fn test_carve() {
+    use std::io::Write as _;
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    for (ty, data) in [
+        (b"IHDR", &[0u8; 13][..]),
+        (b"IDAT", &[1, 2, 3]),
+        (b"IEND", &[]),
+    ] {
+        png.extend(u32::try_from(data.len()).unwrap().to_be_bytes());
+        png.extend(ty);
+        png.extend(data);
+        png.extend([0; 4]);
+    }
+    let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    gz.write_all(&[42; 1000]).unwrap();
+    let gz = gz.finish().unwrap();
+    let jpeg = b"\xff\xd8\xff\xe0\x00\x04ab\xff\xda\x00\x02\x12\xff\x00\x34\xff\xd0\x56\xff\xd9";
+    let mut data = vec![0; 10];
+    data.extend(&png);
+    data.extend(b"MZ garbage");
+    data.extend(&gz);
+    data.extend(jpeg);
+    // Truncated
+    data.extend(b"PK\x03\x04");
+    let hits: Vec<_> = scan(&data, true)
+        .iter()
+        .map(|hit| (hit.offset, hit.sig.name, hit.len))
+        .collect();
+    let gz_at = 10 + png.len() + 10;
+    assert_eq!(
+        hits,
+        [
+            (10, "PNG", Some(png.len())),
+            (gz_at, "gzip", Some(gz.len())),
+            (gz_at + gz.len(), "JPEG", Some(jpeg.len())),
+            (gz_at + gz.len() + jpeg.len(), "ZIP", None),
+        ]
+    );
+}
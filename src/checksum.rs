@@ -0,0 +1,175 @@
+//! Checksums and hashes of byte ranges
+
+use {
+    crate::struct_meta_item::Endian,
+    crc::{
+        CRC_8_SMBUS, CRC_16_ARC, CRC_16_IBM_3740, CRC_16_KERMIT, CRC_16_MODBUS, CRC_16_XMODEM,
+        CRC_32_ISCSI, CRC_32_ISO_HDLC, Crc,
+    },
+    md5::Digest as _,
+    serde::{Deserialize, Serialize},
+    std::fmt::Write as _,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::EnumIter, Serialize, Deserialize)]
+pub enum HashAlgo {
+    /// Sum of the bytes, truncated to 8 bits
+    Sum8,
+    /// Sum of the bytes, truncated to 16 bits
+    Sum16,
+    /// Sum of the bytes, truncated to 32 bits
+    Sum32,
+    /// All the bytes xored together
+    Xor8,
+    Adler32,
+    Crc8,
+    Crc16Arc,
+    Crc16Modbus,
+    /// Also known as CRC-16/CCITT-FALSE
+    Crc16Ibm3740,
+    Crc16Xmodem,
+    Crc16Kermit,
+    Crc32,
+    /// Castagnoli
+    Crc32c,
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl HashAlgo {
+    /// Short name, also used to refer to the algorithm from Lua
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sum8 => "sum8",
+            Self::Sum16 => "sum16",
+            Self::Sum32 => "sum32",
+            Self::Xor8 => "xor8",
+            Self::Adler32 => "adler32",
+            Self::Crc8 => "crc8",
+            Self::Crc16Arc => "crc16",
+            Self::Crc16Modbus => "crc16-modbus",
+            Self::Crc16Ibm3740 => "crc16-ccitt-false",
+            Self::Crc16Xmodem => "crc16-xmodem",
+            Self::Crc16Kermit => "crc16-kermit",
+            Self::Crc32 => "crc32",
+            Self::Crc32c => "crc32c",
+            Self::Md5 => "md5",
+            Self::Sha1 => "sha1",
+            Self::Sha256 => "sha256",
+        }
+    }
+    /// Whether the result is a byte string rather than a number
+    pub fn is_digest(self) -> bool {
+        matches!(self, Self::Md5 | Self::Sha1 | Self::Sha256)
+    }
+    /// Compute the checksum, with numeric checksums in the given byte order
+    pub fn compute_endian(self, data: &[u8], endian: Endian) -> Vec<u8> {
+        let mut bytes = self.compute(data);
+        if matches!(endian, Endian::Le) && !self.is_digest() {
+            bytes.reverse();
+        }
+        bytes
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        <Self as strum::IntoEnumIterator>::iter().find(|algo| algo.name() == name)
+    }
+    /// Compute the checksum of `data`.
+    ///
+    /// Numeric checksums are returned as big endian bytes of their natural width.
+    pub fn compute(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sum8 => vec![data.iter().fold(0u8, |acc, &b| acc.wrapping_add(b))],
+            Self::Sum16 => data
+                .iter()
+                .fold(0u16, |acc, &b| acc.wrapping_add(u16::from(b)))
+                .to_be_bytes()
+                .to_vec(),
+            Self::Sum32 => data
+                .iter()
+                .fold(0u32, |acc, &b| acc.wrapping_add(u32::from(b)))
+                .to_be_bytes()
+                .to_vec(),
+            Self::Xor8 => vec![data.iter().fold(0u8, |acc, &b| acc ^ b)],
+            Self::Adler32 => adler32(data).to_be_bytes().to_vec(),
+            Self::Crc8 => vec![Crc::<u8>::new(&CRC_8_SMBUS).checksum(data)],
+            Self::Crc16Arc => crc16(&CRC_16_ARC, data),
+            Self::Crc16Modbus => crc16(&CRC_16_MODBUS, data),
+            Self::Crc16Ibm3740 => crc16(&CRC_16_IBM_3740, data),
+            Self::Crc16Xmodem => crc16(&CRC_16_XMODEM, data),
+            Self::Crc16Kermit => crc16(&CRC_16_KERMIT, data),
+            Self::Crc32 => crc32(&CRC_32_ISO_HDLC, data),
+            Self::Crc32c => crc32(&CRC_32_ISCSI, data),
+            Self::Md5 => md5::Md5::digest(data).to_vec(),
+            Self::Sha1 => sha1::Sha1::digest(data).to_vec(),
+            Self::Sha256 => sha2::Sha256::digest(data).to_vec(),
+        }
+    }
+}
+
+fn crc16(algo: &'static crc::Algorithm<u16>, data: &[u8]) -> Vec<u8> {
+    Crc::<u16>::new(algo).checksum(data).to_be_bytes().to_vec()
+}
+
+fn crc32(algo: &'static crc::Algorithm<u32>, data: &[u8]) -> Vec<u8> {
+    Crc::<u32>::new(algo).checksum(data).to_be_bytes().to_vec()
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // The sums can't overflow within this many bytes before taking the modulo
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += u32::from(byte);
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+/// Lowercase hex representation of the bytes
+pub fn to_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        let _ = write!(out, "{b:02x}");
+    }
+    out
+}
+
+#[test]
+fn test_checksums() {
+    let hex = |algo: HashAlgo| to_hex(&algo.compute(b"123456789"));
+    // Check values from the CRC catalogue
+    assert_eq!(hex(HashAlgo::Crc8), "f4");
+    assert_eq!(hex(HashAlgo::Crc16Arc), "bb3d");
+    assert_eq!(hex(HashAlgo::Crc16Modbus), "4b37");
+    assert_eq!(hex(HashAlgo::Crc16Ibm3740), "29b1");
+    assert_eq!(hex(HashAlgo::Crc16Xmodem), "31c3");
+    assert_eq!(hex(HashAlgo::Crc16Kermit), "2189");
+    assert_eq!(hex(HashAlgo::Crc32), "cbf43926");
+    assert_eq!(hex(HashAlgo::Crc32c), "e3069283");
+    assert_eq!(hex(HashAlgo::Adler32), "091e01de");
+    assert_eq!(hex(HashAlgo::Sum8), "dd");
+    assert_eq!(hex(HashAlgo::Xor8), "31");
+    assert_eq!(
+        to_hex(&HashAlgo::Md5.compute(b"")),
+        "d41d8cd98f00b204e9800998ecf8427e"
+    );
+    assert_eq!(
+        to_hex(&HashAlgo::Sha1.compute(b"abc")),
+        "a9993e364706816aba3e25717850c26c9cd0d89d"
+    );
+    assert_eq!(HashAlgo::from_name("crc32"), Some(HashAlgo::Crc32));
+    assert_eq!(
+        to_hex(&HashAlgo::Crc32.compute_endian(b"123456789", Endian::Le)),
+        "2639f4cb"
+    );
+    // Digests aren't numbers, so they don't have a byte order
+    assert_eq!(
+        HashAlgo::Md5.compute_endian(b"", Endian::Le),
+        HashAlgo::Md5.compute(b"")
+    );
+}
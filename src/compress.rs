@@ -0,0 +1,157 @@
+//! Decompressing and recompressing compressed blobs
+
+use {
+    anyhow::Context as _,
+    std::io::{Read as _, Write as _},
+};
+
+/// Upper limit of decompressed size, to avoid running out of memory on decompression bombs
+const MAX_OUTPUT: usize = 1 << 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::EnumIter)]
+pub enum Codec {
+    Zlib,
+    /// Raw deflate, without a header
+    Deflate,
+    Gzip,
+    /// LZ4 block without a frame, like many games use
+    Lz4Block,
+    Lz4Frame,
+}
+
+impl Codec {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Zlib => "zlib",
+            Self::Deflate => "deflate",
+            Self::Gzip => "gzip",
+            Self::Lz4Block => "LZ4 block",
+            Self::Lz4Frame => "LZ4 frame",
+        }
+    }
+    /// Guess the codec from the header of `data`. Raw deflate and LZ4 blocks have no header.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        match data {
+            [0x1f, 0x8b, ..] => Some(Self::Gzip),
+            [0x04, 0x22, 0x4d, 0x18, ..] => Some(Self::Lz4Frame),
+            &[cmf, flg, ..]
+                if cmf & 0x0F == 8 && (u16::from(cmf) << 8 | u16::from(flg)) % 31 == 0 =>
+            {
+                Some(Self::Zlib)
+            }
+            _ => None,
+        }
+    }
+    /// Whether the end of the compressed stream can be told from the stream itself.
+    ///
+    /// Otherwise the stream is assumed to take up all of the input.
+    pub fn is_self_delimiting(self) -> bool {
+        matches!(self, Self::Zlib | Self::Deflate | Self::Gzip)
+    }
+    /// Decompress `data`. Trailing data after the compressed stream is ignored.
+    ///
+    /// Returns the decompressed data, and the length of the compressed stream.
+    pub fn decompress(self, data: &[u8]) -> anyhow::Result<(Vec<u8>, usize)> {
+        // The buffered decoders only consume the input up to the end of the stream
+        let mut rest = data;
+        let out = match self {
+            Self::Zlib => read_limited(flate2::bufread::ZlibDecoder::new(&mut rest))?,
+            Self::Deflate => read_limited(flate2::bufread::DeflateDecoder::new(&mut rest))?,
+            Self::Gzip => read_limited(flate2::bufread::GzDecoder::new(&mut rest))?,
+            Self::Lz4Block => {
+                rest = &[];
+                lz4_block_decompress(data)?
+            }
+            Self::Lz4Frame => read_limited(lz4_flex::frame::FrameDecoder::new(&mut rest))?,
+        };
+        Ok((out, data.len() - rest.len()))
+    }
+    pub fn compress(self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let level = flate2::Compression::best();
+        let out = match self {
+            Self::Zlib => {
+                let mut enc = flate2::write::ZlibEncoder::new(Vec::new(), level);
+                enc.write_all(data)?;
+                enc.finish()?
+            }
+            Self::Deflate => {
+                let mut enc = flate2::write::DeflateEncoder::new(Vec::new(), level);
+                enc.write_all(data)?;
+                enc.finish()?
+            }
+            Self::Gzip => {
+                let mut enc = flate2::write::GzEncoder::new(Vec::new(), level);
+                enc.write_all(data)?;
+                enc.finish()?
+            }
+            Self::Lz4Block => lz4_flex::block::compress(data),
+            Self::Lz4Frame => {
+                let mut enc = lz4_flex::frame::FrameEncoder::new(Vec::new());
+                enc.write_all(data)?;
+                enc.finish()?
+            }
+        };
+        Ok(out)
+    }
+}
+
+fn read_limited(reader: impl std::io::Read) -> anyhow::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    reader.take(MAX_OUTPUT as u64 + 1).read_to_end(&mut out)?;
+    anyhow::ensure!(
+        out.len() <= MAX_OUTPUT,
+        "Decompressed size is over the limit of {MAX_OUTPUT} bytes"
+    );
+    Ok(out)
+}
+
+/// LZ4 blocks don't store the decompressed size, so keep growing the output until it fits
+fn lz4_block_decompress(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut out = vec![0; data.len().saturating_mul(4).clamp(4096, MAX_OUTPUT)];
+    loop {
+        match lz4_flex::block::decompress_into(data, &mut out) {
+            Ok(len) => {
+                out.truncate(len);
+                return Ok(out);
+            }
+            Err(lz4_flex::block::DecompressError::OutputTooSmall { .. })
+                if out.len() < MAX_OUTPUT =>
+            {
+                out.resize(out.len().saturating_mul(2).min(MAX_OUTPUT), 0);
+            }
+            Err(e) => return Err(e).context("LZ4 block decompression failed"),
+        }
+    }
+}
+
+#[test]
+fn test_codec_roundtrip() {
+    use strum::IntoEnumIterator as _;
+    let data: Vec<u8> = (0..10_000u32).map(|i| u8::try_from(i % 251).unwrap()).collect();
+    for codec in Codec::iter() {
+        let compressed = codec.compress(&data).unwrap();
+        assert!(compressed.len() < data.len(), "{codec:?}");
+        assert_eq!(
+            codec.decompress(&compressed).unwrap(),
+            (data.clone(), compressed.len()),
+            "{codec:?}"
+        );
+        if matches!(codec, Codec::Zlib | Codec::Gzip | Codec::Lz4Frame) {
+            assert_eq!(Codec::detect(&compressed), Some(codec));
+        }
+    }
+    assert!(Codec::Zlib.decompress(b"garbage").is_err());
+}
+
+#[test]
+fn test_stream_len() {
+    let data = b"hello hello hello hello";
+    for codec in [Codec::Zlib, Codec::Deflate, Codec::Gzip] {
+        let mut padded = codec.compress(data).unwrap();
+        let len = padded.len();
+        padded.extend_from_slice(&[0xAA; 100]);
+        let (out, stream_len) = codec.decompress(&padded).unwrap();
+        assert_eq!(out, data, "{codec:?}");
+        assert_eq!(stream_len, len, "{codec:?}");
+    }
+}
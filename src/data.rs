@@ -1,5 +1,10 @@
 use {
-    crate::{damage_region::DamageRegion, meta::region::Region},
+    crate::{
+        damage_region::DamageRegion,
+        edit_history::{Edit, EditHistory, PendingEdit, Splice},
+        meta::region::Region,
+    },
+    gamedebug_core::per,
     std::ops::{Deref, DerefMut},
 };
 
@@ -11,6 +16,10 @@ pub struct Data {
     pub dirty_region: Option<Region>,
     /// Original data length. Compared with current data length to detect truncation.
     pub orig_data_len: usize,
+    /// Undo/redo history of the edits made to the data
+    pub history: EditHistory,
+    /// Edits can't change the length, because the source is a fixed size range
+    fixed_len: bool,
 }
 
 enum DataProvider {
@@ -35,6 +44,8 @@ impl Data {
             orig_data_len: buf.len(),
             data: Some(DataProvider::Vec(buf)),
             dirty_region: None,
+            history: EditHistory::default(),
+            fixed_len: false,
         }
     }
     pub(crate) fn new_mmap_mut(mmap: memmap2::MmapMut) -> Self {
@@ -42,6 +53,8 @@ impl Data {
             orig_data_len: mmap.len(),
             data: Some(DataProvider::MmapMut(mmap)),
             dirty_region: None,
+            history: EditHistory::default(),
+            fixed_len: false,
         }
     }
     pub(crate) fn new_mmap_immut(mmap: memmap2::Mmap) -> Self {
@@ -49,12 +62,15 @@ impl Data {
             orig_data_len: mmap.len(),
             data: Some(DataProvider::MmapImmut(mmap)),
             dirty_region: None,
+            history: EditHistory::default(),
+            fixed_len: false,
         }
     }
     /// Drop any expensive allocations and reset to "empty" state
     pub(crate) fn close(&mut self) {
         self.data = None;
         self.dirty_region = None;
+        self.history = EditHistory::default();
     }
     pub(crate) fn widen_dirty_region(&mut self, damage: DamageRegion) {
         match &mut self.dirty_region {
@@ -88,8 +104,23 @@ impl Data {
     pub(crate) fn undirty(&mut self) {
         self.dirty_region = None;
         self.orig_data_len = self.len();
+        self.history.mark_clean();
     }
 
+    /// Whether bytes were inserted or removed since the data was the same as the source
+    pub(crate) fn moved_since_clean(&self) -> bool {
+        self.len() != self.orig_data_len || self.history.changed_len_since_clean()
+    }
+
+    /// Don't allow edits that change the length of the data, because it mirrors a fixed size
+    /// range of the source, like process memory
+    pub(crate) fn fix_len(&mut self) {
+        self.fixed_len = true;
+    }
+    /// Whether edits can change the length of the data
+    pub(crate) fn can_change_len(&self) -> bool {
+        !self.fixed_len && matches!(self.data, Some(DataProvider::Vec(_)))
+    }
     pub(crate) fn resize(&mut self, new_len: usize, value: u8) {
         match &mut self.data {
             Some(DataProvider::Vec(v)) => v.resize(new_len, value),
@@ -120,11 +151,7 @@ impl Data {
     }
 
     pub(crate) fn zero_fill_region(&mut self, region: Region) {
-        let range = region.begin..=region.end;
-        if let Some(data) = self.get_mut(range.clone()) {
-            data.fill(0);
-            self.widen_dirty_region(DamageRegion::RangeInclusive(range));
-        }
+        self.edit(region.begin..region.end + 1, |data| data.fill(0));
     }
 
     pub(crate) fn reload_from_file(
@@ -139,6 +166,8 @@ impl Data {
             etc => anyhow::bail!("Reload not supported for {etc:?}"),
         }
         self.dirty_region = None;
+        // The old edits don't apply to the reloaded data
+        self.history = EditHistory::default();
         Ok(())
     }
 
@@ -147,10 +176,208 @@ impl Data {
         range: std::ops::RangeInclusive<usize>,
         mut f: impl FnMut(&mut u8),
     ) {
-        for byte in self.get_mut(range.clone()).into_iter().flatten() {
-            f(byte);
+        self.edit(*range.start()..*range.end() + 1, |data| {
+            data.iter_mut().for_each(&mut f);
+        });
+    }
+}
+
+/// Editing with undo/redo history
+impl Data {
+    /// Modify `range` through `f`, recording the change in the edit history,
+    /// and widening the dirty region.
+    ///
+    /// Returns `None` if `range` is out of bounds.
+    pub(crate) fn edit<R>(
+        &mut self,
+        range: std::ops::Range<usize>,
+        f: impl FnOnce(&mut [u8]) -> R,
+    ) -> Option<R> {
+        let old = self.get(range.clone())?.to_vec();
+        let ret = f(self.get_mut(range.clone())?);
+        self.record_change(range.start, old);
+        Some(ret)
+    }
+    /// Overwrite the data at `offset` with `bytes`, recording the change in the edit history.
+    ///
+    /// Returns `false` if the write would be out of bounds.
+    pub(crate) fn write(&mut self, offset: usize, bytes: &[u8]) -> bool {
+        self.edit(offset..offset + bytes.len(), |data| {
+            data.copy_from_slice(bytes)
+        })
+        .is_some()
+    }
+    /// Replace `remove_len` bytes at `offset` with `bytes`, recording the change in the edit
+    /// history. Unlike [`Self::write`], this can change the length of the data.
+    ///
+    /// Returns `None` if the range is out of bounds, or the length of the data can't be changed.
+    pub(crate) fn splice(
+        &mut self,
+        offset: usize,
+        remove_len: usize,
+        bytes: &[u8],
+    ) -> Option<Splice> {
+        let old = self.get(offset..offset + remove_len)?.to_vec();
+        if !self.splice_unrecorded(offset, remove_len, bytes) {
+            return None;
+        }
+        let edit = Edit {
+            offset,
+            old,
+            new: bytes.to_vec(),
+        };
+        let splice = edit.splice();
+        self.widen_dirty_for_splice(splice);
+        self.history.push(edit);
+        Some(splice)
+    }
+    /// Insert `bytes` at `offset`, shifting the following bytes forward
+    pub(crate) fn insert(&mut self, offset: usize, bytes: &[u8]) -> Option<Splice> {
+        self.splice(offset, 0, bytes)
+    }
+    /// Remove the bytes in `range`, shifting the following bytes back
+    pub(crate) fn remove(&mut self, range: std::ops::Range<usize>) -> Option<Splice> {
+        self.splice(range.start, range.len(), &[])
+    }
+    /// Like `get_mut`, but the changes made through the returned slice are recorded in the edit
+    /// history when [`Self::commit_pending_edits`] is called.
+    ///
+    /// Used when we can't know when the mutation finishes, e.g. for plugins.
+    pub(crate) fn get_mut_deferred(
+        &mut self,
+        range: std::ops::RangeInclusive<usize>,
+    ) -> Option<&mut [u8]> {
+        let old = self.get(range.clone())?.to_vec();
+        self.history.pending.push(PendingEdit {
+            offset: *range.start(),
+            old,
+        });
+        self.get_mut(range)
+    }
+    /// Record the changes made through slices handed out by [`Self::get_mut_deferred`]
+    pub(crate) fn commit_pending_edits(&mut self) {
+        for PendingEdit { offset, old } in std::mem::take(&mut self.history.pending) {
+            self.record_change(offset, old);
+        }
+    }
+    /// Record an edit if the data at `offset` differs from `old`
+    fn record_change(&mut self, offset: usize, old: Vec<u8>) {
+        let Some(new) = self.get(offset..offset + old.len()) else {
+            return;
+        };
+        if new == old.as_slice() {
+            return;
+        }
+        let new = new.to_vec();
+        self.widen_dirty_region(DamageRegion::Range(offset..offset + old.len()));
+        self.history.push(Edit { offset, old, new });
+    }
+    /// Record the edits made by `f` as a single undo step
+    pub(crate) fn edit_group<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        self.history.begin_group();
+        let ret = f(self);
+        self.history.end_group();
+        ret
+    }
+    /// Undo the last edit (or group of edits).
+    ///
+    /// Returns how the undo moved the data, in the order the data was moved.
+    pub(crate) fn undo(&mut self) -> Vec<Splice> {
+        let Some(group) = self.history.pop_undo() else {
+            return Vec::new();
+        };
+        let mut splices = Vec::new();
+        for (i, edit) in group.iter().enumerate().rev() {
+            if !self.splice_unrecorded(edit.offset, edit.new.len(), &edit.old) {
+                per!("Failed to undo edit at offset {}", edit.offset);
+                // Restore the edits of the group that were already undone
+                for edit in &group[i + 1..] {
+                    self.splice_unrecorded(edit.offset, edit.old.len(), &edit.new);
+                }
+                self.history.push_redone(group);
+                return Vec::new();
+            }
+            splices.push(edit.reverse_splice());
+        }
+        self.history.push_undone(group);
+        self.update_dirty_after_history_step(&splices);
+        splices
+    }
+    /// Redo the last undone edit (or group of edits).
+    ///
+    /// Returns how the redo moved the data, in the order the data was moved.
+    pub(crate) fn redo(&mut self) -> Vec<Splice> {
+        let Some(group) = self.history.pop_redo() else {
+            return Vec::new();
+        };
+        let mut splices = Vec::new();
+        for (i, edit) in group.iter().enumerate() {
+            if !self.splice_unrecorded(edit.offset, edit.old.len(), &edit.new) {
+                per!("Failed to redo edit at offset {}", edit.offset);
+                // Revert the edits of the group that were already redone
+                for edit in group[..i].iter().rev() {
+                    self.splice_unrecorded(edit.offset, edit.new.len(), &edit.old);
+                }
+                self.history.push_undone(group);
+                return Vec::new();
+            }
+            splices.push(edit.splice());
+        }
+        self.history.push_redone(group);
+        self.update_dirty_after_history_step(&splices);
+        splices
+    }
+    fn update_dirty_after_history_step(&mut self, splices: &[Splice]) {
+        if self.history.is_clean() && self.len() == self.orig_data_len {
+            // We're back at the state of the source, nothing to save
+            self.dirty_region = None;
+        } else {
+            for &splice in splices {
+                self.widen_dirty_for_splice(splice);
+            }
+        }
+    }
+    /// Widen the dirty region to cover everything `splice` touched.
+    ///
+    /// If the length changed, everything after the splice moved, so it's all dirty.
+    fn widen_dirty_for_splice(&mut self, splice: Splice) {
+        let end = if splice.changes_len() {
+            self.len()
+        } else {
+            splice.offset + splice.inserted
+        };
+        if end > splice.offset {
+            self.widen_dirty_region(DamageRegion::Range(splice.offset..end));
+        }
+        // The data might have shrunk below the end of the dirty region
+        if let Some(dirty) = &mut self.dirty_region
+            && dirty.end >= self.len()
+        {
+            match self.len().checked_sub(1) {
+                Some(last) if last >= dirty.begin => dirty.end = last,
+                _ => dirty.end = dirty.begin,
+            }
+        }
+    }
+    /// Replace `remove_len` bytes at `offset` with `bytes`, without recording it in the history
+    fn splice_unrecorded(&mut self, offset: usize, remove_len: usize, bytes: &[u8]) -> bool {
+        if remove_len == bytes.len() {
+            match self.get_mut(offset..offset + remove_len) {
+                Some(data) => {
+                    data.copy_from_slice(bytes);
+                    true
+                }
+                None => false,
+            }
+        } else {
+            match &mut self.data {
+                Some(DataProvider::Vec(v)) if !self.fixed_len && offset + remove_len <= v.len() => {
+                    v.splice(offset..offset + remove_len, bytes.iter().copied());
+                    true
+                }
+                _ => false,
+            }
         }
-        self.widen_dirty_region(range.into());
     }
 }
 
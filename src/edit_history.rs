@@ -0,0 +1,235 @@
+//! Undo/redo journal for the edits made to [`Data`](crate::data::Data)
+
+use crate::meta::region::Region;
+
+/// A single recorded edit: the bytes `old` at `offset` were replaced with `new`.
+///
+/// `old` and `new` can differ in length, in which case the edit changed the length of the data.
+#[derive(Debug, Clone)]
+pub struct Edit {
+    pub offset: usize,
+    pub old: Vec<u8>,
+    pub new: Vec<u8>,
+}
+
+impl Edit {
+    /// The [`Splice`] that applying this edit performs
+    pub fn splice(&self) -> Splice {
+        Splice {
+            offset: self.offset,
+            removed: self.old.len(),
+            inserted: self.new.len(),
+        }
+    }
+    /// The [`Splice`] that reverting this edit performs
+    pub fn reverse_splice(&self) -> Splice {
+        Splice {
+            offset: self.offset,
+            removed: self.new.len(),
+            inserted: self.old.len(),
+        }
+    }
+}
+
+/// Describes where an applied edit moved data: `removed` bytes at `offset` were replaced
+/// with `inserted` bytes.
+///
+/// Used to re-offset anything pointing into the data after a length changing edit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Splice {
+    pub offset: usize,
+    pub removed: usize,
+    pub inserted: usize,
+}
+
+impl Splice {
+    /// Whether the bytes after the spliced range moved
+    pub fn changes_len(&self) -> bool {
+        self.removed != self.inserted
+    }
+    /// Map an offset that was valid before the splice to where the same data is after it.
+    ///
+    /// Offsets inside the removed range are clamped to the start of the inserted range.
+    pub fn map_offset(&self, off: usize) -> usize {
+        if off < self.offset {
+            off
+        } else if off >= self.offset + self.removed {
+            off - self.removed + self.inserted
+        } else {
+            self.offset + (off - self.offset).min(self.inserted.saturating_sub(1))
+        }
+    }
+    /// Map a region the same way as [`Self::map_offset`].
+    ///
+    /// A region that was completely removed collapses to a single byte at the splice offset.
+    pub fn map_region(&self, region: Region) -> Region {
+        let begin = self.map_offset(region.begin);
+        let end_removed = (self.offset..self.offset + self.removed).contains(&region.end);
+        let end = if end_removed && self.inserted == 0 {
+            self.offset.saturating_sub(1)
+        } else {
+            self.map_offset(region.end)
+        };
+        Region {
+            begin,
+            end: end.max(begin),
+        }
+    }
+}
+
+/// A snapshot of a range that was handed out for mutation, and will be turned into an [`Edit`]
+/// once the mutation is finished.
+#[derive(Debug)]
+pub struct PendingEdit {
+    pub offset: usize,
+    pub old: Vec<u8>,
+}
+
+/// Edits that are undone and redone together, in the order they were made
+pub type EditGroup = Vec<Edit>;
+
+#[derive(Debug, Default)]
+pub struct EditHistory {
+    undo_stack: Vec<EditGroup>,
+    redo_stack: Vec<EditGroup>,
+    /// The depth of the undo stack at which the data is the same as the source.
+    ///
+    /// `None` if that state can't be reached anymore by undoing/redoing.
+    clean_depth: Option<usize> = Some(0),
+    pub(crate) pending: Vec<PendingEdit>,
+    /// The edits recorded since [`Self::begin_group`], if a group is open
+    open_group: Option<EditGroup>,
+}
+
+impl EditHistory {
+    /// Record a new edit. This invalidates the redo stack.
+    ///
+    /// If a group is open, the edit becomes part of it.
+    pub fn push(&mut self, edit: Edit) {
+        match &mut self.open_group {
+            Some(group) => group.push(edit),
+            None => self.push_group(vec![edit]),
+        }
+    }
+    fn push_group(&mut self, group: EditGroup) {
+        self.redo_stack.clear();
+        if self.clean_depth.is_some_and(|depth| depth > self.undo_stack.len()) {
+            self.clean_depth = None;
+        }
+        self.undo_stack.push(group);
+    }
+    /// Start recording edits as a group, which is undone and redone as a single step
+    pub(crate) fn begin_group(&mut self) {
+        self.open_group.get_or_insert_default();
+    }
+    /// Finish the group started with [`Self::begin_group`]
+    pub(crate) fn end_group(&mut self) {
+        if let Some(group) = self.open_group.take()
+            && !group.is_empty()
+        {
+            self.push_group(group);
+        }
+    }
+    pub(crate) fn pop_undo(&mut self) -> Option<EditGroup> {
+        self.undo_stack.pop()
+    }
+    pub(crate) fn pop_redo(&mut self) -> Option<EditGroup> {
+        self.redo_stack.pop()
+    }
+    /// Push edits that were just undone, so they can be redone
+    pub(crate) fn push_undone(&mut self, group: EditGroup) {
+        self.redo_stack.push(group);
+    }
+    /// Push edits that were just redone, so they can be undone again
+    pub(crate) fn push_redone(&mut self, group: EditGroup) {
+        self.undo_stack.push(group);
+    }
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+    pub fn undo_len(&self) -> usize {
+        self.undo_stack.len()
+    }
+    pub fn redo_len(&self) -> usize {
+        self.redo_stack.len()
+    }
+    /// Mark the current state as being the same as the source (e.g. after saving)
+    pub fn mark_clean(&mut self) {
+        self.clean_depth = Some(self.undo_stack.len());
+    }
+    /// Whether the current state is the same as the source
+    pub fn is_clean(&self) -> bool {
+        self.clean_depth == Some(self.undo_stack.len())
+    }
+    /// Whether bytes were inserted or removed between the clean state and the current one
+    pub fn changed_len_since_clean(&self) -> bool {
+        let Some(clean_depth) = self.clean_depth else {
+            return true;
+        };
+        let depth = self.undo_stack.len();
+        // Undone edits are on the redo stack, the most recently undone one last
+        let groups = if clean_depth <= depth {
+            &self.undo_stack[clean_depth..]
+        } else {
+            match self.redo_stack.len().checked_sub(clean_depth - depth) {
+                Some(start) => &self.redo_stack[start..],
+                None => return true,
+            }
+        };
+        groups.iter().flatten().any(|edit| edit.splice().changes_len())
+    }
+}
+
+#[test]
+fn test_changed_len_since_clean() {
+    let edit = |old: &[u8], new: &[u8]| Edit {
+        offset: 0,
+        old: old.to_vec(),
+        new: new.to_vec(),
+    };
+    let mut history = EditHistory::default();
+    history.push(edit(b"a", b"b"));
+    assert!(!history.changed_len_since_clean());
+    history.begin_group();
+    history.push(edit(b"d", b"e"));
+    history.push(edit(b"", b"c"));
+    history.end_group();
+    assert_eq!(history.undo_len(), 2);
+    assert!(history.changed_len_since_clean());
+    history.mark_clean();
+    assert!(!history.changed_len_since_clean());
+    // Undoing past the clean state
+    let undone = history.pop_undo().unwrap();
+    history.push_undone(undone);
+    assert!(history.changed_len_since_clean());
+    let undone = history.pop_undo().unwrap();
+    history.push_undone(undone);
+    assert!(history.changed_len_since_clean());
+}
+
+#[test]
+fn test_splice_map_region() {
+    let reg = |begin, end| Region { begin, end };
+    // Insert 4 bytes at 10
+    let ins = Splice {
+        offset: 10,
+        removed: 0,
+        inserted: 4,
+    };
+    assert_eq!(ins.map_region(reg(0, 9)), reg(0, 9));
+    assert_eq!(ins.map_region(reg(5, 15)), reg(5, 19));
+    assert_eq!(ins.map_region(reg(10, 15)), reg(14, 19));
+    // Remove 4 bytes at 10
+    let rem = Splice {
+        offset: 10,
+        removed: 4,
+        inserted: 0,
+    };
+    assert_eq!(rem.map_region(reg(0, 9)), reg(0, 9));
+    assert_eq!(rem.map_region(reg(5, 12)), reg(5, 9));
+    assert_eq!(rem.map_region(reg(12, 20)), reg(10, 16));
+    assert_eq!(rem.map_region(reg(11, 12)), reg(10, 10));
+}
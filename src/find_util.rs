@@ -1,29 +1,184 @@
+use {
+    anyhow::Context as _,
+    regex_automata::{Input, meta::Regex as MetaRegex, util::syntax::Config as SyntaxConfig},
+    std::ops::Range,
+    strum::{EnumIter, IntoStaticStr},
+};
+
+pub mod job;
+
 pub fn find_hex_string(
     hex_string: &str,
     haystack: &[u8],
-    mut f: impl FnMut(usize),
+    f: impl FnMut(usize),
 ) -> anyhow::Result<()> {
-    let needle = parse_hex_string(hex_string)?;
-    for offset in memchr::memmem::find_iter(haystack, &needle) {
-        f(offset);
-    }
+    HexPattern::parse(hex_string)?.find_iter(haystack, f);
     Ok(())
 }
 
-enum HexStringSepKind {
-    Comma,
-    Whitespace,
-    Dense,
+/// A hex byte pattern, like `48 8B ?? ?? 4? E8 (01|02)`.
+///
+/// Each element matches one byte. It can be exact (`E8`), a wildcard (`??`),
+/// masked by nibble (`4?`, `?8`), or a group of alternatives (`(01|02|1?)`).
+pub struct HexPattern {
+    elems: Vec<PatElem>,
+}
+
+/// One byte of a pattern. Matches a byte `b` if `b & mask == value` for any of the alternatives.
+struct PatElem {
+    alts: Vec<(u8, u8)>,
+}
+
+impl PatElem {
+    fn matches(&self, byte: u8) -> bool {
+        self.alts.iter().any(|&(value, mask)| byte & mask == value)
+    }
+    fn exact(&self) -> Option<u8> {
+        match self.alts.as_slice() {
+            &[(value, 0xFF)] => Some(value),
+            _ => None,
+        }
+    }
 }
 
-fn detect_hex_string_sep_kind(hex_string: &str) -> HexStringSepKind {
-    if hex_string.contains(',') {
-        HexStringSepKind::Comma
-    } else if hex_string.contains(char::is_whitespace) {
-        HexStringSepKind::Whitespace
+impl HexPattern {
+    pub fn parse(hex_string: &str) -> anyhow::Result<Self> {
+        let mut elems = Vec::new();
+        let mut rest = hex_string;
+        loop {
+            rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+            if rest.is_empty() {
+                break;
+            }
+            if let Some(group) = rest.strip_prefix('(') {
+                let end = group.find(')').context("Unclosed '('")?;
+                let alts = group[..end]
+                    .split('|')
+                    .map(|alt| parse_masked_byte(alt.trim()))
+                    .collect::<anyhow::Result<_>>()?;
+                elems.push(PatElem { alts });
+                rest = &group[end + 1..];
+                continue;
+            }
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == ',' || c == '(')
+                .unwrap_or(rest.len());
+            let (token, after) = rest.split_at(end);
+            rest = after;
+            // Single digit tokens, like the `f` in `0 f`
+            if token.len() == 1 {
+                elems.push(PatElem {
+                    alts: vec![parse_masked_byte(token)?],
+                });
+                continue;
+            }
+            anyhow::ensure!(
+                token.len().is_multiple_of(2),
+                "Odd number of digits in '{token}'"
+            );
+            // Dense tokens, like `deadbeef`
+            for pair in chunks_2(token) {
+                elems.push(PatElem {
+                    alts: vec![parse_masked_byte(pair?)?],
+                });
+            }
+        }
+        Ok(Self { elems })
+    }
+    /// Number of bytes a match spans
+    pub fn len(&self) -> usize {
+        self.elems.len()
+    }
+    /// The bytes of the pattern, if it has no wildcards or alternatives
+    pub fn to_exact(&self) -> Option<Vec<u8>> {
+        self.elems.iter().map(PatElem::exact).collect()
+    }
+    fn matches_at(&self, haystack: &[u8], pos: usize) -> bool {
+        haystack
+            .get(pos..pos + self.elems.len())
+            .is_some_and(|window| self.elems.iter().zip(window).all(|(elem, &b)| elem.matches(b)))
+    }
+    /// The longest run of exact bytes, as (index of first element, bytes)
+    fn anchor(&self) -> (usize, Vec<u8>) {
+        let mut best = (0, Vec::new());
+        let mut run = (0, Vec::new());
+        for (i, elem) in self.elems.iter().enumerate() {
+            match elem.exact() {
+                Some(b) => {
+                    if run.1.is_empty() {
+                        run.0 = i;
+                    }
+                    run.1.push(b);
+                    if run.1.len() > best.1.len() {
+                        best.clone_from(&run);
+                    }
+                }
+                None => run.1.clear(),
+            }
+        }
+        best
+    }
+    /// Call `f` with the offset of each (non-overlapping) match, in order.
+    ///
+    /// The longest exact part of the pattern is searched with `memchr`,
+    /// and the rest is only checked around its occurrences.
+    pub fn find_iter(&self, haystack: &[u8], mut f: impl FnMut(usize)) {
+        let len = self.elems.len();
+        if len == 0 {
+            return;
+        }
+        let (anchor_idx, anchor) = self.anchor();
+        if anchor.is_empty() {
+            let mut pos = 0;
+            while pos + len <= haystack.len() {
+                if self.matches_at(haystack, pos) {
+                    f(pos);
+                    pos += len;
+                } else {
+                    pos += 1;
+                }
+            }
+            return;
+        }
+        let finder = memchr::memmem::Finder::new(&anchor);
+        let mut search_from = anchor_idx;
+        while let Some(rel) = haystack.get(search_from..).and_then(|hay| finder.find(hay)) {
+            let anchor_pos = search_from + rel;
+            let start = anchor_pos - anchor_idx;
+            if self.matches_at(haystack, start) {
+                f(start);
+                search_from = start + len + anchor_idx;
+            } else {
+                search_from = anchor_pos + 1;
+            }
+        }
+    }
+}
+
+/// Parse a byte where each digit can be `?`, returning (value, mask)
+fn parse_masked_byte(tok: &str) -> anyhow::Result<(u8, u8)> {
+    anyhow::ensure!(
+        (1..=2).contains(&tok.len()),
+        "Expected 1 or 2 digits, got '{tok}'"
+    );
+    // A lone digit is the whole byte, like `f` for `0f`
+    let high_nibble = if tok.len() == 1 && tok != "?" {
+        "0"
     } else {
-        HexStringSepKind::Dense
+        ""
+    };
+    let mut value = 0;
+    let mut mask = 0;
+    for c in high_nibble.chars().chain(tok.chars()) {
+        value <<= 4;
+        mask <<= 4;
+        if c != '?' {
+            let digit = c.to_digit(16).with_context(|| format!("Invalid hex digit '{c}'"))?;
+            value |= u8::try_from(digit)?;
+            mask |= 0xF;
+        }
     }
+    Ok((value, mask))
 }
 
 fn chunks_2(input: &str) -> impl Iterator<Item = anyhow::Result<&str>> {
@@ -35,20 +190,232 @@ fn chunks_2(input: &str) -> impl Iterator<Item = anyhow::Result<&str>> {
         .map(|pair| std::str::from_utf8(pair).map_err(anyhow::Error::from))
 }
 
+/// Parse a hex string of exact bytes, like `de ad be ef`
 pub fn parse_hex_string(hex_string: &str) -> anyhow::Result<Vec<u8>> {
-    match detect_hex_string_sep_kind(hex_string) {
-        HexStringSepKind::Comma => {
-            hex_string.split(',').map(|tok| parse_hex_token(tok.trim())).collect()
+    HexPattern::parse(hex_string)?
+        .to_exact()
+        .context("Wildcards and alternatives can't be used here")
+}
+
+/// How the data is interpreted for regex matching
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, EnumIter, IntoStaticStr)]
+pub enum RegexEncoding {
+    /// Match the raw bytes. Non-ascii bytes can be matched with `\xNN`.
+    #[default]
+    Bytes,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// How many bytes around the searched range are decoded for UTF-16 regex searches.
+///
+/// Enough to see one character on either side, which is all the anchors look at.
+const UTF16_CONTEXT: usize = 4;
+
+/// A regular expression compiled for searching the data
+pub struct RegexFinder {
+    re: regex_automata::meta::Regex,
+    encoding: RegexEncoding,
+}
+
+impl RegexFinder {
+    pub fn new(
+        pattern: &str,
+        case_insensitive: bool,
+        encoding: RegexEncoding,
+    ) -> anyhow::Result<Self> {
+        let syntax = SyntaxConfig::new().case_insensitive(case_insensitive);
+        let mut builder = MetaRegex::builder();
+        match encoding {
+            // Any byte can be matched, not just UTF-8
+            RegexEncoding::Bytes => {
+                builder
+                    .syntax(syntax.unicode(false).utf8(false))
+                    .configure(MetaRegex::config().utf8_empty(false));
+            }
+            RegexEncoding::Utf16Le | RegexEncoding::Utf16Be => {
+                builder.syntax(syntax);
+            }
+        }
+        Ok(Self {
+            re: builder.build(pattern)?,
+            encoding,
+        })
+    }
+    /// Find the matches in `range` of `haystack`, calling `f` with the range of each match
+    /// (in `haystack`), in order.
+    ///
+    /// The bytes outside of `range` are never part of a match. They are only looked at
+    /// by anchors like `^`, `$` and `\b`, so searching a part of the data finds
+    /// the same matches as searching all of it.
+    pub fn find(&self, haystack: &[u8], range: Range<usize>, mut f: impl FnMut(Range<usize>)) {
+        match self.encoding {
+            RegexEncoding::Bytes => {
+                for m in self.re.find_iter(Input::new(haystack).span(range)) {
+                    f(m.range());
+                }
+            }
+            RegexEncoding::Utf16Le | RegexEncoding::Utf16Be => {
+                let mut ranges = Vec::new();
+                // Text can start at either alignment
+                for align in 0..2 {
+                    let start = range.start + (range.start + align) % 2;
+                    if start >= range.end {
+                        continue;
+                    }
+                    let ctx_start = start - (start.min(UTF16_CONTEXT) & !1);
+                    let ctx_end = (range.end + UTF16_CONTEXT).min(haystack.len());
+                    let data = &haystack[ctx_start..ctx_end];
+                    let (text, starts) =
+                        decode_utf16(data, self.encoding == RegexEncoding::Utf16Be);
+                    // Index of the first char starting at or after `data_off`
+                    let text_idx = |data_off| {
+                        let idx = starts.partition_point(|&(_, off)| off < data_off);
+                        starts.get(idx).map_or(text.len(), |&(text_idx, _)| text_idx)
+                    };
+                    let span = text_idx(start - ctx_start)..text_idx(range.end - ctx_start);
+                    let data_offset =
+                        |text_idx| match starts.binary_search_by_key(&text_idx, |&(s, _)| s) {
+                            Ok(idx) => Some(starts[idx].1),
+                            // Matched until the end of the decoded text
+                            Err(idx) if idx == starts.len() => Some(data.len() & !1),
+                            Err(_) => None,
+                        };
+                    for m in self.re.find_iter(Input::new(&text).span(span)) {
+                        if let Some(start) = data_offset(m.start())
+                            && let Some(end) = data_offset(m.end())
+                        {
+                            ranges.push(ctx_start + start..ctx_start + end);
+                        }
+                    }
+                }
+                ranges.sort_unstable_by_key(|range| range.start);
+                ranges.into_iter().for_each(f);
+            }
+        }
+    }
+}
+
+/// How far past the match start a UTF-16 match is looked for by [`regex_match_len`].
+///
+/// Also the longest regex match that is found intact across search chunks.
+pub const REGEX_MATCH_WINDOW: usize = 1024 * 1024;
+
+/// Length in bytes of the regex match starting exactly at the start of `haystack`, if any
+pub fn regex_match_len(
+    pattern: &str,
+    haystack: &[u8],
+    case_insensitive: bool,
+    encoding: RegexEncoding,
+) -> anyhow::Result<Option<usize>> {
+    let anchored = format!("^(?:{pattern})");
+    match encoding {
+        RegexEncoding::Bytes => {
+            let re = regex::bytes::RegexBuilder::new(&anchored)
+                .case_insensitive(case_insensitive)
+                .unicode(false)
+                .build()?;
+            Ok(re.find(haystack).map(|m| m.end()))
         }
-        HexStringSepKind::Whitespace => {
-            hex_string.split_whitespace().map(parse_hex_token).collect()
+        RegexEncoding::Utf16Le | RegexEncoding::Utf16Be => {
+            let re =
+                regex::RegexBuilder::new(&anchored).case_insensitive(case_insensitive).build()?;
+            let data = &haystack[..haystack.len().min(REGEX_MATCH_WINDOW)];
+            let (text, starts) = decode_utf16(data, encoding == RegexEncoding::Utf16Be);
+            Ok(re.find(&text).map(|m| {
+                match starts.binary_search_by_key(&m.end(), |&(s, _)| s) {
+                    Ok(idx) => starts[idx].1,
+                    // Matched until the end of the decoded text
+                    Err(_) => data.len() & !1,
+                }
+            }))
         }
-        HexStringSepKind::Dense => chunks_2(hex_string).map(|tok| parse_hex_token(tok?)).collect(),
     }
 }
 
-fn parse_hex_token(tok: &str) -> anyhow::Result<u8> {
-    Ok(u8::from_str_radix(tok, 16)?)
+/// Decode UTF-16 leniently (invalid units become U+FFFD).
+///
+/// Also returns `(text byte index, data offset)` for the start of each char.
+fn decode_utf16(data: &[u8], big_endian: bool) -> (String, Vec<(usize, usize)>) {
+    let units = data.chunks_exact(2).map(|pair| {
+        let pair = [pair[0], pair[1]];
+        if big_endian {
+            u16::from_be_bytes(pair)
+        } else {
+            u16::from_le_bytes(pair)
+        }
+    });
+    let mut text = String::new();
+    let mut starts = Vec::new();
+    let mut unit_pos = 0;
+    for result in char::decode_utf16(units) {
+        starts.push((text.len(), unit_pos * 2));
+        let c = match result {
+            Ok(c) => {
+                unit_pos += c.len_utf16();
+                c
+            }
+            Err(_) => {
+                unit_pos += 1;
+                char::REPLACEMENT_CHARACTER
+            }
+        };
+        text.push(c);
+    }
+    (text, starts)
+}
+
+#[test]
+fn test_find_regex() {
+    let find = |pat, hay: &[u8], range: Range<usize>, ci, enc| {
+        let mut found = Vec::new();
+        RegexFinder::new(pat, ci, enc)
+            .unwrap()
+            .find(hay, range, |m| found.push((m.start, m.end)));
+        found
+    };
+    let hay = b"xxabbbc abc ABC";
+    assert_eq!(
+        find("ab+c", hay, 0..hay.len(), false, RegexEncoding::Bytes),
+        [(2, 7), (8, 11)]
+    );
+    let hay = b"\xFFa\xFFB";
+    assert_eq!(
+        find(r"\xFF[a-c]", hay, 0..hay.len(), true, RegexEncoding::Bytes),
+        [(0, 2), (2, 4)]
+    );
+    // "hi" in UTF-16LE, at an odd offset
+    let hay = b"\0h\0i\0";
+    assert_eq!(
+        find("H.", hay, 0..hay.len(), true, RegexEncoding::Utf16Le),
+        [(1, 5)]
+    );
+    let hay = b"\0h\0i";
+    assert_eq!(
+        find("hi", hay, 0..hay.len(), false, RegexEncoding::Utf16Be),
+        [(0, 4)]
+    );
+}
+
+#[test]
+fn test_find_regex_anchors_in_range() {
+    let find = |pat, hay: &[u8], range: Range<usize>, enc| {
+        let mut found = Vec::new();
+        RegexFinder::new(pat, false, enc)
+            .unwrap()
+            .find(hay, range, |m| found.push((m.start, m.end)));
+        found
+    };
+    // The edges of the range aren't the edges of the data
+    let hay = b"abcabc";
+    assert!(find("^abc", hay, 3..6, RegexEncoding::Bytes).is_empty());
+    assert!(find("abc$", hay, 0..3, RegexEncoding::Bytes).is_empty());
+    assert!(find(r"\babc", hay, 3..6, RegexEncoding::Bytes).is_empty());
+    assert_eq!(find("abc$", hay, 3..6, RegexEncoding::Bytes), [(3, 6)]);
+    let hay = b"a\0b\0a\0b\0";
+    assert!(find("^ab", hay, 4..8, RegexEncoding::Utf16Le).is_empty());
+    assert!(find("ab$", hay, 0..4, RegexEncoding::Utf16Le).is_empty());
+    assert_eq!(find("^ab", hay, 0..4, RegexEncoding::Utf16Le), [(0, 4)]);
 }
 
 #[test]
@@ -66,3 +433,35 @@ fn test_parse_hex_string() {
         vec![0xde, 0xad, 0xbe, 0xef]
     );
 }
+
+#[test]
+fn test_find_hex_pattern() {
+    let hay = [
+        0x48, 0x8B, 0x05, 0x10, 0x42, 0xE8, 0x48, 0x8B, 0, 0, 0x50, 0xE8,
+    ];
+    let mut found = Vec::new();
+    find_hex_string("48 8B ?? ?? 4? E8", &hay, |off| found.push(off)).unwrap();
+    assert_eq!(found, [0]);
+    found.clear();
+    find_hex_string("48 8B ?? ?? (4?|5?) E8", &hay, |off| found.push(off)).unwrap();
+    assert_eq!(found, [0, 6]);
+    found.clear();
+    find_hex_string("?8", &hay, |off| found.push(off)).unwrap();
+    assert_eq!(found, [0, 5, 6, 11]);
+    found.clear();
+    // Anchor not at the start
+    find_hex_string("?? E8", &hay, |off| found.push(off)).unwrap();
+    assert_eq!(found, [4, 10]);
+    assert_eq!(parse_hex_string("0 f").unwrap(), [0x00, 0x0f]);
+    assert!(parse_hex_string("de ?? ef").is_err());
+    assert!(HexPattern::parse("(01|02").is_err());
+}
+
+#[test]
+fn test_regex_match_len() {
+    let len = |pat, hay: &[u8], enc| regex_match_len(pat, hay, false, enc).unwrap();
+    assert_eq!(len("ab+", b"abbbc", RegexEncoding::Bytes), Some(4));
+    assert_eq!(len("ab+", b"cabbb", RegexEncoding::Bytes), None);
+    assert_eq!(len("hi", b"h\0i\0!\0", RegexEncoding::Utf16Le), Some(4));
+    assert_eq!(len("hi!", b"h\0i\0!\0", RegexEncoding::Utf16Le), Some(6));
+}
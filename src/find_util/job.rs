@@ -0,0 +1,201 @@
+//! Searching on a worker thread, so big searches don't freeze the UI
+
+use std::{
+    ops::Range,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{Receiver, TryRecvError},
+    },
+    thread::{self, JoinHandle},
+};
+
+/// Searches `range` of `haystack`, calling the callback with the range (in `haystack`)
+/// of each match, in order.
+///
+/// The bytes outside of `range` can't be part of a match, but they can be looked at
+/// by anchors, like the `^` and `$` of a regex.
+pub type SearchFn =
+    Box<dyn Fn(&[u8], Range<usize>, &mut dyn FnMut(Range<usize>)) -> anyhow::Result<()> + Send>;
+
+/// Make a [`SearchFn`] out of a search that only looks at the bytes it's given
+pub fn sliced(
+    search: impl Fn(&[u8], &mut dyn FnMut(Range<usize>)) -> anyhow::Result<()> + Send + 'static,
+) -> SearchFn {
+    Box::new(move |haystack, range, f| {
+        let start = range.start;
+        search(&haystack[range], &mut |m| f(start + m.start..start + m.end))
+    })
+}
+
+/// The data is searched in chunks of this size, so progress can be reported and
+/// cancellation can be checked between them
+const CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// A search running on a worker thread.
+///
+/// It searches a snapshot of the data, so the data can be edited (or another document
+/// opened) while it's running, and it keeps running while nobody polls it.
+pub struct SearchJob {
+    recv: Receiver<Vec<usize>>,
+    /// Length of the searched data
+    len: usize,
+    /// How many bytes of the data have been searched
+    progress: Arc<AtomicUsize>,
+    cancel: Arc<AtomicBool>,
+    handle: Option<JoinHandle<anyhow::Result<()>>>,
+}
+
+impl SearchJob {
+    /// Start searching `data`. Match offsets are reported relative to `base_offset`.
+    ///
+    /// Chunks are extended by `max_match_len`, so matches crossing chunk boundaries are found.
+    /// Longer matches crossing a boundary are cut short.
+    pub fn spawn(
+        data: Arc<[u8]>,
+        base_offset: usize,
+        max_match_len: usize,
+        search: SearchFn,
+    ) -> Self {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let len = data.len();
+        let progress = Arc::new(AtomicUsize::new(0));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let handle = thread::spawn({
+            let progress = progress.clone();
+            let cancel = cancel.clone();
+            move || {
+                let overlap = max_match_len.saturating_sub(1);
+                // Matches don't overlap, so the next one can't start before this
+                let mut next_allowed = 0;
+                let mut chunk_start = 0;
+                while chunk_start < data.len() {
+                    if cancel.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let chunk_end = (chunk_start + CHUNK_SIZE).min(data.len());
+                    let search_start = chunk_start.max(next_allowed);
+                    let mut found = Vec::new();
+                    if search_start < chunk_end {
+                        let search_end = (chunk_end + overlap).min(data.len());
+                        search(&data, search_start..search_end, &mut |m| {
+                            if m.start < chunk_end && m.start >= next_allowed {
+                                found.push(base_offset + m.start);
+                                next_allowed = m.end.max(m.start + 1);
+                            }
+                        })?;
+                    }
+                    progress.store(chunk_end, Ordering::Relaxed);
+                    if !found.is_empty() && tx.send(found).is_err() {
+                        // Nobody's listening anymore
+                        break;
+                    }
+                    chunk_start = chunk_end;
+                }
+                Ok(())
+            }
+        });
+        Self {
+            recv: rx,
+            len,
+            progress,
+            cancel,
+            handle: Some(handle),
+        }
+    }
+    /// How much of the data has been searched, from 0.0 to 1.0
+    #[expect(clippy::cast_precision_loss)]
+    pub fn progress(&self) -> f32 {
+        if self.len == 0 {
+            return 1.0;
+        }
+        self.progress.load(Ordering::Relaxed) as f32 / self.len as f32
+    }
+    /// Ask the worker to stop. It stops at the next chunk boundary.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+    /// Call `f` with the offsets of the matches found since the last poll.
+    ///
+    /// Returns `Some` with the outcome of the search once it's finished.
+    pub fn poll(&mut self, mut f: impl FnMut(usize)) -> Option<anyhow::Result<()>> {
+        loop {
+            match self.recv.try_recv() {
+                Ok(batch) => batch.into_iter().for_each(&mut f),
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => {
+                    let handle = self.handle.take()?;
+                    return Some(
+                        handle.join().unwrap_or_else(|_| Err(anyhow::anyhow!("Search panicked"))),
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn run_job(data: &[u8], base_offset: usize, max_match_len: usize, search: SearchFn) -> Vec<usize> {
+    let mut job = SearchJob::spawn(data.into(), base_offset, max_match_len, search);
+    let mut found = Vec::new();
+    let result = loop {
+        if let Some(result) = job.poll(|off| found.push(off)) {
+            break result;
+        }
+        thread::yield_now();
+    };
+    assert!(result.is_ok());
+    assert!((job.progress() - 1.0).abs() < f32::EPSILON);
+    found
+}
+
+#[test]
+fn test_search_job_chunks() {
+    // Matches straddling chunk boundaries are found exactly once
+    let mut data = vec![0; CHUNK_SIZE * 2 + 110];
+    for off in [100, CHUNK_SIZE + 98, CHUNK_SIZE * 2 + 104] {
+        data[off..off + 4].copy_from_slice(b"\xde\xad\xbe\xef");
+    }
+    let search = sliced(|hay, f| {
+        memchr::memmem::find_iter(hay, b"\xde\xad\xbe\xef").for_each(|off| f(off..off + 4));
+        Ok(())
+    });
+    let found = run_job(&data[100..], 100, 4, search);
+    assert_eq!(found, [100, CHUNK_SIZE + 98, CHUNK_SIZE * 2 + 104]);
+}
+
+#[test]
+fn test_search_job_variable_len() {
+    // A match crossing a chunk boundary hides the matches inside it in the next chunk
+    let mut data = vec![0; CHUNK_SIZE * 2];
+    data[CHUNK_SIZE - 4..CHUNK_SIZE + 4].fill(b'a');
+    data[CHUNK_SIZE + 10] = b'a';
+    let search = sliced(|hay, f| {
+        let mut pos = 0;
+        while let Some(start) = hay[pos..].iter().position(|&b| b == b'a').map(|p| pos + p) {
+            let len = hay[start..].iter().take_while(|&&b| b == b'a').count();
+            f(start..start + len);
+            pos = start + len;
+        }
+        Ok(())
+    });
+    let found = run_job(&data, 0, 64, search);
+    assert_eq!(found, [CHUNK_SIZE - 4, CHUNK_SIZE + 10]);
+}
+
+#[test]
+fn test_search_job_chunk_edges_arent_data_edges() {
+    // Like a regex `^.` or `.$`, which only match at the start or end of the data
+    let data = vec![0; CHUNK_SIZE * 3];
+    let search: SearchFn = Box::new(|hay, range, f| {
+        if range.start == 0 {
+            f(0..1);
+        }
+        if range.end == hay.len() && range.start < range.end {
+            f(range.end - 1..range.end);
+        }
+        Ok(())
+    });
+    let found = run_job(&data, 0, 16, search);
+    assert_eq!(found, [0, CHUNK_SIZE * 3 - 1]);
+}
@@ -9,6 +9,7 @@ use {
         config::Style,
         meta::{
             Bookmark,
+            region::Region,
             value_type::{self, ValueType},
         },
         view::{ViewportScalar, ViewportVec},
@@ -187,6 +188,7 @@ pub fn do_egui(
             app,
             &mut gui.msg_dialog,
             &mut gui.win.file_diff_result,
+            &mut gui.win.patch,
             font_size,
             line_spacing,
         );
@@ -234,9 +236,25 @@ fn add_new_bookmark(app: &mut App, gui: &mut Gui, byte_off: usize) {
         label: format!("New @ offset {byte_off}"),
         desc: String::new(),
         value_type: ValueType::U8(value_type::U8),
+        frozen: None,
     });
     gui.win.bookmarks.open.set(true);
     gui.win.bookmarks.selected = Some(idx);
     gui.win.bookmarks.edit_name = true;
     gui.win.bookmarks.focus_text_edit = true;
 }
+
+/// Add a bookmark that keeps the selected bytes frozen at their current value
+fn add_frozen_bookmark(app: &mut App, gui: &mut Gui, sel: Region) {
+    let bms = &mut app.meta_state.meta.bookmarks;
+    let idx = bms.len();
+    bms.push(Bookmark {
+        offset: sel.begin,
+        label: format!("Frozen @ offset {}", sel.begin),
+        desc: String::new(),
+        value_type: ValueType::None,
+        frozen: Some(app.data[sel.begin..=sel.end].to_vec()),
+    });
+    gui.win.bookmarks.open.set(true);
+    gui.win.bookmarks.selected = Some(idx);
+}
@@ -3,7 +3,6 @@ use {
     crate::{
         app::{App, interact_mode::InteractMode},
         meta::find_most_specific_region_for_offset,
-        shell::msg_if_fail,
         util::human_size,
         view::ViewportVec,
     },
@@ -32,6 +31,21 @@ pub fn ui(ui: &mut Ui, app: &mut App, mouse_pos: ViewportVec, gui: &mut Gui) {
         {
             app.hex_ui.interact_mode = InteractMode::Edit;
         }
+        let job = key_label(ui, "Ins", "Insert");
+        // Insert mode can always be turned off, but only turned on if the length can change
+        if ui
+            .add_enabled(
+                app.edit_state.insert_mode || app.data.can_change_len(),
+                egui::Button::selectable(app.edit_state.insert_mode, job),
+            )
+            .on_hover_text("Insert bytes instead of overwriting, and delete by shifting bytes back")
+            .on_disabled_hover_text(
+                "The length of this data can't change (memory mapped, or process memory)",
+            )
+            .clicked()
+        {
+            app.edit_state.insert_mode ^= true;
+        }
         ui.separator();
         let data_len = app.data.len();
         if data_len != 0
@@ -128,14 +142,18 @@ pub fn ui(ui: &mut Ui, app: &mut App, mouse_pos: ViewportVec, gui: &mut Gui) {
         let re = ui.label(text);
         re.context_menu(|ui| {
             if ui.button("Copy").clicked() {
-                let result = app.clipboard.set_text(app.edit_state.cursor.to_string());
-                msg_if_fail(result, "Failed to set clipboard text", &mut gui.msg_dialog);
+                crate::app::set_clipboard_string(
+                    &mut app.clipboard,
+                    &mut gui.msg_dialog,
+                    &app.edit_state.cursor.to_string(),
+                );
             }
             if ui.button("Copy absolute").on_hover_text("Hard seek + cursor").clicked() {
-                let result = app.clipboard.set_text(
-                    (app.edit_state.cursor + app.src_args.hard_seek.unwrap_or(0)).to_string(),
+                crate::app::set_clipboard_string(
+                    &mut app.clipboard,
+                    &mut gui.msg_dialog,
+                    &(app.edit_state.cursor + app.src_args.hard_seek.unwrap_or(0)).to_string(),
                 );
-                msg_if_fail(result, "Failed to set clipboard text", &mut gui.msg_dialog);
             }
         });
         if re.clicked() {
@@ -68,10 +68,16 @@ impl Dialog for LuaFillDialog {
             let chunk = lua.load(&app.meta_state.meta.misc.fill_lua_script);
             let res = try {
                 let f = chunk.eval::<Function>()?;
-                for (i, b) in app.data[sel.begin..=sel.end].iter_mut().enumerate() {
-                    *b = f.call((i, *b))?;
+                if let Some(result) =
+                    app.data.edit(sel.begin..sel.end + 1, |data| -> mlua::Result<()> {
+                        for (i, b) in data.iter_mut().enumerate() {
+                            *b = f.call((i, *b))?;
+                        }
+                        Ok(())
+                    })
+                {
+                    result?;
                 }
-                app.data.dirty_region = Some(sel);
             };
             if let Err(e) = res {
                 self.result_info_string = e.to_string();
@@ -1,7 +1,6 @@
 use {
     crate::{
         app::App,
-        damage_region::DamageRegion,
         find_util,
         gui::{Dialog, message_dialog::Icon},
         slice_ext::SliceExt as _,
@@ -47,12 +46,14 @@ impl Dialog for PatternFillDialog {
                 Ok(values) => {
                     for reg in app.hex_ui.selected_regions() {
                         let range = reg.to_range();
-                        let Some(data_slice) = app.data.get_mut(range.clone()) else {
+                        if app
+                            .data
+                            .edit(reg.begin..reg.end + 1, |data| data.pattern_fill(&values))
+                            .is_none()
+                        {
                             gui.msg_dialog.open(Icon::Error, "Pattern fill error", format!("Invalid range for fill.\nRequested range: {range:?}\nData length: {}", app.data.len()));
                             return false;
-                        };
-                        data_slice.pattern_fill(&values);
-                        app.data.widen_dirty_region(DamageRegion::RangeInclusive(range));
+                        }
                     }
                     false
                 }
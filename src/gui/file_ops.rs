@@ -2,8 +2,13 @@ use {
     crate::{
         app::App,
         args::{MmapMode, SourceArgs},
-        gui::{message_dialog::MessageDialog, windows::FileDiffResultWindow},
+        binfmt,
+        gui::{
+            message_dialog::MessageDialog,
+            windows::{FileDiffResultWindow, PatchWindow},
+        },
         meta::{ViewKey, region::Region},
+        patch::PatchFormat,
         result_ext::AnyhowConv as _,
         shell::{msg_fail, msg_if_fail},
         source::Source,
@@ -78,15 +83,29 @@ impl<V> PathCache<V> {
 pub enum FileOp {
     LoadMetaFile,
     LoadFile,
+    LoadFileNewTab,
     LoadPaletteForView(ViewKey),
     LoadPaletteFromImageForView(ViewKey),
     DiffWithFile,
+    CompareWithFile,
+    /// Create a patch from the picked file to the data
+    CreatePatchFrom(PatchFormat),
+    LoadPatch,
     LoadLuaScript,
     SavePaletteForView(ViewKey),
     SaveFileAs,
     SaveLuaScript,
     SaveMetaFileAs,
+    SavePatch(Vec<u8>),
     SaveSelectionToFile(Region),
+    SaveStructTableCsv(String),
+    /// Save regions of the data as files named by the strings, into the picked directory
+    ExtractFiles(Vec<(Region, String)>),
+    Export {
+        format: binfmt::Format,
+        /// Export only this region instead of the whole data
+        region: Option<Region>,
+    },
 }
 
 impl FileOps {
@@ -96,6 +115,7 @@ impl FileOps {
         app: &mut App,
         msg: &mut MessageDialog,
         file_diff_result_window: &mut FileDiffResultWindow,
+        patch_window: &mut PatchWindow,
         font_size: u16,
         line_spacing: u16,
     ) {
@@ -103,7 +123,7 @@ impl FileOps {
             let src_args = self
                 .op
                 .as_ref()
-                .is_some_and(|op| matches!(op, FileOp::LoadFile))
+                .is_some_and(|op| matches!(op, FileOp::LoadFile | FileOp::LoadFileNewTab))
                 .then_some(&mut self.file_dialog_source_args);
             right_panel_ui(ui, dia, &mut self.preview_cache, src_args);
         });
@@ -129,6 +149,15 @@ impl FileOps {
                         None,
                     );
                 }
+                FileOp::LoadFileNewTab => {
+                    self.file_dialog_source_args.file = Some(path);
+                    app.load_file_args_new_document(
+                        self.file_dialog_source_args.clone(),
+                        msg,
+                        font_size,
+                        line_spacing,
+                    );
+                }
                 FileOp::LoadPaletteForView(key) => match value_color::load_palette(&path) {
                     Ok(pal) => {
                         let view = &mut app.meta_state.meta.views[key].view;
@@ -166,6 +195,19 @@ impl FileOps {
                         msg,
                     );
                 }
+                FileOp::CompareWithFile => {
+                    msg_if_fail(app.open_compare(path), "Failed to open for comparing", msg);
+                }
+                FileOp::CreatePatchFrom(format) => {
+                    patch_window.format = format;
+                    match patch_window.create(app, &path) {
+                        Ok(patch) => self.save_patch(patch),
+                        Err(e) => msg_fail(&e, "Failed to create patch", msg),
+                    }
+                }
+                FileOp::LoadPatch => {
+                    msg_if_fail(patch_window.load(app, path), "Failed to load patch", msg);
+                }
                 FileOp::LoadLuaScript => {
                     let res = try {
                         app.meta_state.meta.misc.exec_lua_script =
@@ -206,6 +248,7 @@ impl FileOps {
                             stream_buffer_size: None,
                             unsafe_mmap: None,
                             mmap_len: None,
+                            raw: false,
                         });
                     };
                     msg_if_fail(result, "Failed to save as", msg);
@@ -224,10 +267,39 @@ impl FileOps {
                         msg,
                     );
                 }
+                FileOp::SavePatch(patch) => {
+                    msg_if_fail(std::fs::write(path, patch), "Failed to save patch", msg);
+                }
                 FileOp::SaveSelectionToFile(sel) => {
                     let result = std::fs::write(path, &app.data[sel.begin..=sel.end]);
                     msg_if_fail(result, "Failed to save selection to file", msg);
                 }
+                FileOp::SaveStructTableCsv(csv) => {
+                    msg_if_fail(std::fs::write(path, csv), "Failed to export CSV", msg);
+                }
+                FileOp::ExtractFiles(files) => {
+                    let result: anyhow::Result<()> = try {
+                        for (reg, name) in files {
+                            let data = app
+                                .data
+                                .get(reg.begin..=reg.end)
+                                .context("Region is out of bounds")?;
+                            std::fs::write(path.join(name), data)?;
+                        }
+                    };
+                    msg_if_fail(result, "Failed to extract files", msg);
+                }
+                FileOp::Export { format, region } => {
+                    let result: anyhow::Result<()> = try {
+                        let text = app.encode_data(format, region)?;
+                        std::fs::write(path, text)?;
+                    };
+                    msg_if_fail(
+                        result,
+                        &format!("Failed to export as {}", format.label()),
+                        msg,
+                    );
+                }
             }
         }
     }
@@ -241,6 +313,16 @@ impl FileOps {
         self.dialog.pick_file();
         self.op = Some(FileOp::LoadFile);
     }
+    pub fn load_file_new_tab(&mut self, source_file: Option<&Path>) {
+        if let Some(path) = source_file
+            && let Some(parent) = path.parent()
+        {
+            let cfg = self.dialog.config_mut();
+            parent.clone_into(&mut cfg.initial_directory);
+        }
+        self.dialog.pick_file();
+        self.op = Some(FileOp::LoadFileNewTab);
+    }
     pub fn load_meta_file(&mut self) {
         self.dialog.pick_file();
         self.op = Some(FileOp::LoadMetaFile);
@@ -266,6 +348,36 @@ impl FileOps {
         self.op = Some(FileOp::DiffWithFile);
     }
 
+    pub fn compare_with_file(&mut self, source_file: Option<&Path>) {
+        if let Some(path) = source_file
+            && let Some(parent) = path.parent()
+        {
+            self.dialog.config_mut().initial_directory = parent.to_owned();
+        }
+        self.dialog.pick_file();
+        self.op = Some(FileOp::CompareWithFile);
+    }
+
+    pub fn create_patch_from(&mut self, format: PatchFormat, source_file: Option<&Path>) {
+        if let Some(path) = source_file
+            && let Some(parent) = path.parent()
+        {
+            self.dialog.config_mut().initial_directory = parent.to_owned();
+        }
+        self.dialog.pick_file();
+        self.op = Some(FileOp::CreatePatchFrom(format));
+    }
+
+    pub fn load_patch(&mut self, source_file: Option<&Path>) {
+        if let Some(path) = source_file
+            && let Some(parent) = path.parent()
+        {
+            self.dialog.config_mut().initial_directory = parent.to_owned();
+        }
+        self.dialog.pick_file();
+        self.op = Some(FileOp::LoadPatch);
+    }
+
     pub fn load_lua_script(&mut self) {
         self.dialog.pick_file();
         self.op = Some(FileOp::LoadLuaScript);
@@ -291,10 +403,30 @@ impl FileOps {
         self.op = Some(FileOp::SaveMetaFileAs);
     }
 
+    pub(crate) fn save_patch(&mut self, patch: Vec<u8>) {
+        self.dialog.save_file();
+        self.op = Some(FileOp::SavePatch(patch));
+    }
+
     pub(crate) fn save_selection_to_file(&mut self, region: Region) {
         self.dialog.save_file();
         self.op = Some(FileOp::SaveSelectionToFile(region));
     }
+
+    pub(crate) fn export(&mut self, format: binfmt::Format, region: Option<Region>) {
+        self.dialog.save_file();
+        self.op = Some(FileOp::Export { format, region });
+    }
+
+    pub(crate) fn extract_files(&mut self, files: Vec<(Region, String)>) {
+        self.dialog.pick_directory();
+        self.op = Some(FileOp::ExtractFiles(files));
+    }
+
+    pub(crate) fn save_struct_table_csv(&mut self, csv: String) {
+        self.dialog.save_file();
+        self.op = Some(FileOp::SaveStructTableCsv(csv));
+    }
 }
 
 fn right_panel_ui(
@@ -389,6 +521,8 @@ fn src_args_ui(ui: &mut egui::Ui, src_args: &mut SourceArgs) {
     {
         src_args.read_only = src_args.stream;
     }
+    ui.checkbox(&mut src_args.raw, "raw")
+        .on_hover_text("Open Intel HEX, S-record and base64 files as they are, without decoding");
     ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
     opt(
         ui,
@@ -423,7 +557,7 @@ const DANGEROUS_MUT_LABEL: &str = "⚠ WARNING ⚠\n\
 \n\
 File will be opened with a direct mutable memory map.
 Any changes made to the file will be IMMEDIATE.
-THERE IS NO WAY TO UNDO ANY CHANGES.
+Changes can only be undone while the file is open in this session.
 ";
 
 fn opt<V: Default>(
@@ -2,7 +2,7 @@ use {
     super::message_dialog::{Icon, MessageDialog},
     crate::{
         app::{App, interact_mode::InteractMode},
-        damage_region::DamageRegion,
+        data::Data,
         result_ext::AnyhowConv as _,
         shell::msg_if_fail,
         view::ViewportVec,
@@ -66,6 +66,17 @@ impl SeekRelativity {
     }
 }
 
+impl InspectPanel {
+    /// What to add to data offsets to make them relative to the seek relativity
+    fn seek_add(&self, app: &App) -> usize {
+        match self.seek_relativity {
+            SeekRelativity::Absolute => 0,
+            SeekRelativity::HardSeek => app.src_args.hard_seek.unwrap_or(0),
+            SeekRelativity::User => self.seek_user_offs,
+        }
+    }
+}
+
 impl std::fmt::Debug for InspectPanel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("InspectPanel").finish()
@@ -103,14 +114,15 @@ trait InputThingyTrait {
     fn update(&mut self, data: &[u8], offset: usize, be: bool, format: Format);
     fn label(&self) -> &'static str;
     fn buf_mut(&mut self) -> &mut String;
+    /// Returns whether the data was written
     fn write_data(
         &self,
-        data: &mut [u8],
+        data: &mut Data,
         offset: usize,
         be: bool,
         format: Format,
         msg: &mut MessageDialog,
-    ) -> Option<DamageRegion>;
+    ) -> bool;
 }
 
 impl<T: BytesManip> InputThingyTrait for InputThingy<T> {
@@ -127,12 +139,12 @@ impl<T: BytesManip> InputThingyTrait for InputThingy<T> {
 
     fn write_data(
         &self,
-        data: &mut [u8],
+        data: &mut Data,
         offset: usize,
         be: bool,
         format: Format,
         msg: &mut MessageDialog,
-    ) -> Option<DamageRegion> {
+    ) -> bool {
         T::convert_and_write(&self.string, data, offset, be, format, msg)
     }
 }
@@ -336,12 +348,12 @@ impl<T: NumBytesManip> BytesManip for T {
 
     fn convert_and_write(
         buf: &str,
-        data: &mut [u8],
+        data: &mut Data,
         offset: usize,
         be: bool,
         format: Format,
         msg: &mut MessageDialog,
-    ) -> Option<DamageRegion> {
+    ) -> bool {
         match Self::from_str(buf, format) {
             Ok(this) => {
                 let bytes = if be {
@@ -349,18 +361,11 @@ impl<T: NumBytesManip> BytesManip for T {
                 } else {
                     this.to_le_bytes()
                 };
-                let range = offset..offset + bytes.as_ref().len();
-                match data.get_mut(range.clone()) {
-                    Some(slice) => {
-                        slice.copy_from_slice(bytes.as_ref());
-                        Some(DamageRegion::Range(range))
-                    }
-                    None => None,
-                }
+                data.write(offset, bytes.as_ref())
             }
             Err(e) => {
                 msg.open(Icon::Error, "Convert error", e.to_string());
-                None
+                false
             }
         }
     }
@@ -383,27 +388,21 @@ impl BytesManip for Ascii {
 
     fn convert_and_write(
         buf: &str,
-        data: &mut [u8],
+        data: &mut Data,
         offset: usize,
         _be: bool,
         _format: Format,
         msg: &mut MessageDialog,
-    ) -> Option<DamageRegion> {
-        let len = buf.len();
-        let range = offset..offset + len;
-        match data.get_mut(range.clone()) {
-            Some(slice) => {
-                slice.copy_from_slice(buf.as_bytes());
-                Some(DamageRegion::Range(range))
-            }
-            None => {
-                msg.open(
-                    Icon::Error,
-                    "Convert and write error",
-                    "Failed to write data: Out of bounds",
-                );
-                None
-            }
+    ) -> bool {
+        if data.write(offset, buf.as_bytes()) {
+            true
+        } else {
+            msg.open(
+                Icon::Error,
+                "Convert and write error",
+                "Failed to write data: Out of bounds",
+            );
+            false
         }
     }
 }
@@ -425,21 +424,21 @@ impl<T> Default for InputThingy<T> {
 trait BytesManip {
     fn update_buf(buf: &mut String, data: &[u8], offset: usize, be: bool, format: Format);
     fn label() -> &'static str;
+    /// Returns whether the data was written
     fn convert_and_write(
         buf: &str,
-        data: &mut [u8],
+        data: &mut Data,
         offset: usize,
         be: bool,
         format: Format,
         msg: &mut MessageDialog,
-    ) -> Option<DamageRegion>;
+    ) -> bool;
 }
 
 struct Ascii;
 
 enum Action {
     GoToOffset(usize),
-    AddDirty(DamageRegion),
     JumpForward(usize),
 }
 
@@ -448,19 +447,12 @@ pub fn ui(ui: &mut Ui, app: &mut App, gui: &mut crate::gui::Gui, mouse_pos: View
         ui.label("No active layout");
         return;
     }
+    let mut hovered = false;
     let offset = match app.hex_ui.interact_mode {
         InteractMode::View if !ui.egui_wants_pointer_input() => {
             if let Some((off, _view_idx)) = app.byte_offset_at_pos(mouse_pos.x, mouse_pos.y) {
-                let mut add = 0;
-                match gui.inspect_panel.seek_relativity {
-                    SeekRelativity::Absolute => {}
-                    SeekRelativity::HardSeek => {
-                        add = app.src_args.hard_seek.unwrap_or(0);
-                    }
-                    SeekRelativity::User => {
-                        add = gui.inspect_panel.seek_user_offs;
-                    }
-                }
+                hovered = true;
+                let add = gui.inspect_panel.seek_add(app);
                 ui.link(format!("offset: {} (0x{:x})", off + add, off + add))
                     .context_menu(|ui| {
                         if ui.button("Copy to clipboard").clicked() {
@@ -509,6 +501,18 @@ pub fn ui(ui: &mut Ui, app: &mut App, gui: &mut crate::gui::Gui, mouse_pos: View
     if app.data.is_empty() {
         return;
     }
+    // The bit under the mouse, or under the bit cursor, if it's a binary view
+    let bit = if hovered {
+        app.view_idx_at_pos(mouse_pos.x, mouse_pos.y).and_then(|key| {
+            app.meta_state.meta.views.get(key)?.view.bit_at_pos(mouse_pos.x, mouse_pos.y)
+        })
+    } else {
+        app.hex_ui
+            .focused_view
+            .and_then(|key| app.meta_state.meta.views.get(key)?.view.bit_cursor())
+    };
+    let bit_base = (offset + gui.inspect_panel.seek_add(app)) * 8;
+    bits_ui(ui, &mut app.data, offset, bit_base, bit);
     for thingy in &mut gui.inspect_panel.input_thingies {
         thingy.update(
             &app.data[..],
@@ -554,7 +558,7 @@ pub fn ui(ui: &mut Ui, app: &mut App, gui: &mut crate::gui::Gui, mouse_pos: View
         });
         if ui.text_edit_singleline(thingy.buf_mut()).lost_focus()
             && ui.input(|inp| inp.key_pressed(egui::Key::Enter))
-            && let Some(range) = thingy.write_data(
+            && thingy.write_data(
                 &mut app.data,
                 offset,
                 gui.inspect_panel.big_endian,
@@ -563,7 +567,6 @@ pub fn ui(ui: &mut Ui, app: &mut App, gui: &mut crate::gui::Gui, mouse_pos: View
             )
         {
             gui.inspect_panel.changed_one = true;
-            actions.push(Action::AddDirty(range));
         }
     }
     ui.horizontal(|ui| {
@@ -615,7 +618,6 @@ pub fn ui(ui: &mut Ui, app: &mut App, gui: &mut crate::gui::Gui, mouse_pos: View
                 app.center_view_on_offset(app.edit_state.cursor);
                 app.hex_ui.flash_cursor();
             }
-            Action::AddDirty(damage) => app.data.widen_dirty_region(damage),
             Action::JumpForward(amount) => {
                 app.edit_state.set_cursor(app.edit_state.cursor + amount);
                 app.center_view_on_offset(app.edit_state.cursor);
@@ -627,16 +629,7 @@ pub fn ui(ui: &mut Ui, app: &mut App, gui: &mut crate::gui::Gui, mouse_pos: View
 }
 
 fn edit_offset(app: &mut App, gui: &mut crate::gui::Gui, ui: &mut Ui) -> usize {
-    let mut off = app.edit_state.cursor;
-    match gui.inspect_panel.seek_relativity {
-        SeekRelativity::Absolute => {}
-        SeekRelativity::HardSeek => {
-            off += app.src_args.hard_seek.unwrap_or(0);
-        }
-        SeekRelativity::User => {
-            off += gui.inspect_panel.seek_user_offs;
-        }
-    }
+    let off = app.edit_state.cursor + gui.inspect_panel.seek_add(app);
     ui.link(format!("offset: {off} ({off:x}h)")).context_menu(|ui| {
         if ui.button("Copy to clipboard").clicked() {
             crate::app::set_clipboard_string(
@@ -649,6 +642,43 @@ fn edit_offset(app: &mut App, gui: &mut crate::gui::Gui, ui: &mut Ui) -> usize {
     app.edit_state.cursor
 }
 
+/// The bits of the byte at `offset`, most significant first. Clicking a bit flips it.
+///
+/// `bit_base` is the bit offset of the byte, and `bit` the bit to show the offset of.
+fn bits_ui(ui: &mut Ui, data: &mut Data, offset: usize, bit_base: usize, bit: Option<u8>) {
+    let Some(&byte) = data.get(offset) else {
+        return;
+    };
+    let mut flip = None;
+    ui.horizontal(|ui| {
+        ui.label("bits");
+        for i in 0..8u8 {
+            let text = if byte & (0x80 >> i) == 0 { "0" } else { "1" };
+            let bit_off = bit_base + usize::from(i);
+            if ui
+                .selectable_label(bit == Some(i), text)
+                .on_hover_text(format!(
+                    "bit {}, bit offset {bit_off} ({bit_off:x}h)",
+                    7 - i
+                ))
+                .clicked()
+            {
+                flip = Some(i);
+            }
+        }
+    });
+    if let Some(i) = bit {
+        let bit_off = bit_base + usize::from(i);
+        ui.label(format!(
+            "bit {}, bit offset: {bit_off} ({bit_off:x}h)",
+            7 - i
+        ));
+    }
+    if let Some(i) = flip {
+        data.edit(offset..offset + 1, |bytes| bytes[0] ^= 0x80 >> i);
+    }
+}
+
 fn find_valid_ascii_end(data: &[u8]) -> usize {
     // Don't try to take too many characters, as that degrades performance
     const MAX_TAKE: usize = 50;
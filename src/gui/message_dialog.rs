@@ -76,6 +76,10 @@ impl MessageDialog {
             close: false,
         });
     }
+    /// Take all the pending messages, for reporting them when there is no gui to show them in
+    pub(crate) fn take_payloads(&mut self) -> impl Iterator<Item = Payload> {
+        std::mem::take(&mut self.payloads).into_iter()
+    }
     pub(crate) fn custom_button_row_ui(&mut self, f: Box<UiFn>) {
         if let Some(front) = self.payloads.front_mut() {
             front.buttons_ui_fn = Some(f);
@@ -84,7 +88,7 @@ impl MessageDialog {
     pub(crate) fn show(
         &mut self,
         ctx: &egui::Context,
-        cb: &mut arboard::Clipboard,
+        cb: &mut Option<arboard::Clipboard>,
         cmd: &mut CommandQueue,
     ) {
         let payloads_len = self.payloads.len();
@@ -113,6 +117,7 @@ impl MessageDialog {
                             )
                             .on_hover_text(payload.icon.hover_text())
                             .clicked()
+                        && let Some(cb) = cb
                         && let Err(e) = cb.set_text(payload.desc.clone())
                     {
                         gamedebug_core::per!("Clipboard set error: {e:?}");
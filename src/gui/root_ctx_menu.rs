@@ -12,6 +12,7 @@ const L_CHANGE_THIS_VIEW: &str = concat!(ic::SWAP, " Change this view to");
 const L_REMOVE_FROM_LAYOUT: &str = concat!(ic::TRASH, " Remove from layout");
 const L_OPEN_BOOKMARK: &str = concat!(ic::BOOKMARK, " Open bookmark");
 const L_ADD_BOOKMARK: &str = concat!(ic::BOOKMARK, " Add bookmark");
+const L_FREEZE_SELECTION: &str = concat!(ic::LOCK, " Freeze selection");
 const L_LAYOUT_PROPS: &str = concat!(ic::LAYOUT, " Layout properties...");
 const L_LAYOUTS: &str = concat!(ic::LAYOUT, " Layouts");
 
@@ -71,6 +72,7 @@ fn menu_inner_ui(
             &mut gui.dialogs,
             &mut gui.msg_dialog,
             &mut gui.win.regions,
+            &mut gui.win.decompress,
             sel,
             &mut gui.fileops,
         ) {
@@ -134,6 +136,15 @@ fn menu_inner_ui(
                 }
             }
         }
+        if let Some(sel) = app.hex_ui.selection()
+            && ui
+                .button(L_FREEZE_SELECTION)
+                .on_hover_text("Write the selected bytes back after every reload")
+                .clicked()
+        {
+            crate::gui::add_frozen_bookmark(app, gui, sel);
+            *close = true;
+        }
     }
     ui.separator();
     if ui.button(L_LAYOUT_PROPS).clicked() {
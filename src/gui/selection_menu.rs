@@ -1,25 +1,27 @@
 use {
     crate::{
         app::App,
-        damage_region::DamageRegion,
+        binfmt,
         gui::{
             Gui,
             dialogs::{LuaFillDialog, PatternFillDialog, X86AsmDialog},
             file_ops::FileOps,
             message_dialog::MessageDialog,
-            windows::RegionsWindow,
+            windows::{DecompressWindow, RegionsWindow},
         },
-        shell::msg_fail,
+        shell::{msg_fail, msg_if_fail},
     },
     constcat::concat,
     egui::Button,
     egui_phosphor::regular as ic,
     rand::Rng as _,
     std::fmt::Write as _,
+    strum::IntoEnumIterator as _,
 };
 
 const L_UNSELECT: &str = concat!(ic::SELECTION_SLASH, " Unselect");
 const L_ZERO_FILL: &str = concat!(ic::NUMBER_SQUARE_ZERO, " Zero fill");
+const L_DELETE: &str = concat!(ic::BACKSPACE, " Delete (shift bytes back)");
 const L_PATTERN_FILL: &str = concat!(ic::BINARY, " Pattern fill...");
 const L_LUA_FILL: &str = concat!(ic::MOON, " Lua fill...");
 const L_RANDOM_FILL: &str = concat!(ic::SHUFFLE, " Random fill");
@@ -27,6 +29,8 @@ const L_COPY_AS_HEX_TEXT: &str = concat!(ic::COPY, " Copy as hex text");
 const L_COPY_AS_UTF8: &str = concat!(ic::COPY, " Copy as utf-8 text");
 const L_ADD_AS_REGION: &str = concat!(ic::RULER, " Add as region");
 const L_SAVE_TO_FILE: &str = concat!(ic::FLOPPY_DISK, " Save to file");
+const L_EXPORT_AS: &str = concat!(ic::EXPORT, " Export as");
+const L_DECOMPRESS: &str = concat!(ic::FILE_ZIP, " Decompress...");
 const L_X86_ASM: &str = concat!(ic::PIPE_WRENCH, " X86 asm");
 
 /// Returns whether anything was clicked
@@ -37,6 +41,7 @@ pub fn selection_menu(
     gui_dialogs: &mut crate::gui::Dialogs,
     gui_msg_dialog: &mut MessageDialog,
     gui_regions_window: &mut RegionsWindow,
+    gui_decompress_window: &mut DecompressWindow,
     sel: crate::meta::region::Region,
     file_ops: &mut FileOps,
 ) -> bool {
@@ -52,6 +57,15 @@ pub fn selection_menu(
 
             clicked = true;
         }
+        if ui.add(Button::new(L_DELETE).shortcut_text("Del (insert mode)")).clicked() {
+            msg_if_fail(
+                app.delete_selection_or_cursor(),
+                "Failed to delete",
+                gui_msg_dialog,
+            );
+
+            clicked = true;
+        }
         if ui.button(L_PATTERN_FILL).clicked() {
             Gui::add_dialog(gui_dialogs, PatternFillDialog::default());
 
@@ -64,10 +78,9 @@ pub fn selection_menu(
         }
         if ui.button(L_RANDOM_FILL).clicked() {
             for region in app.hex_ui.selected_regions() {
-                if let Some(data) = app.data.get_mut(region.to_range()) {
+                app.data.edit(region.begin..region.end + 1, |data| {
                     rand::rng().fill_bytes(data);
-                    app.data.widen_dirty_region(DamageRegion::RangeInclusive(region.to_range()));
-                }
+                });
             }
 
             clicked = true;
@@ -114,6 +127,24 @@ pub fn selection_menu(
 
             clicked = true;
         }
+        ui.menu_button(L_EXPORT_AS, |ui| {
+            for format in binfmt::Format::iter() {
+                if ui.button(format.label()).clicked() {
+                    file_ops.export(format, Some(sel));
+
+                    clicked = true;
+                }
+            }
+        });
+        if ui
+            .button(L_DECOMPRESS)
+            .on_hover_text("Decompress into a side buffer, which can be edited and written back")
+            .clicked()
+        {
+            gui_decompress_window.decompress_selection(app, sel, gui_msg_dialog);
+
+            clicked = true;
+        }
         if ui.button(L_X86_ASM).clicked() {
             Gui::add_dialog(gui_dialogs, X86AsmDialog::new());
 
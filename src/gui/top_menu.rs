@@ -43,7 +43,7 @@ pub fn top_menu(
             |ui| match &app.source {
                 Some(src) => {
                     match src.provider {
-                        SourceProvider::File(_) => {
+                        SourceProvider::File(_) | SourceProvider::Encoded { .. } => {
                             match &app.src_args.file {
                                 Some(file) => {
                                     let s = file.display().to_string();
@@ -130,6 +130,36 @@ pub fn top_menu(
                         SourceProvider::WinProc { handle, .. } => {
                             ui.label(format!("Windows process: {:p}", handle));
                         }
+                        #[cfg(target_os = "linux")]
+                        SourceProvider::LinuxProc {
+                            pid, start, size, ..
+                        } => {
+                            ui.label(format!(
+                                "Linux process {pid}: {start:#x}..{:#x}",
+                                start + size
+                            ));
+                        }
+                    }
+                    if let SourceProvider::Encoded {
+                        format,
+                        base,
+                        segments,
+                    } = &src.provider
+                    {
+                        let gaps = crate::binfmt::gaps(segments, app.data.len());
+                        let re = ui.label(format!("[{} @ {base:#x}]", format.label()));
+                        if !gaps.is_empty() {
+                            re.on_hover_ui(|ui| {
+                                ui.label("Unmapped gaps, filled with 0xFF and not written back:");
+                                for gap in gaps {
+                                    ui.label(format!(
+                                        "{:#x}..{:#x}",
+                                        base + gap.start,
+                                        base + gap.end
+                                    ));
+                                }
+                            });
+                        }
                     }
                     if src.attr.stream {
                         if src.state.stream_end {
@@ -12,10 +12,17 @@ const L_DETERMINE_DATA_MIME: &str =
     concat!(ic::SEAL_QUESTION, " Determine data mime type under cursor");
 const L_DETERMINE_DATA_MIME_SEL: &str =
     concat!(ic::SEAL_QUESTION, " Determine data mime type of selection");
+const L_CARVE_FILES: &str = concat!(ic::FILE_MAGNIFYING_GLASS, " Carve embedded files...");
 const L_DIFF_WITH_FILE: &str = concat!(ic::GIT_DIFF, " Diff with file...");
 const L_DIFF_WITH_SOURCE_FILE: &str = concat!(ic::GIT_DIFF, " Diff with source file");
 const L_DIFF_WITH_BACKUP: &str = concat!(ic::GIT_DIFF, " Diff with backup");
+const L_COMPARE_WITH_FILE: &str = concat!(ic::COLUMNS, " Compare side by side...");
+const L_PATCH: &str = concat!(ic::BANDAIDS, " IPS/BPS patch...");
 const L_FIND_MEMORY_POINTERS: &str = concat!(ic::ARROW_UP_RIGHT, " Find memory pointers...");
+const L_MEMORY_SCANNER: &str = concat!(ic::BINOCULARS, " Memory scanner...");
+const L_CHECKSUMS: &str = concat!(ic::HASH, " Checksums...");
+const L_ENTROPY: &str = concat!(ic::CHART_LINE, " Entropy...");
+const L_STRINGS: &str = concat!(ic::TEXT_AA, " Strings...");
 const L_ZERO_PARTITION: &str = concat!(ic::BINARY, " Zero partition...");
 
 pub fn ui(ui: &mut egui::Ui, gui: &mut Gui, app: &App) {
@@ -35,6 +42,20 @@ pub fn ui(ui: &mut egui::Ui, gui: &mut Gui, app: &App) {
             tree_magic_mini::from_u8(&app.data[region.begin..=region.end]).to_string(),
         );
     }
+    if ui
+        .button(L_CARVE_FILES)
+        .on_hover_text("Find files embedded in the data by their magic numbers")
+        .clicked()
+    {
+        gui.win.carve.open.toggle();
+    }
+    if ui
+        .button(L_CHECKSUMS)
+        .on_hover_text("Checksums and hashes of a range")
+        .clicked()
+    {
+        gui.win.checksum.open.toggle();
+    }
     ui.separator();
     if ui.button(L_DIFF_WITH_FILE).clicked() {
         gui.fileops.diff_with_file(app.source_file());
@@ -63,6 +84,37 @@ pub fn ui(ui: &mut egui::Ui, gui: &mut Gui, app: &App) {
             ui.add_enabled(false, egui::Button::new(L_DIFF_WITH_BACKUP));
         }
     }
+    if ui
+        .button(L_COMPARE_WITH_FILE)
+        .on_hover_text("Show another file next to the data, scrolling together")
+        .clicked()
+    {
+        if app.compare.is_none() {
+            gui.fileops.compare_with_file(app.source_file());
+        }
+        gui.win.compare.open.set(true);
+    }
+    if ui
+        .button(L_PATCH)
+        .on_hover_text("Create a patch from the changes to the data, or apply one to it")
+        .clicked()
+    {
+        gui.win.patch.open.toggle();
+    }
+    if ui
+        .button(L_ENTROPY)
+        .on_hover_text("Entropy graph and byte histogram, to find compressed or encrypted data")
+        .clicked()
+    {
+        gui.win.entropy.open.toggle();
+    }
+    if ui
+        .button(L_STRINGS)
+        .on_hover_text("List printable strings in the data")
+        .clicked()
+    {
+        gui.win.strings.open.toggle();
+    }
     ui.separator();
     if ui
         .add_enabled(
@@ -74,6 +126,13 @@ pub fn ui(ui: &mut egui::Ui, gui: &mut Gui, app: &App) {
     {
         gui.win.find_memory_pointers.open.toggle();
     }
+    if ui
+        .button(L_MEMORY_SCANNER)
+        .on_hover_text("Narrow down where a value is by scanning as it changes")
+        .clicked()
+    {
+        gui.win.scanner.open.toggle();
+    }
     if ui
         .button(L_ZERO_PARTITION)
         .on_hover_text("Find regions of non-zero data separated by zeroed regions")
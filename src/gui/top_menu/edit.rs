@@ -14,6 +14,8 @@ use {
     mlua::Lua,
 };
 
+const L_UNDO: &str = concat!(ic::ARROW_COUNTER_CLOCKWISE, " Undo");
+const L_REDO: &str = concat!(ic::ARROW_CLOCKWISE, " Redo");
 const L_FIND: &str = concat!(ic::MAGNIFYING_GLASS, " Find...");
 const L_SELECTION: &str = concat!(ic::SELECTION, " Selection");
 const L_SELECT_A: &str = "🅰 Set select a";
@@ -35,6 +37,27 @@ pub fn ui(
     font_size: u16,
     line_spacing: u16,
 ) {
+    if ui
+        .add_enabled(
+            app.data.history.can_undo(),
+            Button::new(L_UNDO).shortcut_text("Ctrl+Z"),
+        )
+        .on_hover_text(format!("{} edit(s) to undo", app.data.history.undo_len()))
+        .clicked()
+    {
+        app.undo();
+    }
+    if ui
+        .add_enabled(
+            app.data.history.can_redo(),
+            Button::new(L_REDO).shortcut_text("Ctrl+Y"),
+        )
+        .on_hover_text(format!("{} edit(s) to redo", app.data.history.redo_len()))
+        .clicked()
+    {
+        app.redo();
+    }
+    ui.separator();
     if ui.add(Button::new(L_FIND).shortcut_text("Ctrl+F")).clicked() {
         gui.win.find.open.toggle();
     }
@@ -48,6 +71,7 @@ pub fn ui(
                 &mut gui.dialogs,
                 &mut gui.msg_dialog,
                 &mut gui.win.regions,
+                &mut gui.win.decompress,
                 sel,
                 &mut gui.fileops,
             ) {}
@@ -1,19 +1,23 @@
 use {
     crate::{
         app::{App, set_clipboard_string},
+        binfmt,
         gui::{Gui, dialogs::AutoSaveReloadDialog},
         shell::msg_if_fail,
     },
     constcat::concat,
     egui::Button,
     egui_phosphor::regular as ic,
+    strum::IntoEnumIterator as _,
 };
 
 const L_LOPEN: &str = concat!(ic::FOLDER_OPEN, " Open...");
+const L_OPEN_NEW_TAB: &str = concat!(ic::FOLDER_PLUS, " Open in new tab...");
 const L_OPEN_PROCESS: &str = concat!(ic::CPU, " Open process...");
 const L_OPEN_PREVIOUS: &str = concat!(ic::ARROWS_LEFT_RIGHT, " Open previous");
 const L_SAVE: &str = concat!(ic::FLOPPY_DISK, " Save");
 const L_SAVE_AS: &str = concat!(ic::FLOPPY_DISK_BACK, " Save as...");
+const L_EXPORT_AS: &str = concat!(ic::EXPORT, " Export as");
 const L_RELOAD: &str = concat!(ic::ARROW_COUNTER_CLOCKWISE, " Reload");
 const L_RECENT: &str = concat!(ic::CLOCK_COUNTER_CLOCKWISE, " Recent");
 const L_AUTO_SAVE_RELOAD: &str = concat!(ic::MAGNET, " Auto save/reload...");
@@ -27,6 +31,9 @@ pub fn ui(ui: &mut egui::Ui, gui: &mut Gui, app: &mut App, font_size: u16, line_
     if ui.add(Button::new(L_LOPEN).shortcut_text("Ctrl+O")).clicked() {
         gui.fileops.load_file(app.source_file());
     }
+    if ui.button(L_OPEN_NEW_TAB).clicked() {
+        gui.fileops.load_file_new_tab(app.source_file());
+    }
     if ui.button(L_OPEN_PROCESS).clicked() {
         gui.win.open_process.open.toggle();
     }
@@ -105,6 +112,13 @@ pub fn ui(ui: &mut egui::Ui, gui: &mut Gui, app: &mut App, font_size: u16, line_
     if ui.button(L_SAVE_AS).clicked() {
         gui.fileops.save_file_as();
     }
+    ui.menu_button(L_EXPORT_AS, |ui| {
+        for format in binfmt::Format::iter() {
+            if ui.button(format.label()).clicked() {
+                gui.fileops.export(format, None);
+            }
+        }
+    });
     if ui.add(Button::new(L_RELOAD).shortcut_text("Ctrl+R")).clicked() {
         msg_if_fail(app.reload(), "Failed to reload", &mut gui.msg_dialog);
     }
@@ -132,7 +146,7 @@ pub fn ui(ui: &mut egui::Ui, gui: &mut Gui, app: &mut App, font_size: u16, line_
     }
     ui.separator();
     if ui.add(Button::new(L_CLOSE).shortcut_text("Ctrl+W")).clicked() {
-        app.close_file();
+        app.close_document(app.docs.active);
     }
     if ui.button(L_QUIT).clicked() {
         app.quit_requested = true;
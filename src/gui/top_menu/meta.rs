@@ -73,7 +73,10 @@ pub fn ui(ui: &mut egui::Ui, gui: &mut Gui, app: &mut App, font_size: u16, line_
         .clicked()
     {
         msg_if_fail(
-            app.consume_meta_from_file(crate::app::temp_metafile_backup_path(), true),
+            app.consume_meta_from_file(
+                crate::app::temp_metafile_backup_path(app.source_file()),
+                true,
+            ),
             "Failed to load temp metafile",
             &mut gui.msg_dialog,
         );
@@ -85,4 +85,6 @@ pub fn ui(ui: &mut egui::Ui, gui: &mut Gui, app: &mut App) {
         }
     }
     std::mem::swap(&mut app.plugins, &mut plugins);
+    // A plugin method might have modified the data through `get_data_mut`
+    app.commit_pending_edits();
 }
@@ -6,6 +6,7 @@ use {
     crate::{
         app::App,
         color::RgbColor,
+        shell::msg_if_fail,
         util::human_size,
         value_color::{ColorMethod, Palette},
     },
@@ -16,6 +17,9 @@ use {
 
 pub fn ui(ui: &mut Ui, gui: &mut Gui, app: &mut App, lua: &Lua, font_size: u16, line_spacing: u16) {
     top_menu(ui, gui, app, lua, font_size, line_spacing);
+    if app.docs.keys().len() > 1 {
+        doc_tabs(ui, gui, app);
+    }
     ui.horizontal(|ui| {
         if app.hex_ui.select_a.is_some() || app.hex_ui.select_b.is_some() {
             ui.label("Selection");
@@ -208,3 +212,38 @@ fn test_color_from_hexcode() {
     assert_eq!(color_from_hexcode("#ffffff").unwrap(), [255, 255, 255]);
     assert_eq!(color_from_hexcode("ff00ff").unwrap(), [255, 0, 255]);
 }
+
+/// Tabs for switching between the open documents
+fn doc_tabs(ui: &mut Ui, gui: &mut Gui, app: &mut App) {
+    let mut switch = None;
+    let mut close = None;
+    ui.horizontal(|ui| {
+        for &key in app.docs.keys() {
+            let mut title = app.doc_title(key);
+            if app.doc_data(key).is_some_and(|data| data.dirty_region.is_some()) {
+                title.push_str(" •");
+            }
+            let mut re = ui.selectable_label(key == app.docs.active, title);
+            if let Some(path) = app.doc_src_args(key).and_then(|args| args.file.as_ref()) {
+                re = re.on_hover_text(path.display().to_string());
+            }
+            if re.clicked() {
+                switch = Some(key);
+            }
+            if re.middle_clicked() || ui.small_button("🗙").on_hover_text("Close").clicked() {
+                close = Some(key);
+            }
+            ui.separator();
+        }
+    });
+    if let Some(key) = switch {
+        msg_if_fail(
+            app.switch_document(key),
+            "Failed to switch document",
+            &mut gui.msg_dialog,
+        );
+    }
+    if let Some(key) = close {
+        app.close_document(key);
+    }
+}
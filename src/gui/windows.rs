@@ -1,16 +1,21 @@
 pub use self::{
     file_diff_result::FileDiffResultWindow,
     lua_console::{ConMsg, LuaConsoleWindow},
+    patch::PatchWindow,
     regions::{RegionsWindow, region_context_menu},
 };
 use {
     self::{
-        about::AboutWindow, bookmarks::BookmarksWindow, external_command::ExternalCommandWindow,
-        find_dialog::FindDialog, find_memory_pointers::FindMemoryPointersWindow,
-        layouts::LayoutsWindow, lua_help::LuaHelpWindow, lua_watch::LuaWatchWindow,
-        meta_diff::MetaDiffWindow, open_process::OpenProcessWindow,
-        perspectives::PerspectivesWindow, preferences::PreferencesWindow,
-        script_manager::ScriptManagerWindow, structs::StructsWindow, vars::VarsWindow,
+        about::AboutWindow, aligned_diff::AlignedDiffWindow, bookmarks::BookmarksWindow,
+        carve::CarveWindow, checksum::ChecksumWindow, compare::CompareWindow,
+        decompress::DecompressWindow, entropy::EntropyWindow,
+        external_command::ExternalCommandWindow, find_dialog::FindDialog,
+        find_memory_pointers::FindMemoryPointersWindow, layouts::LayoutsWindow,
+        lua_help::LuaHelpWindow, lua_watch::LuaWatchWindow, meta_diff::MetaDiffWindow,
+        open_process::OpenProcessWindow, perspectives::PerspectivesWindow,
+        preferences::PreferencesWindow, scanner::ScannerWindow,
+        script_manager::ScriptManagerWindow, strings::StringsWindow,
+        struct_table::StructTableWindow, structs::StructsWindow, vars::VarsWindow,
         views::ViewsWindow, zero_partition::ZeroPartition,
     },
     super::Gui,
@@ -20,8 +25,14 @@ use {
 };
 
 mod about;
+mod aligned_diff;
 mod bookmarks;
+mod carve;
+mod checksum;
+mod compare;
 pub mod debug;
+mod decompress;
+mod entropy;
 mod external_command;
 mod file_diff_result;
 mod find_dialog;
@@ -33,10 +44,14 @@ mod lua_help;
 mod lua_watch;
 mod meta_diff;
 mod open_process;
+mod patch;
 mod perspectives;
 mod preferences;
 mod regions;
+mod scanner;
 mod script_manager;
+mod strings;
+mod struct_table;
 mod structs;
 mod vars;
 mod views;
@@ -65,6 +80,16 @@ pub struct Windows {
     pub meta_diff: MetaDiffWindow,
     pub zero_partition: ZeroPartition,
     pub structs: StructsWindow,
+    pub struct_table: StructTableWindow,
+    pub scanner: ScannerWindow,
+    pub checksum: ChecksumWindow,
+    pub entropy: EntropyWindow,
+    pub strings: StringsWindow,
+    pub carve: CarveWindow,
+    pub decompress: DecompressWindow,
+    pub aligned_diff: AlignedDiffWindow,
+    pub compare: CompareWindow,
+    pub patch: PatchWindow,
 }
 
 #[derive(Default)]
@@ -111,6 +136,8 @@ struct WinCtx<'a> {
 trait Window {
     fn ui(&mut self, ctx: WinCtx);
     fn title(&self) -> &str;
+    /// Forget the state that refers to the active document, like offsets into its data
+    fn clear_doc_state(&mut self) {}
 }
 
 impl Windows {
@@ -123,6 +150,10 @@ impl Windows {
         line_spacing: u16,
         font: &Font,
     ) {
+        gui.win.find.poll_job(app, &mut gui.msg_dialog);
+        if gui.win.find.searching() {
+            ctx.request_repaint();
+        }
         let mut open;
         macro_rules! windows {
             ($($field:ident,)*) => {
@@ -159,6 +190,16 @@ impl Windows {
             about,
             zero_partition,
             structs,
+            struct_table,
+            scanner,
+            checksum,
+            entropy,
+            strings,
+            carve,
+            decompress,
+            aligned_diff,
+            compare,
+            patch,
         );
 
         let mut watch_windows = std::mem::take(&mut gui.win.lua_watch);
@@ -184,6 +225,30 @@ impl Windows {
         });
         std::mem::swap(&mut gui.win.lua_watch, &mut watch_windows);
     }
+    /// Forget the state of the windows that refers to the active document.
+    ///
+    /// Called after switching documents, so results of the previous one don't point
+    /// into the new one.
+    pub(crate) fn clear_doc_state(&mut self) {
+        macro_rules! clear {
+            ($($field:ident,)*) => {
+                $(self.$field.clear_doc_state();)*
+            };
+        }
+        clear!(
+            find,
+            zero_partition,
+            struct_table,
+            scanner,
+            checksum,
+            entropy,
+            strings,
+            carve,
+            decompress,
+            aligned_diff,
+            patch,
+        );
+    }
     pub fn add_lua_watch_window(&mut self) {
         self.lua_watch.push(LuaWatchWindow::default());
     }
@@ -0,0 +1,243 @@
+use {
+    super::{WinCtx, WindowOpen},
+    crate::bindiff::{self, Hunk, HunkKind},
+    egui::{Color32, RichText},
+    std::{fmt::Write as _, ops::Range},
+};
+
+const ROW_LEN: usize = 16;
+
+/// Diff of the data against a file that aligns insertions and deletions,
+/// with the two shown side by side
+#[derive(Default)]
+pub struct AlignedDiffWindow {
+    pub open: WindowOpen,
+    file_data: Vec<u8>,
+    hunks: Vec<Hunk>,
+    segments: Vec<Segment>,
+    total_rows: usize,
+    /// Keep the side by side view scrolled to the cursor
+    follow_cursor: bool,
+    last_cursor: usize,
+    scroll_to_row: Option<usize>,
+}
+
+/// A stretch of equal data, or a hunk, as laid out in the side by side view
+struct Segment {
+    a: Range<usize>,
+    b: Range<usize>,
+    /// `None` for equal data
+    kind: Option<HunkKind>,
+    first_row: usize,
+}
+
+impl Segment {
+    fn rows(&self) -> usize {
+        self.a.len().max(self.b.len()).div_ceil(ROW_LEN)
+    }
+}
+
+impl AlignedDiffWindow {
+    /// Diff `data` against `file_data`, and open the window
+    pub fn compute(&mut self, data: &[u8], file_data: Vec<u8>) {
+        self.hunks = bindiff::diff(data, &file_data);
+        self.segments.clear();
+        let (mut a, mut b, mut row) = (0, 0, 0);
+        let mut push = |seg: Segment, row: &mut usize| {
+            let rows = seg.rows();
+            if rows != 0 {
+                *row += rows;
+                self.segments.push(seg);
+            }
+        };
+        for hunk in &self.hunks {
+            push(
+                Segment {
+                    a: a..hunk.a.start,
+                    b: b..hunk.b.start,
+                    kind: None,
+                    first_row: row,
+                },
+                &mut row,
+            );
+            push(
+                Segment {
+                    a: hunk.a.clone(),
+                    b: hunk.b.clone(),
+                    kind: Some(hunk.kind),
+                    first_row: row,
+                },
+                &mut row,
+            );
+            (a, b) = (hunk.a.end, hunk.b.end);
+        }
+        push(
+            Segment {
+                a: a..data.len(),
+                b: b..file_data.len(),
+                kind: None,
+                first_row: row,
+            },
+            &mut row,
+        );
+        self.total_rows = row;
+        self.file_data = file_data;
+        self.scroll_to_row = Some(0);
+        self.open.set(true);
+    }
+    /// The side by side row that shows `offset` of the data
+    fn row_of_offset(&self, offset: usize) -> Option<usize> {
+        let idx = self.segments.partition_point(|seg| seg.a.start <= offset);
+        let seg = &self.segments[idx.checked_sub(1)?];
+        Some(seg.first_row + offset.saturating_sub(seg.a.start) / ROW_LEN)
+    }
+}
+
+impl super::Window for AlignedDiffWindow {
+    fn ui(&mut self, WinCtx { ui, app, .. }: WinCtx) {
+        let (mut inserted, mut deleted, mut changed) = (0, 0, 0);
+        for hunk in &self.hunks {
+            match hunk.kind {
+                HunkKind::Inserted => inserted += hunk.b.len(),
+                HunkKind::Deleted => deleted += hunk.a.len(),
+                HunkKind::Changed => changed += hunk.a.len(),
+            }
+        }
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} hunks: {changed} changed, {inserted} inserted, {deleted} deleted bytes",
+                self.hunks.len()
+            ));
+            ui.checkbox(&mut self.follow_cursor, "Follow cursor");
+            ui.label(format!(
+                "Cursor {:X} ⇔ {:X}",
+                app.edit_state.cursor,
+                bindiff::map_offset(&self.hunks, app.edit_state.cursor)
+            ));
+        });
+        if self.follow_cursor && app.edit_state.cursor != self.last_cursor {
+            self.last_cursor = app.edit_state.cursor;
+            self.scroll_to_row = self.row_of_offset(app.edit_state.cursor);
+        }
+        let mut goto = None;
+        egui::CollapsingHeader::new("Hunks").default_open(true).show(ui, |ui| {
+            egui::ScrollArea::vertical().max_height(160.0).show_rows(
+                ui,
+                ui.text_style_height(&egui::TextStyle::Body),
+                self.hunks.len(),
+                |ui, range| {
+                    for hunk in &self.hunks[range] {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(kind_color(hunk.kind), kind_label(hunk.kind));
+                            if ui
+                                .link(format!(
+                                    "{:X}..{:X} ⇔ {:X}..{:X}",
+                                    hunk.a.start, hunk.a.end, hunk.b.start, hunk.b.end
+                                ))
+                                .clicked()
+                            {
+                                goto = Some(hunk.a.start);
+                            }
+                        });
+                    }
+                },
+            );
+        });
+        if let Some(offset) = goto {
+            self.scroll_to_row = self.row_of_offset(offset);
+            app.search_focus(offset);
+        }
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.monospace(format!("{:<w$}", "My data", w = 10 + ROW_LEN * 3));
+            ui.monospace("File");
+        });
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        let mut scroll = egui::ScrollArea::vertical().auto_shrink([false, true]);
+        if let Some(row) = self.scroll_to_row.take() {
+            #[expect(clippy::cast_precision_loss)]
+            let offset = row as f32 * (row_height + ui.spacing().item_spacing.y);
+            scroll = scroll.vertical_scroll_offset(offset);
+        }
+        scroll.show_rows(ui, row_height, self.total_rows, |ui, range| {
+            let mut seg_idx = self.segments.partition_point(|seg| seg.first_row <= range.start);
+            for row in range {
+                while seg_idx < self.segments.len() && self.segments[seg_idx].first_row <= row {
+                    seg_idx += 1;
+                }
+                let Some(seg) = seg_idx.checked_sub(1).map(|i| &self.segments[i]) else {
+                    break;
+                };
+                let rel = (row - seg.first_row) * ROW_LEN;
+                let a = side_range(&seg.a, rel);
+                let b = side_range(&seg.b, rel);
+                let color = seg.kind.map(kind_color);
+                ui.horizontal(|ui| {
+                    let a_text = side_text(&app.data, &a);
+                    let re = ui
+                        .add(egui::Label::new(colored(a_text, color)).sense(egui::Sense::click()));
+                    if re.clicked() && !a.is_empty() {
+                        app.search_focus(a.start);
+                    }
+                    ui.label(colored(side_text(&self.file_data, &b), color));
+                });
+            }
+        });
+    }
+
+    fn title(&self) -> &str {
+        "Aligned diff"
+    }
+
+    fn clear_doc_state(&mut self) {
+        self.file_data.clear();
+        self.hunks.clear();
+        self.segments.clear();
+        self.total_rows = 0;
+        self.scroll_to_row = None;
+    }
+}
+
+/// The part of `range` shown on the row `rel` bytes into its segment
+fn side_range(range: &Range<usize>, rel: usize) -> Range<usize> {
+    let start = (range.start + rel).min(range.end);
+    start..(start + ROW_LEN).min(range.end)
+}
+
+/// Offset and hex of the bytes in `range`, padded to a full row
+fn side_text(data: &[u8], range: &Range<usize>) -> String {
+    let mut s = String::new();
+    if let Some(bytes) = data.get(range.clone())
+        && !bytes.is_empty()
+    {
+        let _ = write!(s, "{:08X}  ", range.start);
+        for b in bytes {
+            let _ = write!(s, "{b:02X} ");
+        }
+    }
+    format!("{s:<w$}", w = 10 + ROW_LEN * 3)
+}
+
+fn colored(text: String, color: Option<Color32>) -> RichText {
+    let text = RichText::new(text).monospace();
+    match color {
+        Some(color) => text.color(color),
+        None => text,
+    }
+}
+
+fn kind_color(kind: HunkKind) -> Color32 {
+    match kind {
+        HunkKind::Changed => Color32::YELLOW,
+        HunkKind::Inserted => Color32::GREEN,
+        HunkKind::Deleted => Color32::RED,
+    }
+}
+
+fn kind_label(kind: HunkKind) -> &'static str {
+    match kind {
+        HunkKind::Changed => "Changed",
+        HunkKind::Inserted => "Inserted",
+        HunkKind::Deleted => "Deleted",
+    }
+}
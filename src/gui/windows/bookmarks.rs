@@ -2,7 +2,6 @@ use {
     super::{WinCtx, WindowOpen},
     crate::{
         app::set_clipboard_string,
-        damage_region::DamageRegion,
         data::Data,
         gui::{message_dialog::MessageDialog, windows::regions::region_context_menu},
         meta::{
@@ -130,7 +129,11 @@ impl super::Window for BookmarksWindow {
                             }
                         });
                         row.col(|ui| {
-                            ui.label(app.meta_state.meta.bookmarks[idx].value_type.label());
+                            let bm = &app.meta_state.meta.bookmarks[idx];
+                            ui.label(bm.value_type.label());
+                            if bm.frozen.is_some() {
+                                ui.label("🔒").on_hover_text("Frozen");
+                            }
                         });
                         row.col(|ui| {
                             let bookmark = &app.meta_state.meta.bookmarks[idx];
@@ -260,6 +263,12 @@ impl super::Window for BookmarksWindow {
                         mark.value_type = val;
                     }
                 });
+            let value_len = match &mark.frozen {
+                Some(bytes) => bytes.len(),
+                None => mark.value_type.byte_len(),
+            };
+            let value_range = mark.offset..mark.offset + value_len;
+            let value_before = app.data.get(value_range.clone()).map(<[u8]>::to_vec);
             ui.horizontal(|ui| {
                 ui.label("Value");
                 let value_ui_action = value_ui(
@@ -282,6 +291,27 @@ impl super::Window for BookmarksWindow {
                     }
                 }
             });
+            let value_after = app.data.get(value_range.clone());
+            // Editing a frozen value freezes it at the new value
+            if mark.frozen.is_some() && value_after != value_before.as_deref() {
+                mark.frozen = value_after.map(<[u8]>::to_vec);
+            }
+            let mut frozen = mark.frozen.is_some();
+            if ui
+                .add_enabled(
+                    value_len != 0,
+                    egui::Checkbox::new(&mut frozen, "🔒 Frozen"),
+                )
+                .on_hover_text("Write the value back after every reload, keeping it fixed")
+                .on_disabled_hover_text("Needs a value type")
+                .changed()
+            {
+                mark.frozen = if frozen {
+                    app.data.get(value_range).map(<[u8]>::to_vec)
+                } else {
+                    None
+                };
+            }
             #[expect(clippy::single_match, reason = "Want to add more variants in future")]
             match &mut mark.value_type {
                 ValueType::StringMap(list) => {
@@ -325,6 +355,7 @@ impl super::Window for BookmarksWindow {
                 label: format!("New bookmark at {}", app.edit_state.cursor),
                 desc: String::new(),
                 value_type: ValueType::None,
+                frozen: None,
             });
             self.selected = Some(app.meta_state.meta.bookmarks.len() - 1);
         }
@@ -406,9 +437,10 @@ trait ValueTrait: EndianedPrimitive {
                     clippy::unwrap_used,
                     reason = "If slicing is successful, we're guaranteed to have slice of right length"
                 )]
-                let out = self.value_change_ui(ui, slice.try_into().unwrap(), cb, msg);
+                let mut bytes: [u8; Self::BYTE_LEN] = (*slice).try_into().unwrap();
+                let out = self.value_change_ui(ui, &mut bytes, cb, msg);
                 if out.changed {
-                    data.widen_dirty_region(DamageRegion::Range(range));
+                    data.write(bm.offset, &bytes);
                 }
                 out.action
             }
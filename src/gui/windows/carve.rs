@@ -0,0 +1,140 @@
+use {
+    super::{WinCtx, WindowOpen},
+    crate::{
+        carve::{self, Carved},
+        meta::{NamedRegion, region::Region},
+        util::human_size,
+    },
+    egui_extras::{Column, TableBuilder},
+};
+
+/// Files embedded in the data, found by their magic numbers
+pub struct CarveWindow {
+    pub open: WindowOpen,
+    hits: Vec<Carved>,
+    skip_contents: bool,
+    scanned: bool,
+}
+
+impl Default for CarveWindow {
+    fn default() -> Self {
+        Self {
+            open: WindowOpen::default(),
+            hits: Vec::new(),
+            skip_contents: true,
+            scanned: false,
+        }
+    }
+}
+
+impl super::Window for CarveWindow {
+    fn ui(&mut self, WinCtx { ui, gui, app, .. }: WinCtx) {
+        ui.horizontal(|ui| {
+            if ui.button("Scan").clicked() {
+                self.hits = carve::scan(&app.data, self.skip_contents);
+                self.scanned = true;
+            }
+            ui.checkbox(&mut self.skip_contents, "Skip contents")
+                .on_hover_text("Don't scan inside files with a known length");
+            if self.scanned {
+                ui.label(format!("{} files", self.hits.len()));
+            }
+        });
+        ui.horizontal(|ui| {
+            let any_known = self.hits.iter().any(|hit| hit.len.is_some());
+            if ui
+                .add_enabled(any_known, egui::Button::new("Add regions"))
+                .on_hover_text("Add a region for every file with a known length")
+                .clicked()
+            {
+                for (hit, reg) in self.known_regions() {
+                    app.meta_state.meta.low.regions.insert(NamedRegion::new(
+                        format!("{} @ {:X}", hit.sig.name, hit.offset),
+                        reg.begin,
+                        reg.end,
+                    ));
+                }
+                gui.win.regions.open.set(true);
+            }
+            if ui
+                .add_enabled(any_known, egui::Button::new("Extract all..."))
+                .on_hover_text("Save every file with a known length into a directory")
+                .clicked()
+            {
+                let files = self
+                    .known_regions()
+                    .map(|(hit, reg)| (reg, format!("{:08X}.{}", hit.offset, hit.sig.ext)))
+                    .collect();
+                gui.fileops.extract_files(files);
+            }
+        });
+        ui.separator();
+        TableBuilder::new(ui)
+            .columns(Column::auto(), 4)
+            .auto_shrink([false, true])
+            .striped(true)
+            .header(20.0, |mut row| {
+                for label in ["Offset", "Type", "Length", ""] {
+                    row.col(|ui| {
+                        ui.label(label);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(20.0, self.hits.len(), |mut row| {
+                    let hit = &self.hits[row.index()];
+                    let reg = hit_region(hit);
+                    row.col(|ui| {
+                        if ui.link(format!("{:X}", hit.offset)).clicked() {
+                            app.search_focus(hit.offset);
+                        }
+                    });
+                    row.col(|ui| {
+                        ui.label(hit.sig.name);
+                    });
+                    row.col(|ui| match hit.len {
+                        Some(len) => {
+                            ui.label(len.to_string()).on_hover_text(human_size(len));
+                        }
+                        None => {
+                            ui.label("?").on_hover_text("Unknown, the file might be truncated");
+                        }
+                    });
+                    row.col(|ui| {
+                        let Some(reg) = reg else {
+                            return;
+                        };
+                        if ui.button("Select").clicked() {
+                            app.hex_ui.select_a = Some(reg.begin);
+                            app.hex_ui.select_b = Some(reg.end);
+                        }
+                        if ui.button("Save...").clicked() {
+                            gui.fileops.save_selection_to_file(reg);
+                        }
+                    });
+                });
+            });
+    }
+
+    fn title(&self) -> &str {
+        "Carve files"
+    }
+
+    fn clear_doc_state(&mut self) {
+        self.hits.clear();
+        self.scanned = false;
+    }
+}
+
+impl CarveWindow {
+    fn known_regions(&self) -> impl Iterator<Item = (&Carved, Region)> {
+        self.hits.iter().filter_map(|hit| Some((hit, hit_region(hit)?)))
+    }
+}
+
+fn hit_region(hit: &Carved) -> Option<Region> {
+    hit.len.map(|len| Region {
+        begin: hit.offset,
+        end: hit.offset + len - 1,
+    })
+}
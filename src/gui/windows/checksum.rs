@@ -0,0 +1,224 @@
+use {
+    super::{WinCtx, WindowOpen},
+    crate::{
+        app::{App, set_clipboard_string},
+        checksum::{HashAlgo, to_hex},
+        gui::Gui,
+        meta::{ChecksumRule, RegionKey, region::Region},
+        shell::msg_if_fail,
+        struct_meta_item::Endian,
+    },
+    egui_extras::{Column, TableBuilder},
+    strum::IntoEnumIterator as _,
+};
+
+/// Checksums and hashes of the selection or a region
+#[derive(Default)]
+pub struct ChecksumWindow {
+    pub open: WindowOpen,
+    region: Option<Region>,
+    /// Results of the last computation, for the region they were computed for
+    results: Vec<(HashAlgo, String)>,
+    computed_for: Option<Region>,
+    new_rule: NewRule,
+}
+
+/// Input state for adding a new checksum rule
+struct NewRule {
+    algo: HashAlgo,
+    region: Option<RegionKey>,
+    dest: usize,
+    endian: Endian,
+}
+
+impl Default for NewRule {
+    fn default() -> Self {
+        Self {
+            algo: HashAlgo::Crc32,
+            region: None,
+            dest: 0,
+            endian: Endian::Le,
+        }
+    }
+}
+
+impl super::Window for ChecksumWindow {
+    fn ui(&mut self, WinCtx { ui, gui, app, .. }: WinCtx) {
+        if self.open.just_now()
+            && let Some(sel) = app.hex_ui.selection()
+        {
+            self.region = Some(sel);
+        }
+        ui.horizontal(|ui| {
+            egui::ComboBox::new("checksum_reg_cbox", "Range")
+                .selected_text(
+                    self.region
+                        .map_or("-".into(), |reg| format!("{:X}..={:X}", reg.begin, reg.end)),
+                )
+                .show_ui(ui, |ui| {
+                    if let Some(sel) = app.hex_ui.selection()
+                        && ui.button("Selection").clicked()
+                    {
+                        self.region = Some(sel);
+                    }
+                    if !app.data.is_empty() && ui.button("Whole data").clicked() {
+                        self.region = Some(Region {
+                            begin: 0,
+                            end: app.data.len() - 1,
+                        });
+                    }
+                    for (_key, reg) in app.meta_state.meta.low.regions.iter() {
+                        if ui.button(&reg.name).clicked() {
+                            self.region = Some(reg.region);
+                        }
+                    }
+                });
+            if ui.add_enabled(self.region.is_some(), egui::Button::new("Compute")).clicked()
+                && let Some(reg) = self.region
+            {
+                self.results.clear();
+                self.computed_for = None;
+                match app.data.get(reg.begin..=reg.end) {
+                    Some(data) => {
+                        self.results = HashAlgo::iter()
+                            .map(|algo| (algo, to_hex(&algo.compute(data))))
+                            .collect();
+                        self.computed_for = Some(reg);
+                    }
+                    None => gui.msg_dialog.open(
+                        crate::gui::message_dialog::Icon::Error,
+                        "Out of bounds",
+                        "The range is out of bounds of the data",
+                    ),
+                }
+            }
+        });
+        if let Some(reg) = self.computed_for {
+            ui.label(format!(
+                "{:X}..={:X} ({} bytes)",
+                reg.begin,
+                reg.end,
+                reg.len()
+            ));
+        }
+        ui.separator();
+        TableBuilder::new(ui)
+            .column(Column::auto())
+            .column(Column::remainder())
+            .striped(true)
+            .header(20.0, |mut row| {
+                row.col(|ui| {
+                    ui.label("Algorithm");
+                });
+                row.col(|ui| {
+                    ui.label("Value");
+                });
+            })
+            .body(|body| {
+                body.rows(20.0, self.results.len(), |mut row| {
+                    let (algo, hex) = &self.results[row.index()];
+                    row.col(|ui| {
+                        ui.label(algo.name());
+                    });
+                    row.col(|ui| {
+                        if ui.link(hex).on_hover_text("Click to copy").clicked() {
+                            set_clipboard_string(&mut app.clipboard, &mut gui.msg_dialog, hex);
+                        }
+                    });
+                });
+            });
+        ui.separator();
+        ui.heading("Rules");
+        ui.label("Checksums that are recomputed and written into the data on every save");
+        self.rules_ui(ui, gui, app);
+    }
+
+    fn title(&self) -> &str {
+        "Checksums"
+    }
+
+    fn clear_doc_state(&mut self) {
+        self.region = None;
+        self.results.clear();
+        self.computed_for = None;
+        self.new_rule = NewRule::default();
+    }
+}
+
+impl ChecksumWindow {
+    fn rules_ui(&mut self, ui: &mut egui::Ui, gui: &mut Gui, app: &mut App) {
+        let meta = &mut app.meta_state.meta;
+        let mut remove = None;
+        for (i, rule) in meta.checksum_rules.iter().enumerate() {
+            ui.horizontal(|ui| {
+                let reg_name =
+                    meta.low.regions.get(rule.region).map_or("?", |reg| reg.name.as_str());
+                ui.label(format!(
+                    "{} of {reg_name} → {:X} ({})",
+                    rule.algo.name(),
+                    rule.dest,
+                    rule.endian.label()
+                ));
+                if ui.button("🗑").on_hover_text("Remove rule").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            meta.checksum_rules.remove(i);
+        }
+        let new = &mut self.new_rule;
+        ui.horizontal(|ui| {
+            egui::ComboBox::new("checksum_rule_algo_cbox", "")
+                .selected_text(new.algo.name())
+                .show_ui(ui, |ui| {
+                    for algo in HashAlgo::iter() {
+                        ui.selectable_value(&mut new.algo, algo, algo.name());
+                    }
+                });
+            ui.label("of");
+            egui::ComboBox::new("checksum_rule_reg_cbox", "")
+                .selected_text(
+                    new.region
+                        .and_then(|key| meta.low.regions.get(key))
+                        .map_or("-", |reg| reg.name.as_str()),
+                )
+                .show_ui(ui, |ui| {
+                    for (key, reg) in meta.low.regions.iter() {
+                        ui.selectable_value(&mut new.region, Some(key), &reg.name);
+                    }
+                });
+            ui.label("to");
+            ui.add(egui::DragValue::new(&mut new.dest).hexadecimal(1, false, true));
+            if !new.algo.is_digest()
+                && ui.button(new.endian.label()).on_hover_text("Endianness").clicked()
+            {
+                new.endian.toggle();
+            }
+            if ui.add_enabled(new.region.is_some(), egui::Button::new("Add")).clicked()
+                && let Some(region) = new.region
+            {
+                meta.checksum_rules.push(ChecksumRule {
+                    algo: new.algo,
+                    region,
+                    dest: new.dest,
+                    endian: new.endian,
+                });
+            }
+        });
+        if ui
+            .add_enabled(
+                !meta.checksum_rules.is_empty(),
+                egui::Button::new("Apply now"),
+            )
+            .on_hover_text("Recompute and write the checksums without saving")
+            .clicked()
+        {
+            msg_if_fail(
+                app.apply_checksum_rules(),
+                "Failed to apply checksum rules",
+                &mut gui.msg_dialog,
+            );
+        }
+    }
+}
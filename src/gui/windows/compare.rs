@@ -0,0 +1,134 @@
+use {
+    super::{WinCtx, WindowOpen},
+    crate::{
+        app::{
+            command::Cmd,
+            compare::{next_difference, prev_difference},
+        },
+        gui::message_dialog::Icon,
+        meta::region::Region,
+        shell::msg_if_fail,
+    },
+};
+
+/// Controls for comparing the data side by side with another document
+#[derive(Default)]
+pub struct CompareWindow {
+    pub open: WindowOpen,
+}
+
+impl super::Window for CompareWindow {
+    fn ui(&mut self, WinCtx { ui, gui, app, .. }: WinCtx) {
+        let Some(doc) = &mut app.compare else {
+            ui.label("Not comparing with any document");
+            if ui.button("Open file...").clicked() {
+                gui.fileops.compare_with_file(app.source_file());
+            }
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.label("Compared with");
+            ui.colored_label(egui::Color32::LIGHT_BLUE, doc.path.display().to_string());
+            if doc.data.dirty_region.is_some() {
+                ui.label("(modified)");
+            }
+        });
+        ui.label(format!(
+            "{} bytes vs {} bytes",
+            app.data.len(),
+            doc.data.len()
+        ));
+        ui.checkbox(&mut doc.highlight_diff, "Highlight differences");
+        let mut found = None;
+        ui.horizontal(|ui| {
+            let cursor = app.edit_state.cursor;
+            if ui.button("⏮ Previous difference").clicked() {
+                found = Some(prev_difference(&app.data, &doc.data, cursor));
+            }
+            if ui.button("Next difference ⏭").clicked() {
+                found = Some(next_difference(&app.data, &doc.data, cursor + 1));
+            }
+        });
+        match found {
+            Some(Some(off)) => app.search_focus(off),
+            Some(None) => gui.msg_dialog.open(
+                Icon::Info,
+                "No difference found",
+                "There are no more differences in that direction",
+            ),
+            None => {}
+        }
+        ui.separator();
+        // Copy the selection, or the byte under the cursor if there is no selection
+        let region = app.hex_ui.selection().unwrap_or(Region {
+            begin: app.edit_state.cursor,
+            end: app.edit_state.cursor,
+        });
+        ui.label(format!(
+            "Copy {:X}..={:X} ({} bytes)",
+            region.begin,
+            region.end,
+            region.len()
+        ));
+        ui.horizontal(|ui| {
+            if ui.button("To compared →").clicked() {
+                msg_if_fail(
+                    app.copy_to_compare(region),
+                    "Failed to copy",
+                    &mut gui.msg_dialog,
+                );
+            }
+            if ui.button("← From compared").clicked() {
+                msg_if_fail(
+                    app.copy_from_compare(region),
+                    "Failed to copy",
+                    &mut gui.msg_dialog,
+                );
+            }
+        });
+        ui.separator();
+        let mut close = false;
+        ui.horizontal(|ui| {
+            let Some(doc) = &mut app.compare else {
+                return;
+            };
+            if ui
+                .add_enabled(
+                    doc.data.dirty_region.is_some(),
+                    egui::Button::new("Save compared"),
+                )
+                .clicked()
+            {
+                msg_if_fail(doc.save(), "Failed to save", &mut gui.msg_dialog);
+            }
+            close = ui.button("Stop comparing").clicked();
+        });
+        if close {
+            if doc.data.dirty_region.is_some() {
+                gui.msg_dialog.open(
+                    Icon::Warn,
+                    "Unsaved changes",
+                    "The compared document has unsaved changes. Stop comparing anyway?",
+                );
+                gui.msg_dialog.custom_button_row_ui(Box::new(|ui, payload, cmd| {
+                    if ui
+                        .button(egui::RichText::new("Discard changes").color(egui::Color32::RED))
+                        .clicked()
+                    {
+                        payload.close = true;
+                        cmd.push(Cmd::StopComparing);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        payload.close = true;
+                    }
+                }));
+            } else {
+                app.compare = None;
+            }
+        }
+    }
+
+    fn title(&self) -> &str {
+        "Compare"
+    }
+}
@@ -0,0 +1,280 @@
+use {
+    super::{WinCtx, WindowOpen},
+    crate::{
+        app::App,
+        compress::Codec,
+        find_util::parse_hex_string,
+        gui::message_dialog::{Icon, MessageDialog},
+        meta::region::Region,
+        shell::msg_if_fail,
+    },
+    anyhow::Context as _,
+    std::fmt::Write as _,
+    strum::IntoEnumIterator as _,
+};
+
+const ROW_LEN: usize = 16;
+
+/// Side buffer holding the decompressed contents of a region of the data
+pub struct DecompressWindow {
+    pub open: WindowOpen,
+    codec: Codec,
+    buffer: Option<SideBuffer>,
+    /// Row being edited, and its hex text
+    edit_row: Option<(usize, String)>,
+    focus_edit: bool,
+}
+
+impl Default for DecompressWindow {
+    fn default() -> Self {
+        Self {
+            open: WindowOpen::default(),
+            codec: Codec::Zlib,
+            buffer: None,
+            edit_row: None,
+            focus_edit: false,
+        }
+    }
+}
+
+struct SideBuffer {
+    /// Where the compressed stream is, without the trailing bytes of the decompressed region
+    source: Region,
+    codec: Codec,
+    data: Vec<u8>,
+    /// Edited since decompressing or writing back
+    dirty: bool,
+}
+
+impl DecompressWindow {
+    /// Decompress `sel`, guessing the codec from its header
+    pub fn decompress_selection(&mut self, app: &App, sel: Region, msg: &mut MessageDialog) {
+        if let Some(codec) = app.data.get(sel.begin..=sel.end).and_then(Codec::detect) {
+            self.codec = codec;
+        }
+        self.open.set(true);
+        self.decompress(app, sel, msg);
+    }
+    fn decompress(&mut self, app: &App, source: Region, msg: &mut MessageDialog) {
+        let result = try {
+            let src = app.data.get(source.begin..=source.end).context("Region out of bounds")?;
+            let (data, stream_len) = self.codec.decompress(src)?;
+            if stream_len == 0 {
+                do yeet anyhow::anyhow!("No compressed data");
+            }
+            (data, stream_len)
+        };
+        match result {
+            Ok((data, stream_len)) => {
+                self.buffer = Some(SideBuffer {
+                    source: Region {
+                        begin: source.begin,
+                        end: source.begin + stream_len - 1,
+                    },
+                    codec: self.codec,
+                    data,
+                    dirty: false,
+                });
+                self.edit_row = None;
+            }
+            Err(e) => msg.open(
+                Icon::Error,
+                format!("{} decompression failed", self.codec.label()),
+                format!("{e:#}"),
+            ),
+        }
+    }
+}
+
+impl SideBuffer {
+    /// Recompress the buffer and write it over the compressed stream.
+    ///
+    /// A shorter stream is zero filled up to the end of the old one, if the codec can tell
+    /// where its stream ends. Otherwise the size has to match exactly.
+    fn write_back(&mut self, app: &mut App) -> anyhow::Result<()> {
+        let compressed = self.codec.compress(&self.data)?;
+        if self.codec.is_self_delimiting() {
+            anyhow::ensure!(
+                compressed.len() <= self.source.len(),
+                "Recompressed data doesn't fit into the source stream ({} > {} bytes)",
+                compressed.len(),
+                self.source.len()
+            );
+        } else {
+            anyhow::ensure!(
+                compressed.len() == self.source.len(),
+                "{} data has to recompress to the size of the source stream ({} != {} bytes)",
+                self.codec.label(),
+                compressed.len(),
+                self.source.len()
+            );
+        }
+        app.data
+            .edit(self.source.begin..self.source.end + 1, |dst| {
+                let (data, rest) = dst.split_at_mut(compressed.len());
+                data.copy_from_slice(&compressed);
+                rest.fill(0);
+            })
+            .context("Source region is out of bounds")?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+impl super::Window for DecompressWindow {
+    fn ui(&mut self, WinCtx { ui, gui, app, .. }: WinCtx) {
+        ui.horizontal(|ui| {
+            egui::ComboBox::new("decompress_codec_cbox", "Codec")
+                .selected_text(self.codec.label())
+                .show_ui(ui, |ui| {
+                    for codec in Codec::iter() {
+                        ui.selectable_value(&mut self.codec, codec, codec.label());
+                    }
+                });
+            if let Some(sel) = app.hex_ui.selection()
+                && ui.button("Decompress selection").clicked()
+            {
+                self.decompress(app, sel, &mut gui.msg_dialog);
+            }
+        });
+        let Some(buf) = &mut self.buffer else {
+            ui.label("Select compressed data, and decompress it");
+            return;
+        };
+        ui.label(format!(
+            "{} of {:X}..={:X}: {} → {} bytes",
+            buf.codec.label(),
+            buf.source.begin,
+            buf.source.end,
+            buf.source.len(),
+            buf.data.len()
+        ));
+        let mut close = false;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(buf.dirty, egui::Button::new("Write back"))
+                .on_hover_text(
+                    "Recompress, and write over the compressed stream if it fits.\n\
+                     The rest of the old stream is zero filled.",
+                )
+                .clicked()
+            {
+                msg_if_fail(
+                    buf.write_back(app),
+                    "Failed to write back",
+                    &mut gui.msg_dialog,
+                );
+            }
+            close = ui.button("Close").clicked();
+        });
+        if close {
+            self.buffer = None;
+            self.edit_row = None;
+            return;
+        }
+        ui.separator();
+        ui.label("Double click a row to edit it");
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        let n_rows = buf.data.len().div_ceil(ROW_LEN);
+        egui::ScrollArea::vertical().auto_shrink([false, true]).show_rows(
+            ui,
+            row_height,
+            n_rows,
+            |ui, range| {
+                for row in range {
+                    let offset = row * ROW_LEN;
+                    let end = (offset + ROW_LEN).min(buf.data.len());
+                    let row_len = end - offset;
+                    ui.horizontal(|ui| {
+                        ui.monospace(format!("{offset:08X}"));
+                        match &mut self.edit_row {
+                            Some((edit_row, text)) if *edit_row == row => {
+                                let re = ui.add(
+                                    egui::TextEdit::singleline(text)
+                                        .font(egui::TextStyle::Monospace)
+                                        .desired_width(f32::INFINITY),
+                                );
+                                if std::mem::take(&mut self.focus_edit) {
+                                    re.request_focus();
+                                }
+                                if ui.input(|inp| inp.key_pressed(egui::Key::Escape)) {
+                                    self.edit_row = None;
+                                } else if re.lost_focus()
+                                    && ui.input(|inp| inp.key_pressed(egui::Key::Enter))
+                                {
+                                    match parse_hex_string(text) {
+                                        Ok(new) if new.len() == row_len => {
+                                            buf.data[offset..end].copy_from_slice(&new);
+                                            buf.dirty = true;
+                                            self.edit_row = None;
+                                        }
+                                        Ok(_) => {
+                                            gui.msg_dialog.open(
+                                                Icon::Error,
+                                                "Wrong length",
+                                                format!("The row must be {row_len} bytes"),
+                                            );
+                                            self.focus_edit = true;
+                                        }
+                                        Err(e) => {
+                                            gui.msg_dialog.open(
+                                                Icon::Error,
+                                                "Invalid hex",
+                                                e.to_string(),
+                                            );
+                                            self.focus_edit = true;
+                                        }
+                                    }
+                                }
+                            }
+                            _ => {
+                                let re = ui.add(
+                                    egui::Label::new(
+                                        egui::RichText::new(row_text(&buf.data[offset..end]))
+                                            .monospace(),
+                                    )
+                                    .sense(egui::Sense::click()),
+                                );
+                                if re.double_clicked() {
+                                    self.edit_row = Some((row, hex_text(&buf.data[offset..end])));
+                                    self.focus_edit = true;
+                                }
+                            }
+                        }
+                    });
+                }
+            },
+        );
+    }
+
+    fn title(&self) -> &str {
+        "Decompressed data"
+    }
+
+    fn clear_doc_state(&mut self) {
+        self.buffer = None;
+        self.edit_row = None;
+    }
+}
+
+fn hex_text(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len() * 3);
+    for b in bytes {
+        let _ = write!(s, "{b:02X} ");
+    }
+    s.pop();
+    s
+}
+
+/// Hex and ASCII representation of a row
+fn row_text(bytes: &[u8]) -> String {
+    let mut s = format!("{:<width$}", hex_text(bytes), width = ROW_LEN * 3);
+    s.extend(bytes.iter().map(|&b| {
+        if b.is_ascii_graphic() || b == b' ' {
+            char::from(b)
+        } else {
+            '.'
+        }
+    }));
+    s
+}
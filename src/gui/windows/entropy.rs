@@ -0,0 +1,294 @@
+use {
+    super::{WinCtx, WindowOpen},
+    crate::{gui::message_dialog::Icon, meta::region::Region},
+    egui::{Color32, Rect, Sense, Shape, Stroke, pos2, vec2},
+};
+
+/// Upper limit on the number of graph points. The step is raised to stay below this.
+const MAX_POINTS: usize = 8192;
+const GRAPH_HEIGHT: f32 = 160.0;
+
+/// Entropy graph and byte histogram, for spotting compressed or encrypted data
+pub struct EntropyWindow {
+    pub open: WindowOpen,
+    region: Option<Region>,
+    window: usize,
+    step: usize,
+    result: Option<EntropyResult>,
+}
+
+struct EntropyResult {
+    region: Region,
+    window: usize,
+    step: usize,
+    /// Entropy in bits per byte of each window
+    points: Vec<f32>,
+    histogram: [u64; 256],
+}
+
+impl Default for EntropyWindow {
+    fn default() -> Self {
+        Self {
+            open: WindowOpen::default(),
+            region: None,
+            window: 256,
+            step: 256,
+            result: None,
+        }
+    }
+}
+
+impl super::Window for EntropyWindow {
+    fn ui(&mut self, WinCtx { ui, gui, app, .. }: WinCtx) {
+        if self.open.just_now() {
+            self.region = app.hex_ui.selection().or_else(|| {
+                (!app.data.is_empty()).then(|| Region {
+                    begin: 0,
+                    end: app.data.len() - 1,
+                })
+            });
+        }
+        ui.horizontal(|ui| {
+            egui::ComboBox::new("entropy_reg_cbox", "Range")
+                .selected_text(
+                    self.region
+                        .map_or("-".into(), |reg| format!("{:X}..={:X}", reg.begin, reg.end)),
+                )
+                .show_ui(ui, |ui| {
+                    if let Some(sel) = app.hex_ui.selection()
+                        && ui.button("Selection").clicked()
+                    {
+                        self.region = Some(sel);
+                    }
+                    if !app.data.is_empty() && ui.button("Whole data").clicked() {
+                        self.region = Some(Region {
+                            begin: 0,
+                            end: app.data.len() - 1,
+                        });
+                    }
+                    for (_key, reg) in app.meta_state.meta.low.regions.iter() {
+                        if ui.button(&reg.name).clicked() {
+                            self.region = Some(reg.region);
+                        }
+                    }
+                });
+            ui.label("Window");
+            ui.add(egui::DragValue::new(&mut self.window).range(16..=1 << 20));
+            ui.label("Step");
+            ui.add(egui::DragValue::new(&mut self.step).range(1..=1 << 20));
+            if ui.add_enabled(self.region.is_some(), egui::Button::new("Compute")).clicked()
+                && let Some(reg) = self.region
+            {
+                match app.data.get(reg.begin..=reg.end) {
+                    Some(data) => {
+                        // Keep the graph at a reasonable resolution for large ranges.
+                        // The window grows along, so no bytes are skipped between windows.
+                        let step = self.step.max(data.len().div_ceil(MAX_POINTS));
+                        let window = self.window.max(step);
+                        self.result = Some(EntropyResult {
+                            region: reg,
+                            window,
+                            step,
+                            points: entropy_graph(data, window, step),
+                            histogram: byte_histogram(data),
+                        });
+                    }
+                    None => gui.msg_dialog.open(
+                        Icon::Error,
+                        "Out of bounds",
+                        "The range is out of bounds of the data",
+                    ),
+                }
+            }
+        });
+        let Some(res) = &self.result else {
+            return;
+        };
+        let total = res.region.len();
+        ui.label(format!(
+            "{:X}..={:X}: {:.3} bits per byte overall, window {}, step {}",
+            res.region.begin,
+            res.region.end,
+            shannon_entropy(&res.histogram, total),
+            res.window,
+            res.step
+        ));
+        ui.separator();
+        ui.label("Entropy (click to jump)");
+        if let Some(idx) = entropy_graph_ui(ui, &res.points, |idx| {
+            format!(
+                "{:X}: {:.3}",
+                res.region.begin + idx * res.step,
+                res.points[idx]
+            )
+        }) {
+            app.search_focus(res.region.begin + idx * res.step);
+        }
+        ui.separator();
+        ui.label("Byte histogram");
+        histogram_ui(ui, &res.histogram);
+    }
+
+    fn title(&self) -> &str {
+        "Entropy"
+    }
+
+    fn clear_doc_state(&mut self) {
+        self.region = None;
+        self.result = None;
+    }
+}
+
+/// Index of the item under the x position `x` of `rect`, if there are `n` items
+#[expect(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn index_at(rect: Rect, x: f32, n: usize) -> usize {
+    let frac = ((x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+    ((frac * n as f32) as usize).min(n.saturating_sub(1))
+}
+
+/// Returns the index of the clicked point
+#[expect(clippy::cast_precision_loss)]
+fn entropy_graph_ui(
+    ui: &mut egui::Ui,
+    points: &[f32],
+    hover_text: impl Fn(usize) -> String,
+) -> Option<usize> {
+    let (rect, re) =
+        ui.allocate_exact_size(vec2(ui.available_width(), GRAPH_HEIGHT), Sense::click());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+    if points.is_empty() {
+        return None;
+    }
+    let x_step = rect.width() / points.len() as f32;
+    let line = points
+        .iter()
+        .enumerate()
+        .map(|(i, &e)| {
+            pos2(
+                rect.left() + (i as f32 + 0.5) * x_step,
+                rect.bottom() - e / 8.0 * rect.height(),
+            )
+        })
+        .collect();
+    painter.add(Shape::line(line, Stroke::new(1.0, Color32::LIGHT_BLUE)));
+    let pos = re.hover_pos()?;
+    let idx = index_at(rect, pos.x, points.len());
+    painter.vline(
+        pos.x,
+        rect.y_range(),
+        ui.visuals().widgets.hovered.fg_stroke,
+    );
+    let clicked = re.clicked();
+    re.on_hover_text_at_pointer(hover_text(idx));
+    clicked.then_some(idx)
+}
+
+#[expect(clippy::cast_precision_loss)]
+fn histogram_ui(ui: &mut egui::Ui, histogram: &[u64; 256]) {
+    let (rect, re) =
+        ui.allocate_exact_size(vec2(ui.available_width(), GRAPH_HEIGHT), Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+    let max = histogram.iter().copied().max().unwrap_or(0).max(1) as f32;
+    let bar_w = rect.width() / 256.0;
+    for (i, &count) in histogram.iter().enumerate() {
+        let h = count as f32 / max * rect.height();
+        let left = rect.left() + i as f32 * bar_w;
+        painter.rect_filled(
+            Rect::from_min_max(
+                pos2(left, rect.bottom() - h),
+                pos2(left + bar_w, rect.bottom()),
+            ),
+            0.0,
+            Color32::LIGHT_GREEN,
+        );
+    }
+    if let Some(pos) = re.hover_pos() {
+        let byte = index_at(rect, pos.x, 256);
+        re.on_hover_text_at_pointer(format!("{byte:02X}: {}", histogram[byte]));
+    }
+}
+
+fn byte_histogram(data: &[u8]) -> [u64; 256] {
+    let mut hist = [0; 256];
+    for &b in data {
+        hist[usize::from(b)] += 1;
+    }
+    hist
+}
+
+/// Shannon entropy in bits per byte (0..=8) of `total` bytes with the given histogram
+#[expect(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+fn shannon_entropy(histogram: &[u64; 256], total: usize) -> f32 {
+    if total == 0 {
+        return 0.0;
+    }
+    let total = total as f64;
+    let entropy: f64 = histogram
+        .iter()
+        .filter(|&&count| count != 0)
+        .map(|&count| {
+            let p = count as f64 / total;
+            -p * p.log2()
+        })
+        .sum();
+    entropy as f32
+}
+
+/// Entropy of each `window` sized window of `data`, starting every `step` bytes.
+///
+/// The last window is shorter if the data doesn't divide evenly.
+fn entropy_graph(data: &[u8], window: usize, step: usize) -> Vec<f32> {
+    let (window, step) = (window.max(1), step.max(1));
+    let mut points = Vec::with_capacity(data.len().div_ceil(step));
+    let mut hist = [0u64; 256];
+    // The range of `data` that `hist` currently counts
+    let mut counted = 0..0;
+    let mut begin = 0;
+    while begin < data.len() {
+        let end = (begin + window).min(data.len());
+        if begin >= counted.end {
+            hist = byte_histogram(&data[begin..end]);
+        } else {
+            // Slide the window, only counting the bytes that went in or out
+            for &b in &data[counted.start..begin] {
+                hist[usize::from(b)] -= 1;
+            }
+            for &b in &data[counted.end..end] {
+                hist[usize::from(b)] += 1;
+            }
+        }
+        counted = begin..end;
+        points.push(shannon_entropy(&hist, end - begin));
+        if end == data.len() {
+            break;
+        }
+        begin += step;
+    }
+    points
+}
+
+#[test]
+fn test_entropy() {
+    assert_eq!(shannon_entropy(&byte_histogram(&[0; 64]), 64), 0.0);
+    let all: Vec<u8> = (0..=255).collect();
+    assert!((shannon_entropy(&byte_histogram(&all), 256) - 8.0).abs() < 1e-4);
+    let mut data = vec![0; 512];
+    data.extend(all.iter().chain(&all));
+    // Sliding windows must agree with computing each window from scratch
+    let graph = entropy_graph(&data, 256, 64);
+    assert_eq!(graph.len(), 13);
+    for (i, &e) in graph.iter().enumerate() {
+        let win = &data[i * 64..(i * 64 + 256).min(data.len())];
+        assert!((e - shannon_entropy(&byte_histogram(win), win.len())).abs() < 1e-4);
+    }
+    assert_eq!(graph[0], 0.0);
+    assert!((graph[12] - 8.0).abs() < 1e-4);
+    // Steps larger than the window
+    assert_eq!(entropy_graph(&data, 16, 256).len(), 4);
+}
@@ -87,6 +87,29 @@ impl super::Window for FileDiffResultWindow {
             if ui.button("🖹 Diff with...").on_hover_text("Diff with another file").clicked() {
                 gui.fileops.diff_with_file(app.source_file());
             }
+            if ui
+                .button("⇔ Aligned diff")
+                .on_hover_text(
+                    "Diff that detects inserted and deleted bytes, instead of comparing \
+                     offset by offset",
+                )
+                .clicked()
+            {
+                gui.win.aligned_diff.compute(&app.data, self.file_data.clone());
+            }
+            if ui
+                .button("◫ Side by side")
+                .on_hover_text("Show the file next to the data, scrolling together")
+                .clicked()
+                && msg_if_fail(
+                    app.open_compare(self.path.clone()),
+                    "Failed to open for comparing",
+                    &mut gui.msg_dialog,
+                )
+                .is_none()
+            {
+                gui.win.compare.open.set(true);
+            }
         });
         ui.separator();
         ui.horizontal(|ui| {
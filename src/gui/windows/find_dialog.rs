@@ -2,7 +2,11 @@ use {
     super::{WinCtx, WindowOpen},
     crate::{
         app::{get_clipboard_string, set_clipboard_string},
-        damage_region::DamageRegion,
+        edit_history::Splice,
+        find_util::{
+            HexPattern, REGEX_MATCH_WINDOW, RegexEncoding, RegexFinder,
+            job::{SearchFn, SearchJob, sliced},
+        },
         gui::{
             message_dialog::{Icon, MessageDialog},
             windows::region_context_menu,
@@ -19,6 +23,7 @@ use {
         parse_radix::parse_guess_radix,
         shell::{msg_fail, msg_if_fail},
     },
+    anyhow::Context as _,
     egui::{Align, Ui},
     egui_extras::{Column, Size, StripBuilder, TableBuilder},
     itertools::Itertools as _,
@@ -52,6 +57,7 @@ pub enum FindType {
     /// Equivalence pattern
     EqPattern,
     HexString,
+    Regex,
 }
 
 impl FindType {
@@ -79,6 +85,14 @@ impl FindType {
             Self::StringDiff => ValueType::None,
             Self::EqPattern => ValueType::None,
             Self::HexString => ValueType::U8(U8),
+            Self::Regex => ValueType::None,
+        }
+    }
+    fn replace_help_str(&self) -> &'static str {
+        match self {
+            Self::HexString => "Hex bytes, e.g. `ff 00 ff`",
+            Self::Ascii | Self::StringDiff | Self::EqPattern | Self::Regex => "Text",
+            _ => "A value of the searched type",
         }
     }
     fn help_str(&self) -> &'static str {
@@ -115,7 +129,18 @@ next to each other regardless of the encoding."
 For example if you type `aabbca`
 then it will match inputs like `00 00 FF FF CC 00`"
             }
-            Self::HexString => "Search a hex string e.g. `ff 00 ff`",
+            Self::HexString => {
+                "Search a hex string e.g. `ff 00 ff`
+
+`??` matches any byte, and `4?` or `?8` match a single nibble.
+`(01|02)` matches any of the alternatives."
+            }
+            Self::Regex => {
+                "Regular expression
+
+When matching bytes, `\\xNN` matches the byte NN.
+With UTF-16, the data is decoded as text before matching."
+            }
         }
     }
 }
@@ -140,6 +165,37 @@ pub struct FindDialog {
     pub reload_before_search: bool,
     /// Only search in selection
     pub selection_only: bool,
+    pub regex_case_insensitive: bool,
+    pub regex_encoding: RegexEncoding,
+    /// The search running in the background, if any
+    job: Option<SearchJob>,
+    /// Go to the first result once the background search finds it
+    focus_first_result: bool,
+}
+
+impl FindDialog {
+    /// Collect the results of the background search.
+    ///
+    /// Called every frame, even while the window is closed.
+    pub(super) fn poll_job(&mut self, app: &mut crate::app::App, msg: &mut MessageDialog) {
+        let Some(job) = &mut self.job else {
+            return;
+        };
+        if let Some(result) = job.poll(|off| self.results_vec.push(off)) {
+            self.job = None;
+            msg_if_fail(result, "Search failed", msg);
+        }
+        if self.focus_first_result
+            && let Some(&off) = self.results_vec.first()
+        {
+            app.search_focus(off);
+            self.focus_first_result = false;
+        }
+    }
+    /// Whether a search is running in the background
+    pub(super) fn searching(&self) -> bool {
+        self.job.is_some()
+    }
 }
 
 impl super::Window for FindDialog {
@@ -169,6 +225,9 @@ impl super::Window for FindDialog {
             if self.reload_before_search {
                 self.reload_data(app, gui);
             }
+            if let Some(job) = self.job.take() {
+                job.cancel();
+            }
             let (data, offs) = self.data_to_search(app);
             msg_if_fail(
                 do_search(data, offs, self, gui),
@@ -179,37 +238,51 @@ impl super::Window for FindDialog {
                 app.search_focus(off);
             }
         }
-        if self.find_type == FindType::Ascii || self.find_type == FindType::HexString {
+        if let Some(job) = &self.job {
+            let mut cancel = false;
             ui.horizontal(|ui| {
-                ui.add(egui::TextEdit::singleline(&mut self.replace_input).hint_text("🔁 Replace"));
-                if ui
-                    .add_enabled(
-                        !self.results_vec.is_empty(),
-                        egui::Button::new("Replace all"),
-                    )
-                    .clicked()
-                {
-                    let bytes_buf;
-                    let replace_data = if self.find_type == FindType::Ascii {
-                        self.replace_input.as_bytes()
-                    } else {
-                        match crate::find_util::parse_hex_string(&self.replace_input) {
-                            Ok(bytes) => {
-                                bytes_buf = bytes;
-                                &bytes_buf
-                            }
-                            Err(e) => {
-                                msg_fail(&e, "Failed to parse hex string", &mut gui.msg_dialog);
-                                return;
-                            }
+                ui.add(
+                    egui::ProgressBar::new(job.progress())
+                        .desired_width(200.0)
+                        .show_percentage()
+                        .animate(true),
+                );
+                cancel = ui.button("Cancel").clicked();
+            });
+            if cancel {
+                job.cancel();
+                self.job = None;
+            }
+        }
+        if self.find_type == FindType::Regex {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.regex_case_insensitive, "Case insensitive");
+                egui::ComboBox::new("regex_enc_combo", "Encoding")
+                    .selected_text(<&str>::from(self.regex_encoding))
+                    .show_ui(ui, |ui| {
+                        for enc in RegexEncoding::iter() {
+                            ui.selectable_value(&mut self.regex_encoding, enc, <&str>::from(enc));
                         }
-                    };
-                    for &offset in &self.results_vec {
-                        app.data[offset..offset + replace_data.len()].copy_from_slice(replace_data);
-                    }
-                }
+                    });
             });
         }
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.replace_input).hint_text("🔁 Replace"))
+                .on_hover_text(self.find_type.replace_help_str());
+            let enabled = !self.results_vec.is_empty() && self.job.is_none();
+            let replace_one = ui
+                .add_enabled(enabled, egui::Button::new("Replace"))
+                .on_hover_text("Replace the current result, and go to the next one")
+                .clicked();
+            let replace_all = ui.add_enabled(enabled, egui::Button::new("Replace all")).clicked();
+            if replace_one || replace_all {
+                msg_if_fail(
+                    self.replace_results(app, replace_all),
+                    "Replace failed",
+                    &mut gui.msg_dialog,
+                );
+            }
+        });
         ui.horizontal(|ui| {
             ui.checkbox(&mut self.filter_results, "Filter results")
                 .on_hover_text("Base search on existing results");
@@ -270,77 +343,71 @@ impl super::Window for FindDialog {
                                         self.result_cursor = i;
                                     }
                                 });
-                                row.col(|ui| {
-                                    let damage = match self.find_type {
-                                        FindType::I8 => {
-                                            data_value_label::<I8>(ui, &mut app.data, off)
-                                        }
-                                        FindType::U8 => {
-                                            data_value_label::<U8>(ui, &mut app.data, off)
-                                        }
-                                        FindType::I16Le => {
-                                            data_value_label::<I16Le>(ui, &mut app.data, off)
-                                        }
-                                        FindType::I16Be => {
-                                            data_value_label::<I16Be>(ui, &mut app.data, off)
-                                        }
-                                        FindType::U16Le => {
-                                            data_value_label::<U16Le>(ui, &mut app.data, off)
-                                        }
-                                        FindType::U16Be => {
-                                            data_value_label::<U16Be>(ui, &mut app.data, off)
-                                        }
-                                        FindType::I32Le => {
-                                            data_value_label::<I32Le>(ui, &mut app.data, off)
-                                        }
-                                        FindType::I32Be => {
-                                            data_value_label::<I32Be>(ui, &mut app.data, off)
-                                        }
-                                        FindType::U32Le => {
-                                            data_value_label::<U32Le>(ui, &mut app.data, off)
-                                        }
-                                        FindType::U32Be => {
-                                            data_value_label::<U32Be>(ui, &mut app.data, off)
-                                        }
-                                        FindType::I64Le => {
-                                            data_value_label::<I64Le>(ui, &mut app.data, off)
-                                        }
-                                        FindType::I64Be => {
-                                            data_value_label::<I64Be>(ui, &mut app.data, off)
-                                        }
-                                        FindType::U64Le => {
-                                            data_value_label::<U64Le>(ui, &mut app.data, off)
-                                        }
-                                        FindType::U64Be => {
-                                            data_value_label::<U64Be>(ui, &mut app.data, off)
-                                        }
-                                        FindType::F32Le => {
-                                            data_value_label::<F32Le>(ui, &mut app.data, off)
-                                        }
-                                        FindType::F32Be => {
-                                            data_value_label::<F32Be>(ui, &mut app.data, off)
-                                        }
-                                        FindType::F64Le => {
-                                            data_value_label::<F64Le>(ui, &mut app.data, off)
-                                        }
-                                        FindType::F64Be => {
-                                            data_value_label::<F64Be>(ui, &mut app.data, off)
-                                        }
-                                        FindType::Ascii => {
-                                            data_value_label::<U8>(ui, &mut app.data, off)
-                                        }
-                                        FindType::HexString => {
-                                            data_value_label::<U8>(ui, &mut app.data, off)
-                                        }
-                                        FindType::StringDiff => {
-                                            data_value_label::<U8>(ui, &mut app.data, off)
-                                        }
-                                        FindType::EqPattern => {
-                                            data_value_label::<U8>(ui, &mut app.data, off)
-                                        }
-                                    };
-                                    if let Some(damage) = damage {
-                                        app.data.widen_dirty_region(damage);
+                                row.col(|ui| match self.find_type {
+                                    FindType::I8 => data_value_label::<I8>(ui, &mut app.data, off),
+                                    FindType::U8 => data_value_label::<U8>(ui, &mut app.data, off),
+                                    FindType::I16Le => {
+                                        data_value_label::<I16Le>(ui, &mut app.data, off)
+                                    }
+                                    FindType::I16Be => {
+                                        data_value_label::<I16Be>(ui, &mut app.data, off)
+                                    }
+                                    FindType::U16Le => {
+                                        data_value_label::<U16Le>(ui, &mut app.data, off)
+                                    }
+                                    FindType::U16Be => {
+                                        data_value_label::<U16Be>(ui, &mut app.data, off)
+                                    }
+                                    FindType::I32Le => {
+                                        data_value_label::<I32Le>(ui, &mut app.data, off)
+                                    }
+                                    FindType::I32Be => {
+                                        data_value_label::<I32Be>(ui, &mut app.data, off)
+                                    }
+                                    FindType::U32Le => {
+                                        data_value_label::<U32Le>(ui, &mut app.data, off)
+                                    }
+                                    FindType::U32Be => {
+                                        data_value_label::<U32Be>(ui, &mut app.data, off)
+                                    }
+                                    FindType::I64Le => {
+                                        data_value_label::<I64Le>(ui, &mut app.data, off)
+                                    }
+                                    FindType::I64Be => {
+                                        data_value_label::<I64Be>(ui, &mut app.data, off)
+                                    }
+                                    FindType::U64Le => {
+                                        data_value_label::<U64Le>(ui, &mut app.data, off)
+                                    }
+                                    FindType::U64Be => {
+                                        data_value_label::<U64Be>(ui, &mut app.data, off)
+                                    }
+                                    FindType::F32Le => {
+                                        data_value_label::<F32Le>(ui, &mut app.data, off)
+                                    }
+                                    FindType::F32Be => {
+                                        data_value_label::<F32Be>(ui, &mut app.data, off)
+                                    }
+                                    FindType::F64Le => {
+                                        data_value_label::<F64Le>(ui, &mut app.data, off)
+                                    }
+                                    FindType::F64Be => {
+                                        data_value_label::<F64Be>(ui, &mut app.data, off)
+                                    }
+                                    FindType::Ascii => {
+                                        data_value_label::<U8>(ui, &mut app.data, off)
+                                    }
+                                    FindType::HexString => {
+                                        data_value_label::<U8>(ui, &mut app.data, off)
+                                    }
+                                    FindType::StringDiff => {
+                                        data_value_label::<U8>(ui, &mut app.data, off)
+                                    }
+                                    FindType::EqPattern => {
+                                        data_value_label::<U8>(ui, &mut app.data, off)
+                                    }
+                                    FindType::Regex => {
+                                        data_value_label::<U8>(ui, &mut app.data, off)
                                     }
                                 });
                                 row.col(|ui| {
@@ -401,6 +468,7 @@ impl super::Window for FindDialog {
                                                     label: "New bookmark".into(),
                                                     desc: String::new(),
                                                     value_type: self.find_type.to_value_type(),
+                                                    frozen: None,
                                                 });
                                                 gui.win.bookmarks.open.set(true);
                                                 gui.win.bookmarks.selected = Some(idx);
@@ -497,6 +565,17 @@ impl super::Window for FindDialog {
     fn title(&self) -> &str {
         "Find"
     }
+
+    fn clear_doc_state(&mut self) {
+        if let Some(job) = self.job.take() {
+            job.cancel();
+        }
+        self.results_vec.clear();
+        self.result_cursor = 0;
+        self.scroll_to = None;
+        self.data_snapshot.clear();
+        self.focus_first_result = false;
+    }
 }
 
 impl FindDialog {
@@ -514,6 +593,138 @@ impl FindDialog {
         }
     }
 
+    /// The bytes that replace a match
+    fn replacement(&self) -> anyhow::Result<Vec<u8>> {
+        let input = &self.replace_input;
+        Ok(match self.find_type {
+            FindType::I8 => num_bytes::<I8>(input)?,
+            FindType::U8 => vec![parse_guess_radix::<u8>(input)?],
+            FindType::I16Le => num_bytes::<I16Le>(input)?,
+            FindType::I16Be => num_bytes::<I16Be>(input)?,
+            FindType::U16Le => num_bytes::<U16Le>(input)?,
+            FindType::U16Be => num_bytes::<U16Be>(input)?,
+            FindType::I32Le => num_bytes::<I32Le>(input)?,
+            FindType::I32Be => num_bytes::<I32Be>(input)?,
+            FindType::U32Le => num_bytes::<U32Le>(input)?,
+            FindType::U32Be => num_bytes::<U32Be>(input)?,
+            FindType::I64Le => num_bytes::<I64Le>(input)?,
+            FindType::I64Be => num_bytes::<I64Be>(input)?,
+            FindType::U64Le => num_bytes::<U64Le>(input)?,
+            FindType::U64Be => num_bytes::<U64Be>(input)?,
+            FindType::F32Le => num_bytes::<F32Le>(input)?,
+            FindType::F32Be => num_bytes::<F32Be>(input)?,
+            FindType::F64Le => num_bytes::<F64Le>(input)?,
+            FindType::F64Be => num_bytes::<F64Be>(input)?,
+            FindType::HexString => crate::find_util::parse_hex_string(input)?,
+            FindType::Ascii | FindType::StringDiff | FindType::EqPattern | FindType::Regex => {
+                input.as_bytes().to_vec()
+            }
+        })
+    }
+    /// Length of the match at the start of `data`
+    fn match_len(&self, data: &[u8]) -> anyhow::Result<usize> {
+        Ok(match self.find_type {
+            FindType::Ascii | FindType::StringDiff | FindType::EqPattern => self.find_input.len(),
+            FindType::HexString => HexPattern::parse(&self.find_input)?.len(),
+            FindType::Regex => crate::find_util::regex_match_len(
+                &self.find_input,
+                data,
+                self.regex_case_insensitive,
+                self.regex_encoding,
+            )?
+            .context("The regex doesn't match here anymore")?,
+            _ => self.find_type.to_value_type().byte_len(),
+        })
+    }
+    /// Replace the current result, or all of them, as a single undo step.
+    ///
+    /// Replaced results are removed from the results.
+    fn replace_results(&mut self, app: &mut crate::app::App, all: bool) -> anyhow::Result<()> {
+        let new = self.replacement()?;
+        let mut offsets = if all {
+            self.results_vec.clone()
+        } else {
+            vec![*self.results_vec.get(self.result_cursor).context("No current result")?]
+        };
+        offsets.sort_unstable();
+        // (offset, length) of the matches. Overlapping matches can't both be replaced.
+        let mut matches: Vec<(usize, usize)> = Vec::new();
+        for off in offsets {
+            if matches.last().is_some_and(|&(prev, len)| off < prev + len.max(1)) {
+                continue;
+            }
+            let len = self.match_len(app.data.get(off..).unwrap_or_default())?;
+            anyhow::ensure!(
+                off + len <= app.data.len(),
+                "Result at {off:X} is out of bounds"
+            );
+            matches.push((off, len));
+        }
+        if matches.is_empty() {
+            return Ok(());
+        }
+        if matches.iter().all(|&(_, len)| len == new.len()) {
+            app.data.edit_group(|data| {
+                for &(off, _) in &matches {
+                    data.write(off, &new);
+                }
+            });
+        } else {
+            self.splice_matches(app, &matches, &new)?;
+        }
+        if all {
+            self.results_vec.clear();
+            self.result_cursor = 0;
+        } else {
+            self.results_vec.remove(self.result_cursor);
+            self.result_cursor = self.result_cursor.min(self.results_vec.len().saturating_sub(1));
+            if let Some(&off) = self.results_vec.get(self.result_cursor) {
+                app.search_focus(off);
+                self.scroll_to = Some(self.result_cursor);
+            }
+        }
+        Ok(())
+    }
+    /// Replace `matches` with `new`, when the lengths differ, in a single splice
+    fn splice_matches(
+        &mut self,
+        app: &mut crate::app::App,
+        matches: &[(usize, usize)],
+        new: &[u8],
+    ) -> anyhow::Result<()> {
+        let (Some(&(first, _)), Some(&(last, last_len))) = (matches.first(), matches.last()) else {
+            return Ok(());
+        };
+        // Build the replaced span in one go, instead of moving the rest of the data
+        // for every match
+        let mut replaced = Vec::new();
+        let mut pos = first;
+        for &(off, len) in matches {
+            replaced.extend_from_slice(&app.data[pos..off]);
+            replaced.extend_from_slice(new);
+            pos = off + len;
+        }
+        app.data.splice(first, last + last_len - first, &replaced).context(
+            "Replacing with a different length requires the data to be loaded into memory \
+             (not memory mapped).",
+        )?;
+        // Move the metadata and the results as if the matches were replaced one by one
+        let mut shift = 0isize;
+        for &(off, len) in matches {
+            let splice = Splice {
+                offset: off.saturating_add_signed(shift),
+                removed: len,
+                inserted: new.len(),
+            };
+            app.follow_splice(splice);
+            for res in &mut self.results_vec {
+                *res = splice.map_offset(*res);
+            }
+            shift += new.len().cast_signed() - len.cast_signed();
+        }
+        Ok(())
+    }
+
     fn reload_data(&self, app: &mut crate::app::App, gui: &mut crate::gui::Gui) {
         let result = match self.search_region(&app.hex_ui) {
             Some(reg) => app.reload_range(reg.begin, reg.end),
@@ -537,28 +748,22 @@ impl<T> SliceExt<T> for [T] {
     }
 }
 
-fn data_value_label<N: EndianedPrimitive>(
-    ui: &mut Ui,
-    data: &mut crate::data::Data,
-    off: usize,
-) -> Option<DamageRegion>
+fn data_value_label<N: EndianedPrimitive>(ui: &mut Ui, data: &mut crate::data::Data, off: usize)
 where
     [(); N::BYTE_LEN]:,
 {
-    let Some(data) = data.get_array_mut(off) else {
+    let Some(bytes) = data.get_array_mut(off) else {
         if let Some(immut) = data.get_array(off) {
             ui.label(N::from_bytes(*immut).to_string());
         } else {
             ui.label("!!").on_hover_text("Out of bounds");
         }
-        return None;
+        return;
     };
-    let mut n = N::from_bytes(*data);
+    let mut n = N::from_bytes(*bytes);
     if ui.add(egui::DragValue::new(&mut n)).changed() {
-        *data = N::to_bytes(n);
-        return Some(DamageRegion::Range(off..off + N::BYTE_LEN));
+        data.write(off, &N::to_bytes(n));
     }
-    None
 }
 
 enum Action {
@@ -567,6 +772,8 @@ enum Action {
     RemoveIdxFromResults(usize),
 }
 
+/// Start searching. Most searches run in the background, and their results arrive
+/// over the next frames.
 fn do_search(
     data: &[u8],
     initial_offset: usize,
@@ -579,56 +786,116 @@ fn do_search(
     if !win.filter_results {
         win.results_vec.clear();
     }
-    match win.find_type {
-        FindType::I8 => find_num::<I8>(win, data)?,
-        FindType::U8 => find_u8(win, data, initial_offset, &mut gui.msg_dialog),
-        FindType::I16Le => find_num::<I16Le>(win, data)?,
-        FindType::I16Be => find_num::<I16Be>(win, data)?,
-        FindType::U16Le => find_num::<U16Le>(win, data)?,
-        FindType::U16Be => find_num::<U16Be>(win, data)?,
-        FindType::I32Le => find_num::<I32Le>(win, data)?,
-        FindType::I32Be => find_num::<I32Be>(win, data)?,
-        FindType::U32Le => find_num::<U32Le>(win, data)?,
-        FindType::U32Be => find_num::<U32Be>(win, data)?,
-        FindType::I64Le => find_num::<I64Le>(win, data)?,
-        FindType::I64Be => find_num::<I64Be>(win, data)?,
-        FindType::U64Le => find_num::<U64Le>(win, data)?,
-        FindType::U64Be => find_num::<U64Be>(win, data)?,
-        FindType::F32Le => find_num::<F32Le>(win, data)?,
-        FindType::F32Be => find_num::<F32Be>(win, data)?,
-        FindType::F64Le => find_num::<F64Le>(win, data)?,
-        FindType::F64Be => find_num::<F64Be>(win, data)?,
-        FindType::Ascii => {
-            for offset in memchr::memmem::find_iter(data, &win.find_input) {
-                win.results_vec.push(initial_offset + offset);
+    anyhow::ensure!(!win.find_input.is_empty(), "Nothing to search for");
+    let (max_match_len, search): (usize, SearchFn) = match win.find_type {
+        FindType::I8 => num_search::<I8>(&win.find_input)?,
+        FindType::U8 => match parse_guess_radix::<u8>(&win.find_input) {
+            Ok(needle) if !win.filter_results => (
+                1,
+                sliced(move |hay, f| {
+                    memchr::memchr_iter(needle, hay).for_each(|off| f(off..off + 1));
+                    Ok(())
+                }),
+            ),
+            // Comparisons against the snapshot, and filtering existing results
+            _ => {
+                find_u8(win, data, initial_offset, &mut gui.msg_dialog);
+                return Ok(());
             }
+        },
+        FindType::I16Le => num_search::<I16Le>(&win.find_input)?,
+        FindType::I16Be => num_search::<I16Be>(&win.find_input)?,
+        FindType::U16Le => num_search::<U16Le>(&win.find_input)?,
+        FindType::U16Be => num_search::<U16Be>(&win.find_input)?,
+        FindType::I32Le => num_search::<I32Le>(&win.find_input)?,
+        FindType::I32Be => num_search::<I32Be>(&win.find_input)?,
+        FindType::U32Le => num_search::<U32Le>(&win.find_input)?,
+        FindType::U32Be => num_search::<U32Be>(&win.find_input)?,
+        FindType::I64Le => num_search::<I64Le>(&win.find_input)?,
+        FindType::I64Be => num_search::<I64Be>(&win.find_input)?,
+        FindType::U64Le => num_search::<U64Le>(&win.find_input)?,
+        FindType::U64Be => num_search::<U64Be>(&win.find_input)?,
+        FindType::F32Le => num_search::<F32Le>(&win.find_input)?,
+        FindType::F32Be => num_search::<F32Be>(&win.find_input)?,
+        FindType::F64Le => num_search::<F64Le>(&win.find_input)?,
+        FindType::F64Be => num_search::<F64Be>(&win.find_input)?,
+        FindType::Ascii => {
+            let needle = win.find_input.clone().into_bytes();
+            (
+                needle.len(),
+                sliced(move |hay, f| {
+                    memchr::memmem::find_iter(hay, &needle)
+                        .for_each(|off| f(off..off + needle.len()));
+                    Ok(())
+                }),
+            )
         }
         FindType::HexString => {
-            let fun = |offset| {
-                win.results_vec.push(initial_offset + offset);
-            };
-            let result = crate::find_util::find_hex_string(&win.find_input, data, fun);
-            msg_if_fail(result, "Hex string search error", &mut gui.msg_dialog);
+            let pat = HexPattern::parse(&win.find_input)?;
+            (
+                pat.len(),
+                sliced(move |hay, f| {
+                    pat.find_iter(hay, |off| f(off..off + pat.len()));
+                    Ok(())
+                }),
+            )
         }
         FindType::StringDiff => {
             let diff = ascii_to_diff_pattern(win.find_input.as_bytes());
-            let mut off = 0;
-            while let Some(offset) = find_diff_pattern(&data[off..], &diff) {
-                off += offset;
-                win.results_vec.push(initial_offset + off);
-                off += diff.len();
-            }
+            anyhow::ensure!(!diff.is_empty(), "Need at least 2 characters");
+            let len = win.find_input.len();
+            (
+                len,
+                sliced(move |hay, f| {
+                    let mut off = 0;
+                    while hay.len() - off >= diff.len()
+                        && let Some(offset) = find_diff_pattern(&hay[off..], &diff)
+                    {
+                        off += offset;
+                        f(off..off + len);
+                        off += diff.len();
+                    }
+                    Ok(())
+                }),
+            )
         }
         FindType::EqPattern => {
             let needle = make_eq_pattern_needle(&win.find_input);
-            let mut off = 0;
-            while let Some(offset) = find_eq_pattern_needle(&needle, &data[off..]) {
-                off += offset;
-                win.results_vec.push(initial_offset + off);
-                off += needle.len();
-            }
+            (
+                needle.len(),
+                sliced(move |hay, f| {
+                    let mut off = 0;
+                    while let Some(offset) = find_eq_pattern_needle(&needle, &hay[off..]) {
+                        off += offset;
+                        f(off..off + needle.len());
+                        off += needle.len();
+                    }
+                    Ok(())
+                }),
+            )
         }
-    }
+        FindType::Regex => {
+            let finder = RegexFinder::new(
+                &win.find_input,
+                win.regex_case_insensitive,
+                win.regex_encoding,
+            )?;
+            (
+                REGEX_MATCH_WINDOW,
+                Box::new(move |hay, range, f| {
+                    finder.find(hay, range, f);
+                    Ok(())
+                }),
+            )
+        }
+    };
+    win.job = Some(SearchJob::spawn(
+        data.into(),
+        initial_offset,
+        max_match_len,
+        search,
+    ));
+    win.focus_first_result = true;
     Ok(())
 }
 
@@ -742,31 +1009,28 @@ fn test_find_diff_pattern() {
     assert_eq!(&s[off..off + key.len()], key);
 }
 
-fn find_num<N: EndianedPrimitive>(win: &mut FindDialog, data: &[u8]) -> Result<(), anyhow::Error>
+fn num_bytes<N: EndianedPrimitive>(input: &str) -> anyhow::Result<Vec<u8>>
 where
     [(); N::BYTE_LEN]:,
     <<N as EndianedPrimitive>::Primitive as FromStr>::Err: Error + Send + Sync,
 {
-    find_num_raw::<N>(&win.find_input, data, |offset| {
-        win.results_vec.push(offset);
-    })
+    let n: N::Primitive = input.parse()?;
+    Ok(N::to_bytes(n).to_vec())
 }
 
-pub(crate) fn find_num_raw<N: EndianedPrimitive>(
-    input: &str,
-    data: &[u8],
-    mut f: impl FnMut(usize),
-) -> anyhow::Result<()>
+fn num_search<N: EndianedPrimitive>(input: &str) -> anyhow::Result<(usize, SearchFn)>
 where
     [(); N::BYTE_LEN]:,
     <<N as EndianedPrimitive>::Primitive as FromStr>::Err: Error + Send + Sync,
 {
-    let n: N::Primitive = input.parse()?;
-    let bytes = N::to_bytes(n);
-    for offset in memchr::memmem::find_iter(data, &bytes) {
-        f(offset);
-    }
-    Ok(())
+    let bytes = num_bytes::<N>(input)?;
+    Ok((
+        N::BYTE_LEN,
+        sliced(move |hay, f| {
+            memchr::memmem::find_iter(hay, &bytes).for_each(|off| f(off..off + N::BYTE_LEN));
+            Ok(())
+        }),
+    ))
 }
 
 fn find_u8(dia: &mut FindDialog, data: &[u8], initial_offset: usize, msg: &mut MessageDialog) {
@@ -13,6 +13,7 @@ use {
 
 const L_NEW_FROM_PERSPECTIVE: &str = concat!(ic::PLUS, " New from perspective");
 const L_HEX: &str = concat!(ic::HEXAGON, " Hex");
+const L_BIN: &str = concat!(ic::BINARY, " Binary");
 const L_TEXT: &str = concat!(ic::TEXT_AA, " Text");
 const L_BLOCK: &str = concat!(ic::RECTANGLE, " Block");
 const L_ADD_TO_NEW_ROW: &str = concat!(ic::PLUS, ic::ARROW_BEND_DOWN_RIGHT);
@@ -224,6 +225,11 @@ fn add_new_view_menu(
                         View::new(ViewKind::Hex(HexData::with_font_size(font_size)), per_key);
                     new = Some(("hex", view));
                 }
+                if ui.button(L_BIN).clicked() {
+                    let view =
+                        View::new(ViewKind::Bin(HexData::with_font_size(font_size)), per_key);
+                    new = Some(("bin", view));
+                }
                 if ui.button(L_TEXT).clicked() {
                     let view = View::new(
                         #[expect(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
@@ -1,6 +1,6 @@
 use {
     super::{WinCtx, WindowOpen},
-    crate::{meta::ScriptKey, scripting::exec_lua, shell::msg_if_fail},
+    crate::{meta::ScriptKey, scripting::exec_lua},
     std::{collections::HashMap, fmt::Write as _},
 };
 
@@ -115,11 +115,7 @@ impl super::Window for LuaConsoleWindow {
                         }
                     }
                 }
-                msg_if_fail(
-                    app.clipboard.set_text(buf),
-                    "Failed to copy clipboard text",
-                    &mut gui.msg_dialog,
-                );
+                crate::app::set_clipboard_string(&mut app.clipboard, &mut gui.msg_dialog, &buf);
             }
         });
         ui.separator();
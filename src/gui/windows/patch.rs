@@ -0,0 +1,194 @@
+use {
+    super::{WinCtx, WindowOpen},
+    crate::{
+        app::{App, read_source_to_buf},
+        bindiff::HunkKind,
+        patch::{self, PatchFormat, Patched},
+        shell::{msg_fail, msg_if_fail},
+    },
+    std::path::{Path, PathBuf},
+    strum::IntoEnumIterator as _,
+};
+
+/// Creating patches from the changes to the data, and applying patches to it
+#[derive(Default)]
+pub struct PatchWindow {
+    pub open: WindowOpen,
+    pub format: PatchFormat,
+    preview: Option<Preview>,
+}
+
+/// A loaded patch, applied to a copy of the data for reviewing before committing it
+struct Preview {
+    path: PathBuf,
+    patch: Vec<u8>,
+    patched: Patched,
+}
+
+impl PatchWindow {
+    /// Create a patch from the file at `path` to the data
+    pub fn create(&self, app: &App, path: &Path) -> anyhow::Result<Vec<u8>> {
+        let source = read_source_to_buf(path, &app.src_args)?;
+        self.format.create(&source, &app.data)
+    }
+    /// Load the patch at `path`, and preview applying it to the data
+    pub fn load(&mut self, app: &App, path: PathBuf) -> anyhow::Result<()> {
+        let patch = std::fs::read(&path)?;
+        let patched = patch::apply(&patch, &app.data)?;
+        self.preview = Some(Preview {
+            path,
+            patch,
+            patched,
+        });
+        self.open.set(true);
+        Ok(())
+    }
+}
+
+impl super::Window for PatchWindow {
+    fn ui(&mut self, WinCtx { ui, gui, app, .. }: WinCtx) {
+        ui.heading("Create");
+        ui.horizontal(|ui| {
+            ui.label("Format");
+            egui::ComboBox::new("patch_format_cbox", "")
+                .selected_text(self.format.label())
+                .show_ui(ui, |ui| {
+                    for format in PatchFormat::iter() {
+                        ui.selectable_value(&mut self.format, format, format.label());
+                    }
+                });
+        });
+        let mut from = None;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    app.source_file().is_some(),
+                    egui::Button::new("From source file"),
+                )
+                .on_hover_text("Patch the file on disk to the data as it is now")
+                .clicked()
+            {
+                from = app.source_file().map(Path::to_owned);
+            }
+            let backup = app.backup_path().filter(|path| path.exists());
+            if ui.add_enabled(backup.is_some(), egui::Button::new("From backup")).clicked() {
+                from = backup;
+            }
+            if ui.button("From file...").clicked() {
+                gui.fileops.create_patch_from(self.format, app.source_file());
+            }
+        });
+        if let Some(path) = from {
+            match self.create(app, &path) {
+                Ok(patch) => gui.fileops.save_patch(patch),
+                Err(e) => msg_fail(&e, "Failed to create patch", &mut gui.msg_dialog),
+            }
+        }
+        ui.separator();
+        ui.heading("Apply");
+        if ui.button("Open patch...").clicked() {
+            gui.fileops.load_patch(app.source_file());
+        }
+        let Some(preview) = &self.preview else {
+            return;
+        };
+        ui.label(format!(
+            "{} patch {}",
+            preview.patched.format.label(),
+            preview.path.display()
+        ));
+        let (old_len, new_len) = (app.data.len(), preview.patched.data.len());
+        if old_len == new_len {
+            ui.label(format!("Size stays {new_len} bytes"));
+        } else {
+            ui.label(format!("Size changes from {old_len} to {new_len} bytes"));
+        }
+        let hunks = &preview.patched.hunks;
+        ui.label(format!("{} changes", hunks.len()));
+        egui::ScrollArea::vertical().max_height(240.0).show_rows(
+            ui,
+            ui.text_style_height(&egui::TextStyle::Body),
+            hunks.len(),
+            |ui, rows| {
+                for hunk in &hunks[rows] {
+                    let label = match hunk.kind {
+                        HunkKind::Changed => format!(
+                            "{:X}..{:X}: {} bytes changed",
+                            hunk.a.start,
+                            hunk.a.end,
+                            hunk.a.len()
+                        ),
+                        HunkKind::Inserted => {
+                            format!("{:X}: {} bytes inserted", hunk.a.start, hunk.b.len())
+                        }
+                        HunkKind::Deleted => format!(
+                            "{:X}..{:X}: {} bytes removed",
+                            hunk.a.start,
+                            hunk.a.end,
+                            hunk.a.len()
+                        ),
+                    };
+                    if ui.link(label).clicked() {
+                        app.search_focus(hunk.a.start.min(app.data.len().saturating_sub(1)));
+                    }
+                }
+            },
+        );
+        let (mut apply, mut discard) = (false, false);
+        ui.horizontal(|ui| {
+            apply = ui.button("✔ Apply").clicked();
+            discard = ui.button("🗙 Discard").clicked();
+        });
+        if apply
+            && msg_if_fail(
+                commit(app, &preview.patch),
+                "Failed to apply patch",
+                &mut gui.msg_dialog,
+            )
+            .is_none()
+        {
+            discard = true;
+        }
+        if discard {
+            self.preview = None;
+        }
+    }
+
+    fn title(&self) -> &str {
+        "Patch"
+    }
+
+    fn clear_doc_state(&mut self) {
+        self.preview = None;
+    }
+}
+
+/// Apply `patch` to the data, recording the changes in the edit history
+fn commit(app: &mut App, patch: &[u8]) -> anyhow::Result<()> {
+    // Applied again, in case the data changed since previewing
+    let patched = patch::apply(patch, &app.data)?;
+    anyhow::ensure!(
+        patched.hunks.iter().all(|hunk| hunk.a.len() == hunk.b.len()) || app.data.can_change_len(),
+        "Changing the size requires the data to be loaded into memory \
+         (not memory mapped or process memory)"
+    );
+    // A single undo step for the whole patch.
+    // Hunks are applied from the end, so the offsets of the ones before stay valid.
+    let splices = app.data.edit_group(|data| {
+        let mut splices = Vec::new();
+        for hunk in patched.hunks.iter().rev() {
+            let bytes = &patched.data[hunk.b.clone()];
+            if hunk.a.len() == bytes.len() {
+                data.write(hunk.a.start, bytes);
+            } else {
+                splices.extend(data.splice(hunk.a.start, hunk.a.len(), bytes));
+            }
+        }
+        splices
+    });
+    for splice in splices {
+        app.follow_splice(splice);
+    }
+    app.edit_state.cursor = app.edit_state.cursor.min(app.data.len().saturating_sub(1));
+    Ok(())
+}
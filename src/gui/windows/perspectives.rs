@@ -1,9 +1,6 @@
 use {
     super::{WinCtx, WindowOpen},
-    crate::{
-        app::command::Cmd, gui::windows::regions::region_context_menu, meta::PerspectiveKey,
-        shell::msg_if_fail,
-    },
+    crate::{app::command::Cmd, gui::windows::regions::region_context_menu, meta::PerspectiveKey},
     egui_extras::{Column, TableBuilder},
     slotmap::Key as _,
 };
@@ -78,11 +75,10 @@ impl super::Window for PerspectivesWindow {
                                     }
                                 });
                                 if ui.button("Copy name to clipboard").clicked() {
-                                    let res = app.clipboard.set_text(name);
-                                    msg_if_fail(
-                                        res,
-                                        "Failed to copy to clipboard",
+                                    crate::app::set_clipboard_string(
+                                        &mut app.clipboard,
                                         &mut gui.msg_dialog,
+                                        name,
                                     );
                                 }
                             });
@@ -0,0 +1,261 @@
+use {
+    super::{WinCtx, WindowOpen},
+    crate::{
+        app::App,
+        scanner::{ScanFilter, ScanSession, parse_value},
+        shell::msg_if_fail,
+        struct_meta_item::{Endian, StructPrimitive, eval::read_value},
+    },
+    egui_extras::{Column, TableBuilder},
+};
+
+/// Scan for a value, then narrow the results down as the value changes
+pub struct ScannerWindow {
+    pub open: WindowOpen,
+    ty: StructPrimitive,
+    endian: Endian,
+    align: usize,
+    value_input: String,
+    first_scan_kind: FirstScan,
+    next_filter: FilterKind,
+    /// Reload the source before every scan, so the values are fresh
+    reload_before_scan: bool,
+    session: Option<ScanSession>,
+}
+
+impl Default for ScannerWindow {
+    fn default() -> Self {
+        Self {
+            open: WindowOpen::default(),
+            ty: StructPrimitive::U32,
+            endian: Endian::Le,
+            align: 4,
+            value_input: String::new(),
+            first_scan_kind: FirstScan::Exact,
+            next_filter: FilterKind::Changed,
+            reload_before_scan: true,
+            session: None,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum FirstScan {
+    Exact,
+    Unknown,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum FilterKind {
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+    Equals,
+}
+
+impl FilterKind {
+    const ALL: [Self; 5] = [
+        Self::Changed,
+        Self::Unchanged,
+        Self::Increased,
+        Self::Decreased,
+        Self::Equals,
+    ];
+    fn label(self) -> &'static str {
+        match self {
+            Self::Changed => "Changed",
+            Self::Unchanged => "Unchanged",
+            Self::Increased => "Increased",
+            Self::Decreased => "Decreased",
+            Self::Equals => "Equals value",
+        }
+    }
+}
+
+/// Don't try to list more candidates than this
+const MAX_LISTED: usize = 100_000;
+
+impl super::Window for ScannerWindow {
+    fn ui(&mut self, WinCtx { ui, gui, app, .. }: WinCtx) {
+        let in_session = self.session.is_some();
+        ui.add_enabled_ui(!in_session, |ui| {
+            ui.horizontal(|ui| {
+                egui::ComboBox::new("scanner_ty_cbox", "Type")
+                    .selected_text(self.ty.label())
+                    .show_ui(ui, |ui| {
+                        for ty in StructPrimitive::ALL {
+                            if ui.selectable_label(self.ty == ty, ty.label()).clicked() {
+                                self.ty = ty;
+                                self.align = ty.size();
+                            }
+                        }
+                    });
+                if ui.button(self.endian.label()).on_hover_text("Endianness").clicked() {
+                    self.endian.toggle();
+                }
+                ui.label("Alignment");
+                ui.add(egui::DragValue::new(&mut self.align).range(1..=64));
+            });
+        });
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.value_input).hint_text("Value"));
+            ui.checkbox(&mut self.reload_before_scan, "Reload")
+                .on_hover_text("Reload the source before every scan");
+        });
+        ui.separator();
+        let mut start_session = false;
+        let mut end_session = false;
+        match &mut self.session {
+            None => {
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.first_scan_kind, FirstScan::Exact, "Exact value");
+                    ui.radio_value(
+                        &mut self.first_scan_kind,
+                        FirstScan::Unknown,
+                        "Unknown initial value",
+                    );
+                });
+                start_session = ui.button("First scan").clicked();
+            }
+            Some(session) => {
+                ui.horizontal(|ui| {
+                    egui::ComboBox::new("scanner_filter_cbox", "")
+                        .selected_text(self.next_filter.label())
+                        .show_ui(ui, |ui| {
+                            for kind in FilterKind::ALL {
+                                ui.selectable_value(&mut self.next_filter, kind, kind.label());
+                            }
+                        });
+                    if ui.button("Next scan").clicked() {
+                        let result = next_scan(
+                            session,
+                            app,
+                            self.next_filter,
+                            &self.value_input,
+                            self.reload_before_scan,
+                        );
+                        msg_if_fail(result, "Next scan failed", &mut gui.msg_dialog);
+                    }
+                    end_session =
+                        ui.button("New scan").on_hover_text("End this scan session").clicked();
+                });
+            }
+        }
+        if start_session {
+            msg_if_fail(
+                self.first_scan(app),
+                "First scan failed",
+                &mut gui.msg_dialog,
+            );
+        }
+        if end_session {
+            self.session = None;
+        }
+        let Some(session) = &self.session else {
+            return;
+        };
+        ui.label(format!(
+            "Scan #{}: {} candidates",
+            session.scans,
+            session.len()
+        ));
+        let Some(cands) = session.candidates() else {
+            ui.label("Do a next scan to narrow down the candidates");
+            return;
+        };
+        if cands.len() > MAX_LISTED {
+            ui.label(format!(
+                "Too many to list. Narrow down to {MAX_LISTED} or less."
+            ));
+            return;
+        }
+        let (ty, endian) = (session.ty, session.endian);
+        let mut goto = None;
+        TableBuilder::new(ui)
+            .columns(Column::auto(), 2)
+            .column(Column::remainder())
+            .striped(true)
+            .resizable(true)
+            .header(20.0, |mut row| {
+                row.col(|ui| {
+                    ui.label("Offset");
+                });
+                row.col(|ui| {
+                    ui.label("Last scan");
+                });
+                row.col(|ui| {
+                    ui.label("Current");
+                });
+            })
+            .body(|body| {
+                body.rows(20.0, cands.len(), |mut row| {
+                    let cand = &cands[row.index()];
+                    row.col(|ui| {
+                        if ui.link(format!("{:X}", cand.offset)).clicked() {
+                            goto = Some(cand.offset);
+                        }
+                    });
+                    row.col(|ui| {
+                        ui.label(cand.value.to_string());
+                    });
+                    row.col(|ui| {
+                        match read_value(&app.data, cand.offset, ty, endian) {
+                            Some(v) => ui.label(v.to_string()),
+                            None => ui.label("-"),
+                        };
+                    });
+                });
+            });
+        if let Some(off) = goto {
+            app.search_focus(off);
+        }
+    }
+
+    fn title(&self) -> &str {
+        "Memory scanner"
+    }
+
+    fn clear_doc_state(&mut self) {
+        self.session = None;
+    }
+}
+
+impl ScannerWindow {
+    fn first_scan(&mut self, app: &mut App) -> anyhow::Result<()> {
+        if self.reload_before_scan {
+            app.reload()?;
+        }
+        self.session = Some(match self.first_scan_kind {
+            FirstScan::Exact => {
+                let value = parse_value(&self.value_input, self.ty)?;
+                ScanSession::first_scan_exact(&app.data, self.ty, self.endian, self.align, value)
+            }
+            FirstScan::Unknown => {
+                ScanSession::first_scan_unknown(&app.data, self.ty, self.endian, self.align)
+            }
+        });
+        Ok(())
+    }
+}
+
+fn next_scan(
+    session: &mut ScanSession,
+    app: &mut App,
+    kind: FilterKind,
+    value_input: &str,
+    reload: bool,
+) -> anyhow::Result<()> {
+    let filter = match kind {
+        FilterKind::Changed => ScanFilter::Changed,
+        FilterKind::Unchanged => ScanFilter::Unchanged,
+        FilterKind::Increased => ScanFilter::Increased,
+        FilterKind::Decreased => ScanFilter::Decreased,
+        FilterKind::Equals => ScanFilter::Equals(parse_value(value_input, session.ty)?),
+    };
+    if reload {
+        app.reload()?;
+    }
+    session.next_scan(&app.data, filter);
+    Ok(())
+}
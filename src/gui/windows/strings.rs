@@ -0,0 +1,381 @@
+use {
+    super::{WinCtx, WindowOpen},
+    crate::meta::{Bookmark, NamedRegion, value_type::ValueType},
+    egui_extras::{Column, TableBuilder},
+    std::collections::BTreeSet,
+};
+
+/// Printable strings in the data, like the `strings` utility
+pub struct StringsWindow {
+    pub open: WindowOpen,
+    /// Minimum length in characters
+    min_len: usize,
+    ascii: bool,
+    utf8: bool,
+    utf16le: bool,
+    utf16be: bool,
+    hits: Vec<StrHit>,
+    filter: String,
+    /// Indices into `hits` that pass the filter
+    filtered: Vec<usize>,
+    /// Indices into `hits` that are selected
+    selected: BTreeSet<usize>,
+}
+
+impl Default for StringsWindow {
+    fn default() -> Self {
+        Self {
+            open: WindowOpen::default(),
+            min_len: 4,
+            ascii: true,
+            utf8: false,
+            utf16le: true,
+            utf16be: false,
+            hits: Vec::new(),
+            filter: String::new(),
+            filtered: Vec::new(),
+            selected: BTreeSet::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StrEncoding {
+    Ascii,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl StrEncoding {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Ascii => "ASCII",
+            Self::Utf8 => "UTF-8",
+            Self::Utf16Le => "UTF-16LE",
+            Self::Utf16Be => "UTF-16BE",
+        }
+    }
+}
+
+struct StrHit {
+    offset: usize,
+    /// Length in bytes
+    len: usize,
+    enc: StrEncoding,
+    text: String,
+}
+
+/// Maximum number of characters of a string used for bookmark and region names
+const NAME_MAX_CHARS: usize = 32;
+
+impl super::Window for StringsWindow {
+    fn ui(&mut self, WinCtx { ui, gui, app, .. }: WinCtx) {
+        ui.horizontal(|ui| {
+            ui.label("Min length");
+            ui.add(egui::DragValue::new(&mut self.min_len).range(1..=1024));
+            ui.checkbox(&mut self.ascii, StrEncoding::Ascii.label());
+            ui.checkbox(&mut self.utf8, StrEncoding::Utf8.label()).on_hover_text(
+                "Only strings that contain non-ASCII characters, if ASCII is checked",
+            );
+            ui.checkbox(&mut self.utf16le, StrEncoding::Utf16Le.label());
+            ui.checkbox(&mut self.utf16be, StrEncoding::Utf16Be.label());
+            if ui.button("Extract").clicked() {
+                let encodings = [
+                    (self.ascii, StrEncoding::Ascii),
+                    (self.utf8, StrEncoding::Utf8),
+                    (self.utf16le, StrEncoding::Utf16Le),
+                    (self.utf16be, StrEncoding::Utf16Be),
+                ];
+                let encodings: Vec<_> = encodings
+                    .into_iter()
+                    .filter_map(|(enabled, enc)| enabled.then_some(enc))
+                    .collect();
+                self.hits = extract_strings(&app.data, self.min_len, &encodings);
+                self.selected.clear();
+                self.apply_filter();
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .add(egui::TextEdit::singleline(&mut self.filter).hint_text("Filter"))
+                .changed()
+            {
+                self.apply_filter();
+            }
+            ui.label(format!(
+                "{}/{} strings, {} selected",
+                self.filtered.len(),
+                self.hits.len(),
+                self.selected.len()
+            ));
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Select all").on_hover_text("Select all shown strings").clicked() {
+                self.selected.extend(&self.filtered);
+            }
+            if ui.button("Select none").clicked() {
+                self.selected.clear();
+            }
+            ui.separator();
+            let any_selected = !self.selected.is_empty();
+            if ui.add_enabled(any_selected, egui::Button::new("Add bookmarks")).clicked() {
+                for &i in &self.selected {
+                    let hit = &self.hits[i];
+                    app.meta_state.meta.bookmarks.push(Bookmark {
+                        offset: hit.offset,
+                        label: hit.name(),
+                        desc: String::new(),
+                        value_type: ValueType::None,
+                        frozen: None,
+                    });
+                }
+                gui.win.bookmarks.open.set(true);
+            }
+            if ui.add_enabled(any_selected, egui::Button::new("Add regions")).clicked() {
+                for &i in &self.selected {
+                    let hit = &self.hits[i];
+                    app.meta_state.meta.low.regions.insert(NamedRegion::new(
+                        hit.name(),
+                        hit.offset,
+                        hit.offset + hit.len - 1,
+                    ));
+                }
+                gui.win.regions.open.set(true);
+            }
+        });
+        ui.separator();
+        TableBuilder::new(ui)
+            .column(Column::auto())
+            .columns(Column::auto(), 3)
+            .column(Column::remainder())
+            .auto_shrink([false, true])
+            .striped(true)
+            .header(20.0, |mut row| {
+                row.col(|_ui| {});
+                for label in ["Offset", "Length", "Encoding", "Text"] {
+                    row.col(|ui| {
+                        ui.label(label);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(20.0, self.filtered.len(), |mut row| {
+                    let i = self.filtered[row.index()];
+                    let hit = &self.hits[i];
+                    row.set_selected(self.selected.contains(&i));
+                    row.col(|ui| {
+                        let mut sel = self.selected.contains(&i);
+                        if ui.checkbox(&mut sel, "").changed() {
+                            if sel {
+                                self.selected.insert(i);
+                            } else {
+                                self.selected.remove(&i);
+                            }
+                        }
+                    });
+                    row.col(|ui| {
+                        if ui.link(format!("{:X}", hit.offset)).clicked() {
+                            app.search_focus(hit.offset);
+                        }
+                    });
+                    row.col(|ui| {
+                        ui.label(hit.len.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(hit.enc.label());
+                    });
+                    row.col(|ui| {
+                        if ui.link(&hit.text).clicked() {
+                            app.search_focus(hit.offset);
+                        }
+                    });
+                });
+            });
+    }
+
+    fn title(&self) -> &str {
+        "Strings"
+    }
+
+    fn clear_doc_state(&mut self) {
+        self.hits.clear();
+        self.filtered.clear();
+        self.selected.clear();
+    }
+}
+
+impl StringsWindow {
+    fn apply_filter(&mut self) {
+        let filter = self.filter.to_lowercase();
+        self.filtered = self
+            .hits
+            .iter()
+            .enumerate()
+            .filter(|(_, hit)| filter.is_empty() || hit.text.to_lowercase().contains(&filter))
+            .map(|(i, _)| i)
+            .collect();
+    }
+}
+
+impl StrHit {
+    fn name(&self) -> String {
+        let mut name: String = self.text.chars().take(NAME_MAX_CHARS).collect();
+        if name.len() < self.text.len() {
+            name.push('…');
+        }
+        name
+    }
+}
+
+fn is_printable_ascii(b: u8) -> bool {
+    b.is_ascii_graphic() || b == b' ' || b == b'\t'
+}
+
+/// Whether `c` can be part of an UTF-16 string.
+///
+/// Only alphabetic scripts below U+0800 are considered, because random binary data is full of
+/// u16 values that are valid CJK characters.
+fn is_printable_utf16(c: char) -> bool {
+    c < '\u{800}' && (c == '\t' || !c.is_control())
+}
+
+/// Every run of at least `min_len` printable characters in `data`, in the given encodings,
+/// sorted by offset
+fn extract_strings(data: &[u8], min_len: usize, encodings: &[StrEncoding]) -> Vec<StrHit> {
+    let min_len = min_len.max(1);
+    let mut hits = Vec::new();
+    let ascii = encodings.contains(&StrEncoding::Ascii);
+    for &enc in encodings {
+        match enc {
+            StrEncoding::Ascii => {
+                let mut offset = 0;
+                for run in data.chunk_by(|&a, &b| is_printable_ascii(a) == is_printable_ascii(b)) {
+                    if run.len() >= min_len && is_printable_ascii(run[0]) {
+                        hits.push(StrHit {
+                            offset,
+                            len: run.len(),
+                            enc,
+                            text: run.iter().map(|&b| char::from(b)).collect(),
+                        });
+                    }
+                    offset += run.len();
+                }
+            }
+            StrEncoding::Utf8 => extract_utf8(data, min_len, ascii, &mut hits),
+            StrEncoding::Utf16Le | StrEncoding::Utf16Be => {
+                for parity in 0..2 {
+                    extract_utf16(data, parity, min_len, enc, &mut hits);
+                }
+            }
+        }
+    }
+    hits.sort_by_key(|hit| hit.offset);
+    hits
+}
+
+/// If `skip_ascii` is set, only strings with non-ASCII characters are extracted,
+/// as the rest are found by the ASCII extraction.
+fn extract_utf8(data: &[u8], min_len: usize, skip_ascii: bool, hits: &mut Vec<StrHit>) {
+    let mut push_run = |offset: usize, run: &str| {
+        if run.chars().count() >= min_len && !(skip_ascii && run.is_ascii()) {
+            hits.push(StrHit {
+                offset,
+                len: run.len(),
+                enc: StrEncoding::Utf8,
+                text: run.to_owned(),
+            });
+        }
+    };
+    let mut offset = 0;
+    for chunk in data.utf8_chunks() {
+        let valid = chunk.valid();
+        let mut run_start = 0;
+        for (i, c) in valid.char_indices() {
+            if c != '\t' && c.is_control() {
+                push_run(offset + run_start, &valid[run_start..i]);
+                run_start = i + c.len_utf8();
+            }
+        }
+        push_run(offset + run_start, &valid[run_start..]);
+        offset += valid.len() + chunk.invalid().len();
+    }
+}
+
+/// Extract from the u16 units starting at `parity`
+fn extract_utf16(
+    data: &[u8],
+    parity: usize,
+    min_len: usize,
+    enc: StrEncoding,
+    hits: &mut Vec<StrHit>,
+) {
+    let Some(data) = data.get(parity..) else {
+        return;
+    };
+    let (units, _) = data.as_chunks::<2>();
+    let mut run = String::new();
+    let mut run_start = 0;
+    let mut run_units = 0;
+    let mut flush = |run: &mut String, run_start: usize, run_units: &mut usize| {
+        if *run_units >= min_len {
+            hits.push(StrHit {
+                offset: parity + run_start * 2,
+                len: *run_units * 2,
+                enc,
+                text: std::mem::take(run),
+            });
+        }
+        run.clear();
+        *run_units = 0;
+    };
+    for (i, &unit) in units.iter().enumerate() {
+        let unit = match enc {
+            StrEncoding::Utf16Be => u16::from_be_bytes(unit),
+            _ => u16::from_le_bytes(unit),
+        };
+        match char::from_u32(u32::from(unit)).filter(|&c| is_printable_utf16(c)) {
+            Some(c) => {
+                if run_units == 0 {
+                    run_start = i;
+                }
+                run.push(c);
+                run_units += 1;
+            }
+            None => flush(&mut run, run_start, &mut run_units),
+        }
+    }
+    flush(&mut run, run_start, &mut run_units);
+}
+
+#[test]
+fn test_extract_strings() {
+    let summary = |hits: Vec<StrHit>| -> Vec<(usize, String, StrEncoding)> {
+        hits.into_iter().map(|hit| (hit.offset, hit.text, hit.enc)).collect()
+    };
+    let mut data = b"\0\x01hello\xffhi\0".to_vec();
+    data.extend("wörld\n".as_bytes());
+    data.extend("test\0".encode_utf16().flat_map(u16::to_le_bytes));
+    assert_eq!(
+        summary(extract_strings(
+            &data,
+            3,
+            &[StrEncoding::Ascii, StrEncoding::Utf8]
+        )),
+        [
+            (2, "hello".into(), StrEncoding::Ascii),
+            (11, "wörld".into(), StrEncoding::Utf8),
+            (14, "rld".into(), StrEncoding::Ascii),
+        ]
+    );
+    let hits = extract_strings(&data, 3, &[StrEncoding::Utf16Le]);
+    assert_eq!(hits.len(), 1);
+    assert_eq!((hits[0].offset, hits[0].len), (18, 8));
+    assert_eq!(hits[0].text, "test");
+    let mut data = vec![0; 3];
+    data.extend("Big".encode_utf16().flat_map(u16::to_be_bytes));
+    assert_eq!(
+        summary(extract_strings(&data, 3, &[StrEncoding::Utf16Be])),
+        [(3, "Big".into(), StrEncoding::Utf16Be)]
+    );
+}
@@ -0,0 +1,334 @@
+use {
+    super::{WinCtx, WindowOpen, structs::primitive_edit_ui},
+    crate::{
+        app::App,
+        meta::region::Region,
+        struct_meta_item::eval::{EvalKind, Value, eval_struct, read_value},
+    },
+    egui_extras::{Column, TableBuilder},
+    std::{cmp::Ordering, fmt::Write as _},
+};
+
+/// Upper limit on the number of records, so a tiny struct over a huge region doesn't hang
+const MAX_RECORDS: usize = 100_000;
+
+/// A struct applied repeatedly over a region, shown as a table
+#[derive(Default)]
+pub struct StructTableWindow {
+    pub open: WindowOpen,
+    /// Name of the struct applied
+    pub struct_name: String,
+    /// The region the records are laid out in
+    pub region: Option<Region>,
+    /// Full path of each column, like `header.len`
+    columns: Vec<String>,
+    records: Vec<Record>,
+    /// Indices into `records`, in display order
+    order: Vec<usize>,
+    /// Sorted column, and whether it's descending
+    sort: Option<(usize, bool)>,
+    error: String,
+}
+
+struct Record {
+    offset: usize,
+    /// Indexed by column. `None` if the field isn't present in this record.
+    cells: Vec<Option<Cell>>,
+}
+
+struct Cell {
+    offset: usize,
+    size: usize,
+    kind: EvalKind,
+}
+
+impl super::Window for StructTableWindow {
+    fn ui(&mut self, WinCtx { ui, gui, app, .. }: WinCtx) {
+        if self.open.just_now() {
+            self.lay_out(app);
+        }
+        ui.horizontal(|ui| {
+            egui::ComboBox::new("struct_table_cbox", "Struct")
+                .selected_text(&self.struct_name)
+                .show_ui(ui, |ui| {
+                    for struct_ in &app.meta_state.meta.structs {
+                        ui.selectable_value(
+                            &mut self.struct_name,
+                            struct_.name.clone(),
+                            &struct_.name,
+                        );
+                    }
+                });
+            egui::ComboBox::new("struct_table_reg_cbox", "Region")
+                .selected_text(
+                    self.region
+                        .map_or("-".into(), |reg| format!("{:X}..={:X}", reg.begin, reg.end)),
+                )
+                .show_ui(ui, |ui| {
+                    if let Some(sel) = app.hex_ui.selection()
+                        && ui.button("Selection").clicked()
+                    {
+                        self.region = Some(sel);
+                    }
+                    for (_key, reg) in app.meta_state.meta.low.regions.iter() {
+                        if ui.button(&reg.name).clicked() {
+                            self.region = Some(reg.region);
+                        }
+                    }
+                });
+            if ui.button("Apply").on_hover_text("Lay out the records again").clicked() {
+                self.lay_out(app);
+            }
+            if ui
+                .add_enabled(!self.records.is_empty(), egui::Button::new("Export CSV..."))
+                .clicked()
+            {
+                gui.fileops.save_struct_table_csv(self.to_csv(&app.data));
+            }
+        });
+        if !self.error.is_empty() {
+            ui.label(egui::RichText::new(&self.error).color(egui::Color32::RED));
+        }
+        ui.label(format!("{} records", self.records.len()));
+        ui.separator();
+        let mut sort_clicked = None;
+        let mut goto = None;
+        let mut edited = false;
+        egui::ScrollArea::horizontal().show(ui, |ui| {
+            TableBuilder::new(ui)
+                .columns(Column::auto(), self.columns.len() + 1)
+                .striped(true)
+                .resizable(true)
+                .header(20.0, |mut row| {
+                    row.col(|ui| {
+                        ui.label("Offset");
+                    });
+                    for (i, name) in self.columns.iter().enumerate() {
+                        row.col(|ui| {
+                            let arrow = match self.sort {
+                                Some((col, false)) if col == i => " ⏶",
+                                Some((col, true)) if col == i => " ⏷",
+                                _ => "",
+                            };
+                            if ui.button(format!("{name}{arrow}")).clicked() {
+                                sort_clicked = Some(i);
+                            }
+                        });
+                    }
+                })
+                .body(|body| {
+                    body.rows(20.0, self.order.len(), |mut row| {
+                        let rec = &self.records[self.order[row.index()]];
+                        row.col(|ui| {
+                            if ui.link(format!("{:X}", rec.offset)).clicked() {
+                                goto = Some(rec.offset);
+                            }
+                        });
+                        for col in 0..self.columns.len() {
+                            row.col(|ui| {
+                                let Some(Some(cell)) = rec.cells.get(col) else {
+                                    return;
+                                };
+                                edited |= cell_ui(ui, cell, app);
+                            });
+                        }
+                    });
+                });
+        });
+        if let Some(col) = sort_clicked {
+            let desc = self.sort == Some((col, false));
+            self.sort = Some((col, desc));
+            self.sort_records(&app.data);
+        }
+        if let Some(off) = goto {
+            app.search_focus(off);
+        }
+        // Edited values can change the layout (lengths, conditions, enum variants)
+        if edited {
+            self.lay_out(app);
+        }
+    }
+
+    fn title(&self) -> &str {
+        "Struct table"
+    }
+
+    fn clear_doc_state(&mut self) {
+        self.region = None;
+        self.records.clear();
+        self.order.clear();
+    }
+}
+
+impl StructTableWindow {
+    /// Evaluate the struct repeatedly over the region
+    fn lay_out(&mut self, app: &App) {
+        self.columns.clear();
+        self.records.clear();
+        self.order.clear();
+        self.error.clear();
+        let Some(reg) = self.region else {
+            return;
+        };
+        let structs = &app.meta_state.meta.structs;
+        let Some(struct_) = structs.iter().find(|s| s.name == self.struct_name) else {
+            self.error = format!("No struct named '{}'", self.struct_name);
+            return;
+        };
+        let mut offset = reg.begin;
+        while offset <= reg.end {
+            if self.records.len() >= MAX_RECORDS {
+                self.error = format!("Stopped at {MAX_RECORDS} records");
+                break;
+            }
+            let ev = eval_struct(struct_, structs, &app.data, offset);
+            let Some(size) = ev.size.filter(|&size| size != 0) else {
+                self.error = format!("Failed to evaluate record at {offset:X}");
+                break;
+            };
+            // A partial record at the end doesn't count
+            if offset + size > reg.end + 1 {
+                break;
+            }
+            let mut path: Vec<&str> = Vec::new();
+            let mut cells = Vec::new();
+            for row in &ev.rows {
+                path.truncate(row.depth);
+                path.push(&row.name);
+                if matches!(row.kind, EvalKind::Group) {
+                    continue;
+                }
+                let name = path.join(".");
+                let col = match self.columns.iter().position(|col| *col == name) {
+                    Some(col) => col,
+                    None => {
+                        self.columns.push(name);
+                        self.columns.len() - 1
+                    }
+                };
+                if cells.len() <= col {
+                    cells.resize_with(col + 1, || None);
+                }
+                cells[col] = Some(Cell {
+                    offset: row.offset,
+                    size: row.size,
+                    kind: row.kind.clone(),
+                });
+            }
+            self.records.push(Record { offset, cells });
+            offset += size;
+        }
+        self.order = (0..self.records.len()).collect();
+        self.sort_records(&app.data);
+    }
+    fn sort_records(&mut self, data: &[u8]) {
+        let Some((col, desc)) = self.sort else {
+            return;
+        };
+        let key = |idx: usize| {
+            self.records[idx]
+                .cells
+                .get(col)
+                .and_then(Option::as_ref)
+                .and_then(|cell| cell_value(data, cell))
+        };
+        self.order.sort_by(|&a, &b| {
+            let ord = key(a).partial_cmp(&key(b)).unwrap_or(Ordering::Equal);
+            if desc { ord.reverse() } else { ord }
+        });
+    }
+    fn to_csv(&self, data: &[u8]) -> String {
+        let mut out = String::from("offset");
+        for col in &self.columns {
+            out.push(',');
+            out.push_str(&csv_field(col));
+        }
+        out.push('\n');
+        for &idx in &self.order {
+            let rec = &self.records[idx];
+            let _ = write!(out, "{}", rec.offset);
+            for cell in &rec.cells {
+                out.push(',');
+                if let Some(cell) = cell {
+                    out.push_str(&csv_field(&cell_text(data, cell)));
+                }
+            }
+            // Trailing cells of fields that weren't present
+            for _ in rec.cells.len()..self.columns.len() {
+                out.push(',');
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Returns whether the value was edited
+fn cell_ui(ui: &mut egui::Ui, cell: &Cell, app: &mut App) -> bool {
+    let range = cell.offset..cell.offset + cell.size;
+    match &cell.kind {
+        EvalKind::Primitive { ty, endian } | EvalKind::Pointer { ty, endian, .. } => app
+            .data
+            .edit(range, |bytes| primitive_edit_ui(ui, *ty, *endian, bytes))
+            .unwrap_or(false),
+        EvalKind::Enum {
+            ty,
+            endian,
+            variant,
+        } => {
+            let changed = app
+                .data
+                .edit(range, |bytes| primitive_edit_ui(ui, *ty, *endian, bytes))
+                .unwrap_or(false);
+            ui.label(variant.as_deref().unwrap_or("?"));
+            changed
+        }
+        EvalKind::Bits { value, .. } => {
+            ui.label(value.to_string());
+            false
+        }
+        EvalKind::Group => false,
+        EvalKind::Error(msg) => {
+            ui.label(egui::RichText::new("error").color(egui::Color32::RED))
+                .on_hover_text(msg);
+            false
+        }
+    }
+}
+
+fn cell_value(data: &[u8], cell: &Cell) -> Option<Value> {
+    match cell.kind {
+        EvalKind::Primitive { ty, endian }
+        | EvalKind::Enum { ty, endian, .. }
+        | EvalKind::Pointer { ty, endian, .. } => read_value(data, cell.offset, ty, endian),
+        EvalKind::Bits { value, .. } => Some(Value::UInt(value)),
+        EvalKind::Group | EvalKind::Error(_) => None,
+    }
+}
+
+/// Text of a cell for export. Enums are exported by variant name if they have one.
+fn cell_text(data: &[u8], cell: &Cell) -> String {
+    match &cell.kind {
+        EvalKind::Enum {
+            variant: Some(name),
+            ..
+        } => name.clone(),
+        EvalKind::Error(msg) => format!("error: {msg}"),
+        _ => cell_value(data, cell).map_or_else(String::new, |v| v.to_string()),
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[test]
+fn test_csv_field() {
+    assert_eq!(csv_field("plain"), "plain");
+    assert_eq!(csv_field("a,b"), "\"a,b\"");
+    assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+}
@@ -1,10 +1,14 @@
 use {
-    super::WindowOpen,
+    super::{WindowOpen, struct_table::StructTableWindow},
     crate::{
         meta::Meta,
-        struct_meta_item::{Endian, StructMetaItem, StructPrimitive, StructTy},
+        struct_meta_item::{
+            Endian, StructMetaItem, StructPrimitive,
+            eval::{EvalKind, eval_struct},
+        },
     },
     egui_code_editor::{CodeEditor, Syntax},
+    std::fmt::Write as _,
 };
 
 pub struct StructsWindow {
@@ -38,7 +42,7 @@ enum Tab {
 }
 
 impl super::Window for StructsWindow {
-    fn ui(&mut self, super::WinCtx { ui, app, .. }: super::WinCtx) {
+    fn ui(&mut self, super::WinCtx { ui, gui, app, .. }: super::WinCtx) {
         if self.open.just_now()
             && let Some(struct_) = app.meta_state.meta.structs.get(self.selected_idx)
         {
@@ -59,7 +63,7 @@ impl super::Window for StructsWindow {
             });
         });
         ui.separator();
-        self.bottom_bar_ui(ui, app);
+        self.bottom_bar_ui(ui, app, &mut gui.win.struct_table);
     }
 
     fn title(&self) -> &str {
@@ -93,15 +97,10 @@ impl StructsWindow {
 
         if re.changed() {
             self.error_label.clear();
-            match structparse::Struct::parse(&self.struct_text_buf) {
-                Ok(struct_) => match StructMetaItem::new(struct_, self.struct_text_buf.clone()) {
-                    Ok(struct_) => {
-                        self.parsed_struct = Some(struct_);
-                    }
-                    Err(e) => {
-                        self.error_label = format!("Resolve error: {e}");
-                    }
-                },
+            match StructMetaItem::parse(&self.struct_text_buf) {
+                Ok(struct_) => {
+                    self.parsed_struct = Some(struct_);
+                }
                 Err(e) => {
                     self.parsed_struct = None;
                     self.error_label = format!("Parse error: {e}");
@@ -146,7 +145,12 @@ impl StructsWindow {
             }
         });
     }
-    fn bottom_bar_ui(&mut self, ui: &mut egui::Ui, app: &mut crate::app::App) {
+    fn bottom_bar_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        app: &mut crate::app::App,
+        table_win: &mut StructTableWindow,
+    ) {
         match &mut self.parsed_struct {
             Some(struct_) => {
                 let mut del = false;
@@ -165,6 +169,15 @@ impl StructsWindow {
                     if ui.button("Delete").clicked() {
                         del = true;
                     }
+                    if ui
+                        .button("Table...")
+                        .on_hover_text("Apply repeatedly over the selection, as a table")
+                        .clicked()
+                    {
+                        table_win.struct_name = struct_.name.clone();
+                        table_win.region = app.hex_ui.selection();
+                        table_win.open.set(true);
+                    }
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                         if ui.button("Restore all").clicked() {
                             app.meta_state.meta.structs = app.meta_state.clean_meta.structs.clone();
@@ -191,42 +204,94 @@ impl StructsWindow {
 }
 
 fn fields_ui(struct_: &mut StructMetaItem, ui: &mut egui::Ui) {
-    for (_off, field) in struct_.fields_with_offsets_mut() {
+    for field in &mut struct_.fields {
         ui.horizontal(|ui| {
-            ui.label(format!(
-                "{}: {} [size: {}]",
-                field.name,
-                field.ty,
-                field.ty.size()
-            ));
-            let en = field.ty.endian_mut();
-            if ui.checkbox(&mut matches!(en, Endian::Be), en.label()).clicked() {
+            let mut label = String::new();
+            if !field.conds.is_empty() {
+                let conds: Vec<String> = field.conds.iter().map(ToString::to_string).collect();
+                label = format!("if {}: ", conds.join(" && "));
+            }
+            let _ = write!(label, "{}: {}", field.name, field.ty);
+            if let Some(size) = field.ty.size() {
+                let _ = write!(label, " [size: {size}]");
+            }
+            ui.label(label);
+            if let Some(en) = field.ty.endian_mut()
+                && ui.checkbox(&mut matches!(en, Endian::Be), en.label()).clicked()
+            {
                 en.toggle();
             }
         });
     }
 }
 
-fn at_row_ui(struct_: &mut StructMetaItem, ui: &mut egui::Ui, app: &mut crate::app::App) {
+fn at_row_ui(struct_: &StructMetaItem, ui: &mut egui::Ui, app: &mut crate::app::App) {
     if let Some([row, _]) = app.row_col_of_cursor()
         && let Some(reg) = app.row_region(row)
     {
-        for (off, field) in struct_.fields_with_offsets_mut() {
+        let rows = eval_struct(struct_, &app.meta_state.meta.structs, &app.data, reg.begin).rows;
+        for row in rows {
             ui.horizontal(|ui| {
-                let data_off = reg.begin + off;
-                if ui.link(off.to_string()).clicked() {
-                    app.search_focus(data_off);
+                if ui.link((row.offset - reg.begin).to_string()).clicked() {
+                    app.search_focus(row.offset);
                 }
-                ui.label(&field.name);
-                let field_bytes_len = field.ty.size();
-                if let Some(byte_slice) = app.data.get_mut(data_off..data_off + field_bytes_len) {
-                    field_edit_ui(ui, field, byte_slice);
-                } else {
-                    ui.label("<out of bounds>");
+                ui.add_space(f32::from(u16::try_from(row.depth).unwrap_or(u16::MAX)) * 16.0);
+                ui.label(&row.name);
+                let range = row.offset..row.offset + row.size;
+                match row.kind {
+                    EvalKind::Primitive { ty, endian } => {
+                        if app
+                            .data
+                            .edit(range, |bytes| primitive_edit_ui(ui, ty, endian, bytes))
+                            .is_none()
+                        {
+                            ui.label("<out of bounds>");
+                        }
+                    }
+                    EvalKind::Enum {
+                        ty,
+                        endian,
+                        variant,
+                    } => {
+                        if app
+                            .data
+                            .edit(range, |bytes| primitive_edit_ui(ui, ty, endian, bytes))
+                            .is_none()
+                        {
+                            ui.label("<out of bounds>");
+                        }
+                        ui.label(variant.as_deref().unwrap_or("<unknown variant>"));
+                    }
+                    EvalKind::Bits { value, width } => {
+                        ui.label(format!("{value} ({width} bits)"));
+                    }
+                    EvalKind::Pointer { ty, endian, target } => {
+                        if app
+                            .data
+                            .edit(range, |bytes| primitive_edit_ui(ui, ty, endian, bytes))
+                            .is_none()
+                        {
+                            ui.label("<out of bounds>");
+                        }
+                        match target {
+                            Some(target) => {
+                                if ui.link(format!("→ {target:#x}")).clicked() {
+                                    app.search_focus(target);
+                                }
+                            }
+                            None => {
+                                ui.label("→ <out of bounds>");
+                            }
+                        }
+                    }
+                    EvalKind::Group => {}
+                    EvalKind::Error(msg) => {
+                        ui.label(egui::RichText::new(msg).color(egui::Color32::RED));
+                    }
                 }
-                if ui.button("select").clicked() {
-                    app.hex_ui.select_a = Some(data_off);
-                    app.hex_ui.select_b = Some(data_off + field.ty.size().saturating_sub(1));
+                if row.size != 0 && ui.button("select").clicked() {
+                    app.hex_ui.select_a = Some(row.offset);
+                    app.hex_ui.select_b = Some(row.offset + row.size - 1);
                 }
             });
         }
@@ -282,66 +347,65 @@ to_from_impl!(u64);
 to_from_impl!(f32);
 to_from_impl!(f64);
 
-fn field_edit_ui(
+/// Returns whether the value was changed
+pub(super) fn primitive_edit_ui(
     ui: &mut egui::Ui,
-    field: &crate::struct_meta_item::StructField,
+    ty: StructPrimitive,
+    endian: Endian,
     byte_slice: &mut [u8],
-) {
-    match &field.ty {
-        StructTy::Primitive { ty, endian } => match ty {
-            StructPrimitive::I8 => {
-                with_bytes_as_primitive(byte_slice, *endian, |num: &mut i8| {
-                    ui.add(egui::DragValue::new(num));
-                });
-            }
-            StructPrimitive::U8 => {
-                with_bytes_as_primitive(byte_slice, *endian, |num: &mut u8| {
-                    ui.add(egui::DragValue::new(num));
-                });
-            }
-            StructPrimitive::I16 => {
-                with_bytes_as_primitive(byte_slice, *endian, |num: &mut i16| {
-                    ui.add(egui::DragValue::new(num));
-                });
-            }
-            StructPrimitive::U16 => {
-                with_bytes_as_primitive(byte_slice, *endian, |num: &mut u16| {
-                    ui.add(egui::DragValue::new(num));
-                });
-            }
-            StructPrimitive::I32 => {
-                with_bytes_as_primitive(byte_slice, *endian, |num: &mut i32| {
-                    ui.add(egui::DragValue::new(num));
-                });
-            }
-            StructPrimitive::U32 => {
-                with_bytes_as_primitive(byte_slice, *endian, |num: &mut u32| {
-                    ui.add(egui::DragValue::new(num));
-                });
-            }
-            StructPrimitive::I64 => {
-                with_bytes_as_primitive(byte_slice, *endian, |num: &mut i64| {
-                    ui.add(egui::DragValue::new(num));
-                });
-            }
-            StructPrimitive::U64 => {
-                with_bytes_as_primitive(byte_slice, *endian, |num: &mut u64| {
-                    ui.add(egui::DragValue::new(num));
-                });
-            }
-            StructPrimitive::F32 => {
-                with_bytes_as_primitive(byte_slice, *endian, |num: &mut f32| {
-                    ui.add(egui::DragValue::new(num));
-                });
-            }
-            StructPrimitive::F64 => {
-                with_bytes_as_primitive(byte_slice, *endian, |num: &mut f64| {
-                    ui.add(egui::DragValue::new(num));
-                });
-            }
-        },
-        StructTy::Array { .. } => {
-            ui.label("<array>");
+) -> bool {
+    let mut changed = false;
+    match ty {
+        StructPrimitive::I8 => {
+            with_bytes_as_primitive(byte_slice, endian, |num: &mut i8| {
+                changed = ui.add(egui::DragValue::new(num)).changed();
+            });
+        }
+        StructPrimitive::U8 => {
+            with_bytes_as_primitive(byte_slice, endian, |num: &mut u8| {
+                changed = ui.add(egui::DragValue::new(num)).changed();
+            });
+        }
+        StructPrimitive::I16 => {
+            with_bytes_as_primitive(byte_slice, endian, |num: &mut i16| {
+                changed = ui.add(egui::DragValue::new(num)).changed();
+            });
+        }
+        StructPrimitive::U16 => {
+            with_bytes_as_primitive(byte_slice, endian, |num: &mut u16| {
+                changed = ui.add(egui::DragValue::new(num)).changed();
+            });
+        }
+        StructPrimitive::I32 => {
+            with_bytes_as_primitive(byte_slice, endian, |num: &mut i32| {
+                changed = ui.add(egui::DragValue::new(num)).changed();
+            });
+        }
+        StructPrimitive::U32 => {
+            with_bytes_as_primitive(byte_slice, endian, |num: &mut u32| {
+                changed = ui.add(egui::DragValue::new(num)).changed();
+            });
+        }
+        StructPrimitive::I64 => {
+            with_bytes_as_primitive(byte_slice, endian, |num: &mut i64| {
+                changed = ui.add(egui::DragValue::new(num)).changed();
+            });
+        }
+        StructPrimitive::U64 => {
+            with_bytes_as_primitive(byte_slice, endian, |num: &mut u64| {
+                changed = ui.add(egui::DragValue::new(num)).changed();
+            });
+        }
+        StructPrimitive::F32 => {
+            with_bytes_as_primitive(byte_slice, endian, |num: &mut f32| {
+                changed = ui.add(egui::DragValue::new(num)).changed();
+            });
+        }
+        StructPrimitive::F64 => {
+            with_bytes_as_primitive(byte_slice, endian, |num: &mut f64| {
+                changed = ui.add(egui::DragValue::new(num)).changed();
+            });
         }
     }
+    changed
 }
@@ -22,12 +22,14 @@ pub struct ViewsWindow {
 impl ViewKind {
     const HEX_NAME: &'static str = "Hex";
     const DEC_NAME: &'static str = "Decimal";
+    const BIN_NAME: &'static str = "Binary";
     const TEXT_NAME: &'static str = "Text";
     const BLOCK_NAME: &'static str = "Block";
     fn name(&self) -> &'static str {
         match *self {
             Self::Hex(_) => Self::HEX_NAME,
             Self::Dec(_) => Self::DEC_NAME,
+            Self::Bin(_) => Self::BIN_NAME,
             Self::Text(_) => Self::TEXT_NAME,
             Self::Block => Self::BLOCK_NAME,
         }
@@ -199,6 +201,7 @@ impl super::Window for ViewsWindow {
                 match &mut view.view.kind {
                     ViewKind::Hex(HexData { font_size, .. })
                     | ViewKind::Dec(HexData { font_size, .. })
+                    | ViewKind::Bin(HexData { font_size, .. })
                     | ViewKind::Text(TextData { font_size, .. }) => {
                         ui.horizontal(|ui| {
                             ui.label("Font size");
@@ -319,6 +322,13 @@ fn view_combo(
             *kind = ViewKind::Dec(HexData::with_font_size(font_size));
             changed = true;
         }
+        if ui
+            .selectable_label(kind.name() == ViewKind::BIN_NAME, ViewKind::BIN_NAME)
+            .clicked()
+        {
+            *kind = ViewKind::Bin(HexData::with_font_size(font_size));
+            changed = true;
+        }
         if ui
             .selectable_label(kind.name() == ViewKind::TEXT_NAME, ViewKind::TEXT_NAME)
             .clicked()
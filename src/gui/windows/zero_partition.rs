@@ -109,6 +109,10 @@ impl super::Window for ZeroPartition {
     fn title(&self) -> &str {
         "Zero partition"
     }
+
+    fn clear_doc_state(&mut self) {
+        self.regions.clear();
+    }
 }
 
 fn zero_partition(data: &[u8], threshold: usize) -> Vec<Region> {
@@ -0,0 +1,74 @@
+use {
+    crate::{
+        App,
+        data::Data,
+        source::{Source, SourceAttributes, SourcePermissions, SourceProvider, SourceState},
+    },
+    anyhow::Context as _,
+    std::{
+        fs::{File, OpenOptions},
+        os::unix::fs::FileExt as _,
+        path::Path,
+    },
+};
+
+pub fn load_proc_memory(
+    app: &mut App,
+    pid: sysinfo::Pid,
+    start: usize,
+    size: usize,
+    is_write: bool,
+    font_size: u16,
+    line_spacing: u16,
+) -> anyhow::Result<()> {
+    let path = Path::new("/proc/").join(pid.to_string()).join("mem");
+    let mem = OpenOptions::new()
+        .read(true)
+        .write(is_write)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut buf = vec![0; size];
+    read_proc_memory(&mem, &mut buf, start)?;
+    app.data = Data::clean_from_buf(buf);
+    app.data.fix_len();
+    app.source = Some(Source {
+        attr: SourceAttributes {
+            permissions: SourcePermissions { write: is_write },
+            stream: false,
+        },
+        provider: SourceProvider::LinuxProc {
+            pid: pid.as_u32(),
+            mem,
+            start,
+            size,
+        },
+        state: SourceState::default(),
+    });
+    if !app.preferences.keep_meta {
+        app.set_new_clean_meta(font_size, line_spacing, None);
+    }
+    app.src_args.file = None;
+    app.src_args.hard_seek = Some(start);
+    app.src_args.take = Some(size);
+    Ok(())
+}
+
+/// Read `buf.len()` bytes of process memory at address `start`
+pub fn read_proc_memory(mem: &File, buf: &mut [u8], start: usize) -> anyhow::Result<()> {
+    mem.read_exact_at(buf, start as u64).with_context(|| {
+        format!(
+            "Failed to read process memory at {start:#x}..{:#x}",
+            start + buf.len()
+        )
+    })
+}
+
+/// Write `buf` to process memory at address `start`
+pub fn write_proc_memory(mem: &File, buf: &[u8], start: usize) -> anyhow::Result<()> {
+    mem.write_all_at(buf, start as u64).with_context(|| {
+        format!(
+            "Failed to write process memory at {start:#x}..{:#x}",
+            start + buf.len()
+        )
+    })
+}
@@ -72,23 +72,34 @@ use {
 mod app;
 mod args;
 mod backend;
+mod batch;
+mod bindiff;
+mod binfmt;
+mod carve;
+mod checksum;
 mod color;
+mod compress;
 mod config;
 mod damage_region;
 mod data;
 mod dec_conv;
 pub mod edit_buffer;
+mod edit_history;
 mod find_util;
 mod gui;
 mod hex_conv;
 mod hex_ui;
 mod input;
 mod layout;
+#[cfg(target_os = "linux")]
+mod linux;
 mod meta;
 mod meta_state;
 mod parse_radix;
+mod patch;
 mod plugin;
 mod result_ext;
+mod scanner;
 mod scripting;
 mod session_prefs;
 mod shell;
@@ -144,6 +155,14 @@ fn try_main() -> anyhow::Result<()> {
         print_version_info();
         return Ok(());
     }
+    if args.batch {
+        let LoadedConfig { config, .. } = Config::load_or_default()?;
+        if let Err(e) = batch::run(args, config) {
+            eprintln!("Error: {e:#}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
     let desktop_mode = VideoMode::desktop_mode();
     let mut window = RenderWindow::new(
         desktop_mode,
@@ -254,9 +273,9 @@ fn try_main() -> anyhow::Result<()> {
         )? {
             return Ok(());
         }
-        // Save a metafile backup every so often
+        // Save metafile backups every so often
         if app.meta_state.last_meta_backup.get().elapsed() >= Duration::from_secs(60)
-            && let Err(e) = app.save_temp_metafile_backup()
+            && let Err(e) = app.save_temp_metafile_backups()
         {
             gamedebug_core::per!("Failed to save temp metafile backup: {}", e);
         }
@@ -303,7 +322,7 @@ fn main() {
             Some(loc) => (loc.file(), loc.line().to_string(), loc.column().to_string()),
             None => ("unknown", "unknown".into(), "unknown".into()),
         };
-        let bkpath = app::temp_metafile_backup_path();
+        let bkpath = std::env::temp_dir();
         let bkpath = bkpath.display();
         let btrace = Backtrace::force_capture();
         do_fatal_error_report(
@@ -313,7 +332,7 @@ fn main() {
             {msg}\n\n\
             Location:\n\
             {file}:{line}:{column}\n\n\
-            Meta Backup path:\n\
+            Meta Backup directory:\n\
             {bkpath}",
             ),
             &btrace,
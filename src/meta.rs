@@ -1,9 +1,16 @@
 use {
     self::{perspective::Perspective, region::Region, value_type::ValueType},
-    crate::{layout::Layout, struct_meta_item::StructMetaItem, view::View},
+    crate::{
+        checksum::HashAlgo,
+        data::Data,
+        edit_history::Splice,
+        layout::Layout,
+        struct_meta_item::{Endian, StructMetaItem},
+        view::View,
+    },
     serde::{Deserialize, Serialize},
     slotmap::{SlotMap, new_key_type},
-    std::{collections::HashMap, io::Write as _},
+    std::{collections::HashMap, io::Write as _, ops::Range},
 };
 
 pub mod perspective;
@@ -51,8 +58,19 @@ pub struct Bookmark {
     /// A bookmark can optionally have a type, which can be used to display its value, etc.
     #[serde(default)]
     pub value_type: ValueType,
+    /// If set, these bytes are written back at the offset after every reload,
+    /// keeping the value fixed
+    #[serde(default)]
+    pub frozen: Option<Vec<u8>>,
 }
 impl Bookmark {
+    /// Freeze the value at `val`, encoded according to the value type
+    pub(crate) fn freeze_int(&mut self, val: i64) -> std::io::Result<()> {
+        let mut bytes = vec![0; self.value_type.byte_len()];
+        self.write_int(&mut bytes, val)?;
+        self.frozen = Some(bytes);
+        Ok(())
+    }
     #[expect(
         clippy::cast_possible_truncation,
         clippy::cast_sign_loss,
@@ -121,6 +139,21 @@ pub struct Meta {
     pub onload_script: Option<ScriptKey>,
     #[serde(default)]
     pub structs: Vec<StructMetaItem>,
+    /// Checksums that are recomputed on every save
+    #[serde(default)]
+    pub checksum_rules: Vec<ChecksumRule>,
+}
+
+/// A checksum over a region, that's recomputed and written into the data on every save
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ChecksumRule {
+    pub algo: HashAlgo,
+    /// The region the checksum is computed over
+    pub region: RegionKey,
+    /// Where the checksum is written
+    pub dest: usize,
+    /// Byte order of numeric checksums. Digests like MD5 are always written as is.
+    pub endian: Endian,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -215,6 +248,69 @@ impl Meta {
     pub(crate) fn region_by_name_mut(&mut self, name: &str) -> Option<&mut NamedRegion> {
         self.low.regions.iter_mut().find_map(|(_k, v)| (v.name == name).then_some(v))
     }
+    /// Re-offset regions and bookmarks after `splice` moved the data around,
+    /// so they keep pointing at the same data
+    pub(crate) fn apply_splice(&mut self, splice: Splice) {
+        for reg in self.low.regions.values_mut() {
+            reg.region = splice.map_region(reg.region);
+        }
+        for bm in &mut self.bookmarks {
+            bm.offset = splice.map_offset(bm.offset);
+        }
+        for rule in &mut self.checksum_rules {
+            rule.dest = splice.map_offset(rule.dest);
+        }
+        // Regions might have shrunk below the column count of their perspectives
+        for per in self.low.perspectives.values_mut() {
+            per.clamp_cols(&self.low.regions);
+        }
+    }
+    /// Recompute the checksums declared by the checksum rules, and write them into `data`
+    pub(crate) fn apply_checksum_rules(&self, data: &mut Data) -> anyhow::Result<()> {
+        for rule in &self.checksum_rules {
+            let Some(reg) = self.low.regions.get(rule.region) else {
+                anyhow::bail!("Checksum rule refers to a nonexistent region");
+            };
+            let reg = reg.region;
+            let Some(src) = data.get(reg.begin..=reg.end) else {
+                anyhow::bail!(
+                    "Checksum source {:X}..={:X} is out of bounds",
+                    reg.begin,
+                    reg.end
+                );
+            };
+            let sum = rule.algo.compute_endian(src, rule.endian);
+            if data.get(rule.dest..rule.dest + sum.len()) == Some(sum.as_slice()) {
+                continue;
+            }
+            anyhow::ensure!(
+                data.write(rule.dest, &sum),
+                "Checksum destination {:X} is out of bounds",
+                rule.dest
+            );
+        }
+        Ok(())
+    }
+    /// Write the values of frozen bookmarks into `data`.
+    ///
+    /// They aren't recorded in the edit history, and don't touch the dirty region.
+    /// Returns the ranges that changed.
+    pub(crate) fn apply_frozen_values(&self, data: &mut Data) -> Vec<Range<usize>> {
+        let mut changed = Vec::new();
+        for bm in &self.bookmarks {
+            let Some(bytes) = &bm.frozen else {
+                continue;
+            };
+            let range = bm.offset..bm.offset + bytes.len();
+            if let Some(cur) = data.get_mut(range.clone())
+                && cur != bytes.as_slice()
+            {
+                cur.copy_from_slice(bytes);
+                changed.push(range);
+            }
+        }
+        changed
+    }
     /// Remove anything that contains dangling keys
     pub(crate) fn remove_dangling(&mut self) {
         self.low.perspectives.retain(|_k, v| {
@@ -236,6 +332,13 @@ impl Meta {
         for layout in self.layouts.values_mut() {
             layout.remove_dangling(&self.views);
         }
+        self.checksum_rules.retain(|rule| {
+            let retain = self.low.regions.contains_key(rule.region);
+            if !retain {
+                eprintln!("Removed checksum rule with dangling region");
+            }
+            retain
+        });
     }
 }
 
@@ -276,3 +379,56 @@ pub struct Script {
     pub desc: String,
     pub content: String,
 }
+
+#[test]
+fn test_apply_checksum_rules() {
+    let mut meta = Meta::default();
+    let region = meta.low.regions.insert(NamedRegion::new("body".into(), 4, 7));
+    meta.checksum_rules.push(ChecksumRule {
+        algo: HashAlgo::Sum16,
+        region,
+        dest: 0,
+        endian: Endian::Le,
+    });
+    let mut data = Data::clean_from_buf(vec![0, 0, 0xAA, 0xBB, 0x80, 0x80, 0x01, 0x02]);
+    meta.apply_checksum_rules(&mut data).unwrap();
+    // Only the checksum bytes are written
+    assert_eq!(&data[..], [0x03, 0x01, 0xAA, 0xBB, 0x80, 0x80, 0x01, 0x02]);
+    assert_eq!(data.history.undo_len(), 1);
+    // An up to date checksum is left alone
+    meta.apply_checksum_rules(&mut data).unwrap();
+    assert_eq!(data.history.undo_len(), 1);
+    // A destination past the end is an error
+    meta.checksum_rules[0].dest = 7;
+    assert!(meta.apply_checksum_rules(&mut data).is_err());
+}
+
+#[test]
+fn test_apply_frozen_values() {
+    let bookmark = |offset, frozen: Option<&[u8]>| Bookmark {
+        offset,
+        label: String::new(),
+        desc: String::new(),
+        value_type: ValueType::None,
+        frozen: frozen.map(<[u8]>::to_vec),
+    };
+    let meta = Meta {
+        bookmarks: vec![
+            bookmark(0, Some(&[1, 2])),
+            bookmark(2, None),
+            bookmark(3, Some(&[4])),
+            bookmark(5, Some(&[9, 9])),
+        ],
+        ..Meta::default()
+    };
+    let mut data = Data::clean_from_buf(vec![0, 0, 7, 4, 0, 0]);
+    let changed = meta.apply_frozen_values(&mut data);
+    // Unchanged and out of bounds values are skipped
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0], 0..2);
+    assert_eq!(&data[..], [1, 2, 7, 4, 0, 0]);
+    assert_eq!(data.history.undo_len(), 0);
+    assert!(data.dirty_region.is_none());
+    // Nothing to do once they're written
+    assert!(meta.apply_frozen_values(&mut data).is_empty());
+}
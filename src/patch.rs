@@ -0,0 +1,474 @@
+//! Creating and applying IPS and BPS patches
+
+use {
+    crate::bindiff::{self, Hunk, HunkKind},
+    anyhow::Context as _,
+    crc::{CRC_32_ISO_HDLC, Crc},
+    std::ops::Range,
+};
+
+const IPS_MAGIC: &[u8] = b"PATCH";
+const IPS_EOF: &[u8] = b"EOF";
+/// A record can't start at this offset, because it would read as the end of the patch
+const IPS_EOF_OFFSET: usize = 0x45_4F46;
+/// IPS offsets are 24 bits
+const IPS_MAX_LEN: usize = 1 << 24;
+const IPS_MAX_RECORD: usize = 0xFFFF;
+/// Header size of an IPS record. Unchanged gaps smaller than this are cheaper to include.
+const IPS_RECORD_HEADER: usize = 5;
+/// Runs of the same byte at least this long are written as RLE records
+const IPS_RLE_MIN: usize = 9;
+const BPS_MAGIC: &[u8] = b"BPS1";
+const BPS_SOURCE_READ: usize = 0;
+const BPS_TARGET_READ: usize = 1;
+const BPS_SOURCE_COPY: usize = 2;
+const CRC32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, strum::EnumIter)]
+pub enum PatchFormat {
+    /// Classic IPS, with the truncation extension. Limited to 16 MiB.
+    Ips,
+    /// beat's BPS, which handles insertions and deletions, and is checksummed
+    #[default]
+    Bps,
+}
+
+impl PatchFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Ips => "IPS",
+            Self::Bps => "BPS",
+        }
+    }
+    /// Detect the format from the header of `patch`
+    pub fn detect(patch: &[u8]) -> Option<Self> {
+        if patch.starts_with(IPS_MAGIC) {
+            Some(Self::Ips)
+        } else if patch.starts_with(BPS_MAGIC) {
+            Some(Self::Bps)
+        } else {
+            None
+        }
+    }
+    /// Create a patch that turns `source` into `target`
+    pub fn create(self, source: &[u8], target: &[u8]) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Self::Ips => create_ips(source, target),
+            Self::Bps => Ok(create_bps(source, target)),
+        }
+    }
+}
+
+/// The result of applying a patch
+pub struct Patched {
+    pub format: PatchFormat,
+    pub data: Vec<u8>,
+    /// How `data` differs from the source, in order.
+    ///
+    /// If the size changed, bytes inserted or removed in the middle are their own hunks,
+    /// so the data after them can be moved instead of overwritten.
+    pub hunks: Vec<Hunk>,
+}
+
+/// Apply `patch` to `source`, detecting its format
+pub fn apply(patch: &[u8], source: &[u8]) -> anyhow::Result<Patched> {
+    let format = PatchFormat::detect(patch).context("Not an IPS or BPS patch")?;
+    let data = match format {
+        PatchFormat::Ips => apply_ips(patch, source)?,
+        PatchFormat::Bps => apply_bps(patch, source)?,
+    };
+    let hunks = if data.len() == source.len() {
+        changed_ranges(source, &data)
+            .into_iter()
+            .map(|range| Hunk {
+                kind: HunkKind::Changed,
+                a: range.clone(),
+                b: range,
+            })
+            .collect()
+    } else {
+        bindiff::diff(source, &data)
+    };
+    Ok(Patched {
+        format,
+        data,
+        hunks,
+    })
+}
+
+/// Ranges of `new` that differ from `old`
+fn changed_ranges(old: &[u8], new: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = None;
+    for (i, &b) in new.iter().enumerate() {
+        let differs = old.get(i) != Some(&b);
+        match (differs, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                ranges.push(s..i);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        ranges.push(s..new.len());
+    }
+    ranges
+}
+
+fn create_ips(source: &[u8], target: &[u8]) -> anyhow::Result<Vec<u8>> {
+    anyhow::ensure!(
+        target.len() < IPS_MAX_LEN,
+        "IPS patches can't address data past 16 MiB, use BPS instead"
+    );
+    let mut out = IPS_MAGIC.to_vec();
+    let mut ranges = changed_ranges(source, target).into_iter().peekable();
+    while let Some(mut range) = ranges.next() {
+        // Merging across small unchanged gaps saves on record headers
+        while let Some(next) = ranges.next_if(|next| next.start - range.end < IPS_RECORD_HEADER) {
+            range.end = next.end;
+        }
+        push_ips_records(&mut out, target, range);
+    }
+    out.extend_from_slice(IPS_EOF);
+    if target.len() < source.len() {
+        push_u24(&mut out, target.len());
+    }
+    Ok(out)
+}
+
+/// Write `target[range]` as IPS records, using RLE for long runs of the same byte
+fn push_ips_records(out: &mut Vec<u8>, target: &[u8], range: Range<usize>) {
+    let mut pos = range.start;
+    while pos < range.end {
+        if pos == IPS_EOF_OFFSET {
+            // Start one byte earlier instead. Rewriting that byte is harmless.
+            push_ips_literal(out, pos - 1, &target[pos - 1..=pos]);
+            pos += 1;
+            continue;
+        }
+        let run = run_len(&target[pos..range.end]);
+        if run >= IPS_RLE_MIN {
+            let len = run.min(IPS_MAX_RECORD);
+            push_u24(out, pos);
+            push_u16(out, 0);
+            push_u16(out, len);
+            out.push(target[pos]);
+            pos += len;
+            continue;
+        }
+        // Literal up to the next long enough run
+        let mut end = pos;
+        while end < range.end {
+            let run = run_len(&target[end..range.end]);
+            if run >= IPS_RLE_MIN {
+                break;
+            }
+            end += run;
+        }
+        let end = end.min(pos + IPS_MAX_RECORD);
+        push_ips_literal(out, pos, &target[pos..end]);
+        pos = end;
+    }
+}
+
+fn push_ips_literal(out: &mut Vec<u8>, offset: usize, bytes: &[u8]) {
+    push_u24(out, offset);
+    push_u16(out, bytes.len());
+    out.extend_from_slice(bytes);
+}
+
+/// Number of bytes at the start of `data` equal to the first one
+fn run_len(data: &[u8]) -> usize {
+    data.first()
+        .map_or(0, |&first| data.iter().take_while(|&&b| b == first).count())
+}
+
+fn push_u24(out: &mut Vec<u8>, value: usize) {
+    out.extend_from_slice(&value.to_be_bytes()[size_of::<usize>() - 3..]);
+}
+
+fn push_u16(out: &mut Vec<u8>, value: usize) {
+    out.extend_from_slice(&value.to_be_bytes()[size_of::<usize>() - 2..]);
+}
+
+fn apply_ips(patch: &[u8], source: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut rd = Reader::new(&patch[IPS_MAGIC.len()..]);
+    let mut out = source.to_vec();
+    loop {
+        if rd.rest().starts_with(IPS_EOF) {
+            rd.take(IPS_EOF.len())?;
+            break;
+        }
+        let offset = rd.be_uint(3)?;
+        let len = rd.be_uint(2)?;
+        let (len, fill) = if len == 0 {
+            (rd.be_uint(2)?, Some(rd.take(1)?[0]))
+        } else {
+            (len, None)
+        };
+        let end = offset + len;
+        if out.len() < end {
+            out.resize(end, 0);
+        }
+        match fill {
+            Some(byte) => out[offset..end].fill(byte),
+            None => out[offset..end].copy_from_slice(rd.take(len)?),
+        }
+    }
+    // Truncation extension
+    if rd.rest().len() >= 3 {
+        let len = rd.be_uint(3)?;
+        out.truncate(len);
+    }
+    Ok(out)
+}
+
+fn create_bps(source: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut out = BPS_MAGIC.to_vec();
+    push_varint(&mut out, source.len());
+    push_varint(&mut out, target.len());
+    // No metadata
+    push_varint(&mut out, 0);
+    let mut source_rel = 0;
+    let mut push_equal = |out: &mut Vec<u8>, src: Range<usize>, tgt_start: usize| {
+        if src.is_empty() {
+            return;
+        }
+        if src.start == tgt_start {
+            push_bps_action(out, BPS_SOURCE_READ, src.len());
+        } else {
+            push_bps_action(out, BPS_SOURCE_COPY, src.len());
+            let (delta, negative) = if src.start >= source_rel {
+                (src.start - source_rel, 0)
+            } else {
+                (source_rel - src.start, 1)
+            };
+            push_varint(out, delta << 1 | negative);
+            source_rel = src.end;
+        }
+    };
+    let (mut a, mut b) = (0, 0);
+    for hunk in bindiff::diff(source, target) {
+        push_equal(&mut out, a..hunk.a.start, b);
+        if !hunk.b.is_empty() {
+            push_bps_action(&mut out, BPS_TARGET_READ, hunk.b.len());
+            out.extend_from_slice(&target[hunk.b.clone()]);
+        }
+        (a, b) = (hunk.a.end, hunk.b.end);
+    }
+    push_equal(&mut out, a..source.len(), b);
+    out.extend_from_slice(&CRC32.checksum(source).to_le_bytes());
+    out.extend_from_slice(&CRC32.checksum(target).to_le_bytes());
+    let patch_crc = CRC32.checksum(&out);
+    out.extend_from_slice(&patch_crc.to_le_bytes());
+    out
+}
+
+fn push_bps_action(out: &mut Vec<u8>, action: usize, len: usize) {
+    push_varint(out, (len - 1) << 2 | action);
+}
+
+#[expect(clippy::cast_possible_truncation, reason = "Masked to 7 bits")]
+fn push_varint(out: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let low = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(0x80 | low);
+            return;
+        }
+        out.push(low);
+        value -= 1;
+    }
+}
+
+fn apply_bps(patch: &[u8], source: &[u8]) -> anyhow::Result<Vec<u8>> {
+    anyhow::ensure!(patch.len() >= BPS_MAGIC.len() + 12, "Patch is truncated");
+    let (body, footer) = patch.split_at(patch.len() - 12);
+    let crc_at = |i: usize| u32::from_le_bytes(footer[i..i + 4].try_into().unwrap_or_default());
+    anyhow::ensure!(
+        CRC32.checksum(&patch[..patch.len() - 4]) == crc_at(8),
+        "Patch checksum mismatch, the patch is corrupt"
+    );
+    anyhow::ensure!(
+        CRC32.checksum(source) == crc_at(0),
+        "Source checksum mismatch, the patch is for different data"
+    );
+    let mut rd = Reader::new(&body[BPS_MAGIC.len()..]);
+    let source_len = rd.varint()?;
+    let target_len = rd.varint()?;
+    anyhow::ensure!(
+        source_len == source.len(),
+        "Patch expects {source_len} bytes of source data, but there are {}",
+        source.len()
+    );
+    let meta_len = rd.varint()?;
+    rd.take(meta_len)?;
+    // The target length comes from the patch, so don't trust it for allocating
+    let mut out: Vec<u8> =
+        Vec::with_capacity(target_len.min(source.len().saturating_add(patch.len())));
+    let (mut source_rel, mut target_rel) = (0usize, 0usize);
+    while !rd.rest().is_empty() {
+        let cmd = rd.varint()?;
+        let len = (cmd >> 2) + 1;
+        anyhow::ensure!(
+            out.len() + len <= target_len,
+            "Patch writes past the target size"
+        );
+        match cmd & 3 {
+            BPS_SOURCE_READ => {
+                let bytes =
+                    source.get(out.len()..out.len() + len).context("Source read out of bounds")?;
+                out.extend_from_slice(bytes);
+            }
+            BPS_TARGET_READ => out.extend_from_slice(rd.take(len)?),
+            BPS_SOURCE_COPY => {
+                source_rel = rel_offset(source_rel, rd.varint()?)?;
+                let bytes = source
+                    .get(source_rel..source_rel + len)
+                    .context("Source copy out of bounds")?;
+                out.extend_from_slice(bytes);
+                source_rel += len;
+            }
+            // Target copy
+            _ => {
+                target_rel = rel_offset(target_rel, rd.varint()?)?;
+                anyhow::ensure!(target_rel < out.len(), "Target copy out of bounds");
+                // The copy can overlap what it's writing, so go byte by byte
+                for _ in 0..len {
+                    out.push(out[target_rel]);
+                    target_rel += 1;
+                }
+            }
+        }
+    }
+    anyhow::ensure!(
+        out.len() == target_len,
+        "Patch output is shorter than the target size"
+    );
+    anyhow::ensure!(
+        CRC32.checksum(&out) == crc_at(4),
+        "Target checksum mismatch, the patch was applied incorrectly"
+    );
+    Ok(out)
+}
+
+/// Apply a BPS relative offset, where the lowest bit is the sign
+fn rel_offset(base: usize, encoded: usize) -> anyhow::Result<usize> {
+    let delta = encoded >> 1;
+    let offset = if encoded & 1 == 0 {
+        base.checked_add(delta)
+    } else {
+        base.checked_sub(delta)
+    };
+    offset.context("Relative offset out of bounds")
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+    fn rest(&self) -> &'a [u8] {
+        self.data
+    }
+    fn take(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        anyhow::ensure!(self.data.len() >= len, "Unexpected end of patch");
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+    fn be_uint(&mut self, len: usize) -> anyhow::Result<usize> {
+        Ok(self.take(len)?.iter().fold(0, |acc, &b| acc << 8 | usize::from(b)))
+    }
+    fn varint(&mut self) -> anyhow::Result<usize> {
+        let mut value: usize = 0;
+        let mut shift: usize = 1;
+        loop {
+            let byte = self.take(1)?[0];
+            value = usize::from(byte & 0x7F)
+                .checked_mul(shift)
+                .and_then(|v| v.checked_add(value))
+                .context("Number too large")?;
+            if byte & 0x80 != 0 {
+                return Ok(value);
+            }
+            shift = shift.checked_shl(7).context("Number too large")?;
+            value = value.checked_add(shift).context("Number too large")?;
+        }
+    }
+}
+
+#[test]
+fn test_patch_roundtrip() {
+    use strum::IntoEnumIterator as _;
+    let source: Vec<u8> = (0..20_000u32).map(|i| u8::try_from(i % 251).unwrap()).collect();
+    let mut grown = source.clone();
+    grown[100..110].fill(0);
+    grown.splice(5000..5000, b"inserted".iter().copied());
+    grown[9000..9100].fill(0xAA);
+    grown.extend_from_slice(b"tail");
+    let mut shrunk = source.clone();
+    shrunk.drain(3000..4000);
+    shrunk.truncate(15_000);
+    for format in PatchFormat::iter() {
+        for target in [&grown, &shrunk, &source] {
+            let patch = format.create(&source, target).unwrap();
+            assert_eq!(PatchFormat::detect(&patch), Some(format));
+            let patched = apply(&patch, &source).unwrap();
+            assert_eq!(&patched.data, target);
+        }
+    }
+    // BPS is checksummed against the source
+    let patch = create_bps(&source, &grown);
+    assert!(apply(&patch, &grown).is_err());
+}
+
+#[test]
+fn test_patch_hunks() {
+    let mut state = 0x1234_5678_u32;
+    let source: Vec<u8> = std::iter::repeat_with(|| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state.to_le_bytes()[0]
+    })
+    .take(20_000)
+    .collect();
+    let hunks = |target: &[u8]| -> Vec<_> {
+        let patched = apply(&create_bps(&source, target), &source).unwrap();
+        patched.hunks.iter().map(|h| (h.kind, h.a.clone())).collect()
+    };
+    let mut changed = source.clone();
+    changed[100..110].iter_mut().for_each(|b| *b = !*b);
+    changed[200] ^= 1;
+    let ranges: Vec<_> = hunks(&changed).into_iter().map(|(_, a)| a).collect();
+    assert_eq!(ranges.first(), Some(&(100..110)));
+    assert_eq!(ranges.last(), Some(&(200..201)));
+    // Bytes inserted or removed in the middle don't change all the bytes after them
+    let mut grown = source.clone();
+    grown.splice(5000..5000, *b"inserted");
+    grown.extend_from_slice(b"tail");
+    assert_eq!(
+        hunks(&grown),
+        [
+            (HunkKind::Inserted, 5000..5000),
+            (HunkKind::Inserted, 20_000..20_000)
+        ]
+    );
+    let mut shrunk = source.clone();
+    shrunk.drain(3000..4000);
+    shrunk.truncate(15_000);
+    assert_eq!(
+        hunks(&shrunk),
+        [
+            (HunkKind::Deleted, 3000..4000),
+            (HunkKind::Deleted, 16_000..20_000)
+        ]
+    );
+}
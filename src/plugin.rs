@@ -1,6 +1,11 @@
 use {
-    crate::{app::App, meta::PerspectiveKey},
-    hexerator_plugin_api::{HexeratorHandle, PerspectiveHandle, Plugin, PluginMethod},
+    crate::{
+        app::{App, document::DocKey},
+        meta::PerspectiveKey,
+    },
+    hexerator_plugin_api::{
+        DocumentHandle, HexeratorHandle, PerspectiveHandle, Plugin, PluginMethod,
+    },
     slotmap::{Key as _, KeyData},
     std::path::PathBuf,
 };
@@ -23,7 +28,7 @@ impl HexeratorHandle for App {
     }
 
     fn get_data_mut(&mut self, start: usize, end: usize) -> Option<&mut [u8]> {
-        self.data.get_mut(start..=end)
+        self.data.get_mut_deferred(start..=end)
     }
 
     fn selection_range(&self) -> Option<[usize; 2]> {
@@ -55,6 +60,36 @@ impl HexeratorHandle for App {
         }
         out
     }
+
+    fn documents(&self) -> Vec<DocumentHandle> {
+        self.docs
+            .keys()
+            .iter()
+            .map(|key| DocumentHandle {
+                key_data: key.to_handle(),
+            })
+            .collect()
+    }
+
+    fn active_document(&self) -> DocumentHandle {
+        DocumentHandle {
+            key_data: self.docs.active.to_handle(),
+        }
+    }
+
+    fn document_data(&self, doc: &DocumentHandle, start: usize, end: usize) -> Option<&[u8]> {
+        self.doc_data(DocKey::from_handle(doc.key_data))?.get(start..=end)
+    }
+
+    fn document_data_mut(
+        &mut self,
+        doc: &DocumentHandle,
+        start: usize,
+        end: usize,
+    ) -> Option<&mut [u8]> {
+        self.doc_data_mut(DocKey::from_handle(doc.key_data))?
+            .get_mut_deferred(start..=end)
+    }
 }
 
 impl PluginContainer {
@@ -0,0 +1,247 @@
+//! Narrowing down where a value lives by scanning repeatedly as it changes,
+//! like a game memory scanner
+
+use crate::struct_meta_item::{
+    Endian, StructPrimitive,
+    eval::{Value, read_value},
+};
+
+/// A first scan followed by any number of filtering scans
+pub struct ScanSession {
+    pub ty: StructPrimitive,
+    pub endian: Endian,
+    /// Only offsets that are a multiple of this are considered
+    pub align: usize,
+    candidates: Candidates,
+    /// Number of scans done, including the first one
+    pub scans: usize,
+}
+
+enum Candidates {
+    /// Every aligned offset. The data at the first scan is kept to compare against.
+    ///
+    /// This is what an unknown initial value scan results in, as listing every offset
+    /// would take several times more memory than the data itself.
+    All { snapshot: Vec<u8> },
+    /// The offsets that passed all the scans so far
+    List(Vec<Candidate>),
+}
+
+#[derive(Clone, Copy)]
+pub struct Candidate {
+    pub offset: usize,
+    /// The value at the last scan
+    pub value: Value,
+}
+
+/// How the values at the candidates are filtered by a next scan
+#[derive(Clone, Copy, PartialEq)]
+pub enum ScanFilter {
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+    Equals(Value),
+}
+
+impl ScanFilter {
+    fn keep(&self, old: Value, new: Value) -> bool {
+        match self {
+            Self::Changed => new != old,
+            Self::Unchanged => new == old,
+            Self::Increased => new > old,
+            Self::Decreased => new < old,
+            Self::Equals(value) => new == *value,
+        }
+    }
+}
+
+impl ScanSession {
+    /// Start a session with the offsets where the value is `value`
+    pub fn first_scan_exact(
+        data: &[u8],
+        ty: StructPrimitive,
+        endian: Endian,
+        align: usize,
+        value: Value,
+    ) -> Self {
+        let align = align.max(1);
+        let candidates = (0..data.len())
+            .step_by(align)
+            .filter_map(|offset| {
+                let v = read_value(data, offset, ty, endian)?;
+                (v == value).then_some(Candidate { offset, value: v })
+            })
+            .collect();
+        Self {
+            ty,
+            endian,
+            align,
+            candidates: Candidates::List(candidates),
+            scans: 1,
+        }
+    }
+    /// Start a session where every offset is a candidate
+    pub fn first_scan_unknown(
+        data: &[u8],
+        ty: StructPrimitive,
+        endian: Endian,
+        align: usize,
+    ) -> Self {
+        Self {
+            ty,
+            endian,
+            align: align.max(1),
+            candidates: Candidates::All {
+                snapshot: data.to_vec(),
+            },
+            scans: 1,
+        }
+    }
+    /// Keep only the candidates whose value in `data` passes `filter`,
+    /// compared to their value at the last scan
+    pub fn next_scan(&mut self, data: &[u8], filter: ScanFilter) {
+        let (ty, endian) = (self.ty, self.endian);
+        match &mut self.candidates {
+            Candidates::All { snapshot } => {
+                let len = snapshot.len().min(data.len());
+                let list = (0..len)
+                    .step_by(self.align)
+                    .filter_map(|offset| {
+                        let old = read_value(snapshot, offset, ty, endian)?;
+                        let new = read_value(data, offset, ty, endian)?;
+                        filter.keep(old, new).then_some(Candidate { offset, value: new })
+                    })
+                    .collect();
+                self.candidates = Candidates::List(list);
+            }
+            Candidates::List(list) => {
+                list.retain_mut(|cand| {
+                    let Some(new) = read_value(data, cand.offset, ty, endian) else {
+                        return false;
+                    };
+                    let keep = filter.keep(cand.value, new);
+                    cand.value = new;
+                    keep
+                });
+            }
+        }
+        self.scans += 1;
+    }
+    /// Number of candidates left
+    pub fn len(&self) -> usize {
+        match &self.candidates {
+            Candidates::All { snapshot } => snapshot.len().div_ceil(self.align),
+            Candidates::List(list) => list.len(),
+        }
+    }
+    /// The candidates, if they are narrowed down enough to be listed
+    pub fn candidates(&self) -> Option<&[Candidate]> {
+        match &self.candidates {
+            Candidates::All { .. } => None,
+            Candidates::List(list) => Some(list),
+        }
+    }
+}
+
+/// Parse a value of type `ty`, like `-5` or `1.5`. Integers can be hex with a `0x` prefix.
+pub fn parse_value(input: &str, ty: StructPrimitive) -> anyhow::Result<Value> {
+    let input = input.trim();
+    if ty.is_float() {
+        return Ok(Value::Float(input.parse()?));
+    }
+    let (digits, radix) = match input.strip_prefix("0x") {
+        Some(hex) => (hex, 16),
+        None => (input, 10),
+    };
+    let size_bits = u32::try_from(ty.size() * 8)?;
+    let value = match ty {
+        StructPrimitive::I8
+        | StructPrimitive::I16
+        | StructPrimitive::I32
+        | StructPrimitive::I64 => {
+            let v = i64::from_str_radix(digits, radix)?;
+            let min = i64::MIN >> (64 - size_bits);
+            anyhow::ensure!(v >= min && v <= !min, "{v} doesn't fit in {}", ty.label());
+            Value::Int(v)
+        }
+        _ => {
+            let v = u64::from_str_radix(digits, radix)?;
+            anyhow::ensure!(
+                v <= u64::MAX >> (64 - size_bits),
+                "{v} doesn't fit in {}",
+                ty.label()
+            );
+            Value::UInt(v)
+        }
+    };
+    Ok(value)
+}
+
+#[test]
+fn test_scan_session() {
+    let mut data = vec![0u8; 64];
+    data[8..12].copy_from_slice(&100u32.to_le_bytes());
+    data[20..24].copy_from_slice(&100u32.to_le_bytes());
+    let mut session =
+        ScanSession::first_scan_exact(&data, StructPrimitive::U32, Endian::Le, 4, Value::UInt(100));
+    assert_eq!(session.len(), 2);
+    data[20..24].copy_from_slice(&99u32.to_le_bytes());
+    session.next_scan(&data, ScanFilter::Decreased);
+    let offsets: Vec<_> = session.candidates().unwrap().iter().map(|c| c.offset).collect();
+    assert_eq!(offsets, [20]);
+    // Unknown initial value
+    let mut session = ScanSession::first_scan_unknown(&data, StructPrimitive::U8, Endian::Le, 1);
+    assert_eq!(session.len(), 64);
+    assert!(session.candidates().is_none());
+    data[3] = 7;
+    session.next_scan(&data, ScanFilter::Changed);
+    assert_eq!(session.candidates().unwrap().len(), 1);
+    session.next_scan(&data, ScanFilter::Unchanged);
+    session.next_scan(&data, ScanFilter::Equals(Value::UInt(7)));
+    assert_eq!(session.candidates().unwrap()[0].offset, 3);
+}
+
+#[test]
+fn test_parse_value() {
+    assert!(matches!(
+        parse_value("-128", StructPrimitive::I8),
+        Ok(Value::Int(-128))
+    ));
+    assert!(parse_value("128", StructPrimitive::I8).is_err());
+    assert!(matches!(
+        parse_value("0xff", StructPrimitive::U8),
+        Ok(Value::UInt(255))
+    ));
+    assert!(parse_value("256", StructPrimitive::U8).is_err());
+    assert!(matches!(
+        parse_value("1.5", StructPrimitive::F32),
+        Ok(Value::Float(1.5))
+    ));
+}
+
+/// Scan the memory of a live process: this test process itself, through `/proc/self/mem`
+#[cfg(target_os = "linux")]
+#[test]
+fn test_scan_process_memory() {
+    let mem = std::fs::File::open("/proc/self/mem").unwrap();
+    let mut value = Box::new([0u64; 8]);
+    value[3] = 0x1234_5678_9abc;
+    let start = value.as_ptr() as usize;
+    let size = std::mem::size_of_val(&*value);
+    let mut buf = vec![0; size];
+    crate::linux::read_proc_memory(&mem, &mut buf, start).unwrap();
+    let mut session = ScanSession::first_scan_exact(
+        &buf,
+        StructPrimitive::U64,
+        Endian::Le,
+        8,
+        Value::UInt(0x1234_5678_9abc),
+    );
+    assert_eq!(session.len(), 1);
+    value[3] += 1;
+    std::hint::black_box(&value);
+    crate::linux::read_proc_memory(&mem, &mut buf, start).unwrap();
+    session.next_scan(&buf, ScanFilter::Increased);
+    assert_eq!(session.candidates().unwrap()[0].offset, 3 * 8);
+}
@@ -1,6 +1,8 @@
 use {
     crate::{
-        app::App,
+        app::{App, document::DocKey},
+        args::SourceArgs,
+        checksum::{self, HashAlgo},
         gui::{ConMsg, Gui},
         meta::{
             Bookmark, NamedRegion, ScriptKey,
@@ -72,6 +74,73 @@ def_method! {
     }
 }
 
+def_method! {
+    "Returns the handles of the open documents, in tab order"
+    documents(_lua, exec,) -> Vec<u64> {
+        Ok(exec.app.docs.keys().iter().map(|key| key.to_handle()).collect())
+    }
+}
+
+def_method! {
+    "Returns the handle of the active document. Methods without a document handle act on the active document."
+    document(_lua, exec,) -> u64 {
+        Ok(exec.app.docs.active.to_handle())
+    }
+}
+
+def_method! {
+    "Makes the document `doc` the active document"
+    switch_document(_lua, exec, doc: u64) -> () {
+        exec.app.switch_document(DocKey::from_handle(doc)).into_lua_err()
+    }
+}
+
+def_method! {
+    "Opens a file as a new active document, and returns its handle"
+    open_document(_lua, exec, path: String) -> u64 {
+        let src_args = SourceArgs {
+            file: Some(path.into()),
+            ..Default::default()
+        };
+        exec.app
+            .load_file_args_new_document(src_args, &mut exec.gui.msg_dialog, exec.font_size, exec.line_spacing)
+            .map(DocKey::to_handle)
+            .context("Failed to open document")
+            .into_lua_err()
+    }
+}
+
+def_method! {
+    "Returns the file path of the document `doc`, if it has one"
+    document_path(_lua, exec, doc: u64) -> Option<String> {
+        let src_args = exec.app.doc_src_args(DocKey::from_handle(doc)).context("No such document").into_lua_err()?;
+        Ok(src_args.file.as_ref().map(|path| path.display().to_string()))
+    }
+}
+
+def_method! {
+    "Reads a binary blob at `offset` of length `len` from the document `doc`"
+    doc_read_blob(_lua, exec, doc: u64, offset: usize, len: usize) -> Vec<u8> {
+        let data = exec.app.doc_data(DocKey::from_handle(doc)).context("No such document").into_lua_err()?;
+        match data.get(offset..offset + len) {
+            Some(slice) => Ok(slice.to_vec()),
+            None => Err("out of bounds".into_lua_err()),
+        }
+    }
+}
+
+def_method! {
+    "Writes the binary blob `blob` at `offset` into the document `doc`"
+    doc_write_blob(_lua, exec, doc: u64, offset: usize, blob: Vec<u8>) -> () {
+        let data = exec.app.doc_data_mut(DocKey::from_handle(doc)).context("No such document").into_lua_err()?;
+        if data.write(offset, &blob) {
+            Ok(())
+        } else {
+            Err("out of bounds".into_lua_err())
+        }
+    }
+}
+
 def_method! {
     "Sets the value pointed to by the bookmark to an integer value"
     bookmark_set_int(_lua, exec, name: String, val: i64) -> () {
@@ -81,7 +150,40 @@ def_method! {
             .meta
             .bookmark_by_name_mut(&name)
             .ok_or("no such bookmark".into_lua_err())?;
-        bm.write_int(&mut exec.app.data[bm.offset..], val).map_err(|e| e.into_lua_err())?;
+        let range = bm.offset..bm.offset + bm.value_type.byte_len();
+        exec.app
+            .data
+            .edit(range, |data| bm.write_int(data, val))
+            .ok_or("out of bounds".into_lua_err())?
+            .map_err(|e| e.into_lua_err())?;
+        Ok(())
+    }
+}
+
+def_method! {
+    "Freezes the value pointed to by the bookmark at an integer value, rewriting it after every reload"
+    bookmark_freeze_int(_lua, exec, name: String, val: i64) -> () {
+        let bm = exec
+            .app
+            .meta_state
+            .meta
+            .bookmark_by_name_mut(&name)
+            .ok_or("no such bookmark".into_lua_err())?;
+        bm.freeze_int(val).map_err(|e| e.into_lua_err())?;
+        Ok(())
+    }
+}
+
+def_method! {
+    "Unfreezes the value pointed to by the bookmark"
+    bookmark_unfreeze(_lua, exec, name: String) -> () {
+        let bm = exec
+            .app
+            .meta_state
+            .meta
+            .bookmark_by_name_mut(&name)
+            .ok_or("no such bookmark".into_lua_err())?;
+        bm.frozen = None;
         Ok(())
     }
 }
@@ -96,8 +198,10 @@ def_method! {
             .region_by_name_mut(&name)
             .ok_or("no such region".into_lua_err())?;
         let pat = crate::find_util::parse_hex_string(&pattern).map_err(|e| e.into_lua_err())?;
-        exec.app.data[reg.region.begin..=reg.region.end].pattern_fill(&pat);
-        Ok(())
+        exec.app
+            .data
+            .edit(reg.region.begin..reg.region.end + 1, |data| data.pattern_fill(&pat))
+            .ok_or("out of bounds".into_lua_err())
     }
 }
 
@@ -121,12 +225,10 @@ def_method! {
 def_method! {
     "Sets unsigned 8 bit integer at `offset` to `value`"
     write_u8(_lua, exec, offset: usize, value: u8) -> () {
-        match exec.app.data.get_mut(offset) {
-            Some(byte) => {
-                *byte = value;
-                Ok(())
-            }
-            None => Err("out of bounds".into_lua_err())
+        if exec.app.data.write(offset, &[value]) {
+            Ok(())
+        } else {
+            Err("out of bounds".into_lua_err())
         }
     }
 }
@@ -185,16 +287,10 @@ def_method! {
 def_method! {
     "Fills a range from `start` to `end` with the value `fill`"
     fill_range(_lua, exec, start: usize, end: usize, fill: u8) -> () {
-        match exec
-              .app
-              .data
-              .get_mut(start..end) {
-            Some(slice) => {
-                slice.fill(fill);
-                Ok(())
-            }
-            None => Err("out of bounds".into_lua_err()),
-        }
+        exec.app
+            .data
+            .edit(start..end, |slice| slice.fill(fill))
+            .ok_or("out of bounds".into_lua_err())
     }
 }
 
@@ -260,13 +356,14 @@ def_method! {
             label: name,
             desc: String::new(),
             value_type: ValueType::None,
+            frozen: None,
         });
         Ok(())
     }
 }
 
 def_method! {
-    "Finds a hex string in the format '99 aa bb ...' format, and returns its offset"
+    "Finds a hex string in the format '99 aa ?? b? (01|02) ...' format, and returns its offset"
     find_hex_string(_lua, exec, hex_string: String) -> Option<usize> {
         let mut offset = None;
         crate::find_util::find_hex_string(&hex_string, &exec.app.data, |off| {
@@ -276,6 +373,17 @@ def_method! {
     }
 }
 
+def_method! {
+    "Computes a checksum/hash of the (inclusive) range `begin..=end`, and returns it as a hex string.\n\
+     Algorithms: sum8, sum16, sum32, xor8, adler32, crc8, crc16, crc16-modbus, crc16-ccitt-false,\n\
+     crc16-xmodem, crc16-kermit, crc32, crc32c, md5, sha1, sha256"
+    hash(_lua, exec, algo: String, begin: usize, end: usize) -> String {
+        let algo = HashAlgo::from_name(&algo).ok_or_else(|| format!("unknown algorithm: {algo}").into_lua_err())?;
+        let data = exec.app.data.get(begin..=end).ok_or_else(|| "out of bounds".into_lua_err())?;
+        Ok(checksum::to_hex(&algo.compute(data)))
+    }
+}
+
 def_method! {
     "Set the cursor to `offset`, center the view on the cursor, and flash the cursor"
     focus_cursor(_lua, exec, offset: usize) -> () {
@@ -399,7 +507,16 @@ macro_rules! for_each_method {
     ($m:ident) => {
         $m!(add_region);
         $m!(load_file);
+        $m!(documents);
+        $m!(document);
+        $m!(switch_document);
+        $m!(open_document);
+        $m!(document_path);
+        $m!(doc_read_blob);
+        $m!(doc_write_blob);
         $m!(bookmark_set_int);
+        $m!(bookmark_freeze_int);
+        $m!(bookmark_unfreeze);
         $m!(region_pattern_fill);
         $m!(find_result_offsets);
         $m!(read_u8);
@@ -416,6 +533,7 @@ macro_rules! for_each_method {
         $m!(clear_bookmarks);
         $m!(add_bookmark);
         $m!(find_hex_string);
+        $m!(hash);
         $m!(focus_cursor);
         $m!(reoffset_bookmarks_cursor_diff);
         $m!(log);
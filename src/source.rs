@@ -1,11 +1,26 @@
-use std::{
-    fs::File,
-    io::{Read, Stdin},
+use {
+    crate::binfmt::Format,
+    std::{
+        fs::File,
+        io::{Read, Stdin},
+        ops::Range,
+    },
 };
 
 #[derive(Debug)]
 pub enum SourceProvider {
     File(File),
+    /// A file in a text encoding like Intel HEX, decoded into memory.
+    ///
+    /// Saving encodes the data back into the same format.
+    Encoded {
+        file: File,
+        format: Format,
+        /// Address of the first byte
+        base: usize,
+        /// Ranges of the data that are present in the file. The rest is gaps.
+        segments: Vec<Range<usize>>,
+    },
     Stdin(Stdin),
     #[cfg(windows)]
     WinProc {
@@ -13,6 +28,16 @@ pub enum SourceProvider {
         start: usize,
         size: usize,
     },
+    /// Memory of a Linux process, accessed through `/proc/<pid>/mem`
+    #[cfg(target_os = "linux")]
+    LinuxProc {
+        pid: u32,
+        /// Handle to `/proc/<pid>/mem`
+        mem: File,
+        /// Start address of the mapped range
+        start: usize,
+        size: usize,
+    },
 }
 
 /// FIXME: Prove this is actually safe
@@ -65,6 +90,17 @@ impl Clone for SourceProvider {
     fn clone(&self) -> Self {
         match self {
             Self::File(file) => Self::File(file.try_clone().unwrap()),
+            Self::Encoded {
+                file,
+                format,
+                base,
+                segments,
+            } => Self::Encoded {
+                file: file.try_clone().unwrap(),
+                format: *format,
+                base: *base,
+                segments: segments.clone(),
+            },
             Self::Stdin(_) => Self::Stdin(std::io::stdin()),
             #[cfg(windows)]
             Self::WinProc {
@@ -76,6 +112,18 @@ impl Clone for SourceProvider {
                 start: *start,
                 size: *size,
             },
+            #[cfg(target_os = "linux")]
+            Self::LinuxProc {
+                pid,
+                mem,
+                start,
+                size,
+            } => Self::LinuxProc {
+                pid: *pid,
+                mem: mem.try_clone().unwrap(),
+                start: *start,
+                size: *size,
+            },
         }
     }
 }
@@ -84,12 +132,19 @@ impl Read for SourceProvider {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         match self {
             Self::File(f) => f.read(buf),
+            // Decoded all at once on load, never streamed
+            Self::Encoded { .. } => Ok(0),
             Self::Stdin(stdin) => stdin.read(buf),
             #[cfg(windows)]
             SourceProvider::WinProc { .. } => {
                 gamedebug_core::per!("Todo: Read unimplemented");
                 Ok(0)
             }
+            #[cfg(target_os = "linux")]
+            SourceProvider::LinuxProc { .. } => {
+                gamedebug_core::per!("Todo: Read unimplemented");
+                Ok(0)
+            }
         }
     }
 }
@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+pub mod eval;
+mod parse;
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct StructMetaItem {
     pub name: String,
@@ -8,67 +11,78 @@ pub struct StructMetaItem {
 }
 
 impl StructMetaItem {
-    pub fn new(parsed: structparse::Struct, src: String) -> anyhow::Result<Self> {
-        let fields: anyhow::Result<Vec<StructField>> =
-            parsed.fields.into_iter().map(try_resolve_field).collect();
+    /// Parse a struct template source.
+    ///
+    /// The source can contain enum definitions, and exactly one struct definition.
+    pub fn parse(src: &str) -> anyhow::Result<Self> {
+        let parsed = parse::parse(src)?;
         Ok(Self {
-            name: parsed.name.to_string(),
-            src,
-            fields: fields?,
-        })
-    }
-    pub fn fields_with_offsets_mut(&mut self) -> impl Iterator<Item = (usize, &mut StructField)> {
-        let mut offset = 0;
-        let mut fields = self.fields.iter_mut();
-        std::iter::from_fn(move || {
-            let field = fields.next()?;
-            let ty_size = field.ty.size();
-            let item = (offset, &mut *field);
-            offset += ty_size;
-            Some(item)
+            name: parsed.name,
+            src: src.to_string(),
+            fields: parsed.fields,
         })
     }
 }
 
-fn try_resolve_field(field: structparse::Field) -> anyhow::Result<StructField> {
-    Ok(StructField {
-        name: field.name.to_string(),
-        ty: try_resolve_ty(field.ty)?,
-    })
-}
-
-fn try_resolve_ty(ty: structparse::Ty) -> anyhow::Result<StructTy> {
-    match ty {
-        structparse::Ty::Ident(ident) => {
-            let prim = match ident {
-                "i8" => StructPrimitive::I8,
-                "u8" => StructPrimitive::U8,
-                "i16" => StructPrimitive::I16,
-                "u16" => StructPrimitive::U16,
-                "i32" => StructPrimitive::I32,
-                "u32" => StructPrimitive::U32,
-                "i64" => StructPrimitive::I64,
-                "u64" => StructPrimitive::U64,
-                "f32" => StructPrimitive::F32,
-                "f64" => StructPrimitive::F64,
-                _ => anyhow::bail!("Unknown type"),
-            };
-            Ok(StructTy::Primitive {
-                ty: prim,
-                endian: Endian::Le,
-            })
-        }
-        structparse::Ty::Array(array) => Ok(StructTy::Array {
-            item_ty: Box::new(try_resolve_ty(*array.ty)?),
-            len: array.len.try_into()?,
-        }),
-    }
-}
-
 #[derive(Serialize, Deserialize, Clone)]
 pub struct StructField {
     pub name: String,
     pub ty: StructTy,
+    /// The field is only present if all of these conditions hold
+    #[serde(default)]
+    pub conds: Vec<Cond>,
+}
+
+/// Condition on the value of an earlier field, like `kind == 2`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Cond {
+    /// Name of the field. Bitfield members are referred to as `field.member`.
+    pub field: String,
+    pub op: CmpOp,
+    pub value: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// Any of the bits of the value are set
+    BitAnd,
+}
+
+impl CmpOp {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::BitAnd => "&",
+        }
+    }
+    pub fn eval(&self, lhs: i64, rhs: i64) -> bool {
+        match self {
+            Self::Eq => lhs == rhs,
+            Self::Ne => lhs != rhs,
+            Self::Lt => lhs < rhs,
+            Self::Le => lhs <= rhs,
+            Self::Gt => lhs > rhs,
+            Self::Ge => lhs >= rhs,
+            Self::BitAnd => lhs & rhs != 0,
+        }
+    }
+}
+
+impl std::fmt::Display for Cond {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.field, self.op.label(), self.value)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy)]
@@ -95,11 +109,57 @@ impl Endian {
 
 #[derive(Serialize, Deserialize, Clone)]
 pub enum StructTy {
-    Primitive { ty: StructPrimitive, endian: Endian },
-    Array { item_ty: Box<Self>, len: usize },
+    Primitive {
+        ty: StructPrimitive,
+        endian: Endian,
+    },
+    Array {
+        item_ty: Box<Self>,
+        len: usize,
+    },
+    /// Another struct from the metafile, looked up by name
+    Struct {
+        name: String,
+    },
+    /// A primitive whose values have names
+    Enum {
+        name: String,
+        ty: StructPrimitive,
+        endian: Endian,
+        variants: Vec<EnumVariant>,
+    },
+    /// Named bit ranges of a primitive, starting from the least significant bit
+    Bits {
+        ty: StructPrimitive,
+        endian: Endian,
+        fields: Vec<BitField>,
+    },
+    /// Array whose length is the value of an earlier field
+    DynArray {
+        item_ty: Box<Self>,
+        len_field: String,
+    },
+    /// A primitive holding an offset (from the start of the data) to a struct
+    Pointer {
+        ty: StructPrimitive,
+        endian: Endian,
+        target: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone)]
+pub struct EnumVariant {
+    pub name: String,
+    pub value: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BitField {
+    pub name: String,
+    pub width: u8,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum StructPrimitive {
     I8,
     U8,
@@ -114,7 +174,19 @@ pub enum StructPrimitive {
 }
 
 impl StructPrimitive {
-    fn label(&self) -> &'static str {
+    pub const ALL: [Self; 10] = [
+        Self::I8,
+        Self::U8,
+        Self::I16,
+        Self::U16,
+        Self::I32,
+        Self::U32,
+        Self::I64,
+        Self::U64,
+        Self::F32,
+        Self::F64,
+    ];
+    pub(crate) fn label(&self) -> &'static str {
         match self {
             Self::I8 => "i8",
             Self::U8 => "u8",
@@ -128,24 +200,54 @@ impl StructPrimitive {
             Self::F64 => "f64",
         }
     }
+    fn from_label(label: &str) -> Option<Self> {
+        Some(match label {
+            "i8" => Self::I8,
+            "u8" => Self::U8,
+            "i16" => Self::I16,
+            "u16" => Self::U16,
+            "i32" => Self::I32,
+            "u32" => Self::U32,
+            "i64" => Self::I64,
+            "u64" => Self::U64,
+            "f32" => Self::F32,
+            "f64" => Self::F64,
+            _ => return None,
+        })
+    }
+    pub fn size(&self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::I64 | Self::U64 | Self::F64 => 8,
+        }
+    }
+    pub fn is_float(&self) -> bool {
+        matches!(self, Self::F32 | Self::F64)
+    }
 }
 
 impl StructTy {
-    pub fn size(&self) -> usize {
+    /// The size of the type, if it can be known without looking at the data
+    pub fn size(&self) -> Option<usize> {
         match self {
-            Self::Primitive { ty, .. } => match ty {
-                StructPrimitive::I8 | StructPrimitive::U8 => 1,
-                StructPrimitive::I16 | StructPrimitive::U16 => 2,
-                StructPrimitive::I32 | StructPrimitive::U32 | StructPrimitive::F32 => 4,
-                StructPrimitive::I64 | StructPrimitive::U64 | StructPrimitive::F64 => 8,
-            },
-            Self::Array { item_ty, len } => item_ty.size() * *len,
+            Self::Primitive { ty, .. }
+            | Self::Enum { ty, .. }
+            | Self::Bits { ty, .. }
+            | Self::Pointer { ty, .. } => Some(ty.size()),
+            Self::Array { item_ty, len } => item_ty.size()?.checked_mul(*len),
+            Self::Struct { .. } | Self::DynArray { .. } => None,
         }
     }
-    pub fn endian_mut(&mut self) -> &mut Endian {
+    pub fn endian_mut(&mut self) -> Option<&mut Endian> {
         match self {
-            Self::Primitive { endian, .. } => endian,
-            Self::Array { item_ty, .. } => item_ty.endian_mut(),
+            Self::Primitive { endian, .. }
+            | Self::Enum { endian, .. }
+            | Self::Bits { endian, .. }
+            | Self::Pointer { endian, .. } => Some(endian),
+            Self::Array { item_ty, .. } | Self::DynArray { item_ty, .. } => item_ty.endian_mut(),
+            Self::Struct { .. } => None,
         }
     }
 }
@@ -161,6 +263,25 @@ impl std::fmt::Display for StructTy {
             Self::Array { item_ty, len } => {
                 write!(f, "[{item_ty}; {len}]")
             }
+            Self::Struct { name } => f.write_str(name),
+            Self::Enum {
+                name, ty, endian, ..
+            } => {
+                write!(f, "{name} ({}-{})", ty.label(), endian.label())
+            }
+            Self::Bits { ty, endian, fields } => {
+                write!(f, "bits<{}-{}> {{", ty.label(), endian.label())?;
+                for field in fields {
+                    write!(f, " {}: {},", field.name, field.width)?;
+                }
+                f.write_str(" }")
+            }
+            Self::DynArray { item_ty, len_field } => {
+                write!(f, "[{item_ty}; {len_field}]")
+            }
+            Self::Pointer { ty, endian, target } => {
+                write!(f, "ptr<{}-{}> {target}", ty.label(), endian.label())
+            }
         }
     }
 }
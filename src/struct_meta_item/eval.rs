@@ -0,0 +1,474 @@
+//! Evaluation of struct templates over data
+
+use {
+    super::{Endian, StructField, StructMetaItem, StructPrimitive, StructTy},
+    std::collections::{HashMap, HashSet},
+};
+
+/// How deep nested structs and pointers are followed
+const MAX_DEPTH: usize = 32;
+/// Maximum number of rows produced by an evaluation.
+///
+/// Sizes are still computed past this, so offsets of later fields stay correct.
+const MAX_ROWS: usize = 10_000;
+
+/// A row of an evaluated struct
+pub struct EvalField {
+    pub name: String,
+    /// Nesting depth, for indentation
+    pub depth: usize,
+    pub offset: usize,
+    pub size: usize,
+    pub kind: EvalKind,
+}
+
+#[derive(Clone)]
+pub enum EvalKind {
+    Primitive {
+        ty: StructPrimitive,
+        endian: Endian,
+    },
+    Enum {
+        ty: StructPrimitive,
+        endian: Endian,
+        /// Name of the variant matching the value, if any
+        variant: Option<String>,
+    },
+    /// Member of a bitfield
+    Bits {
+        value: u64,
+        width: u8,
+    },
+    Pointer {
+        ty: StructPrimitive,
+        endian: Endian,
+        /// The target offset, if it's in bounds
+        target: Option<usize>,
+    },
+    /// Array or struct, the members follow with higher depth
+    Group,
+    Error(String),
+}
+
+pub struct Evaluated {
+    pub rows: Vec<EvalField>,
+    /// Size of the struct at this offset, if evaluation succeeded
+    pub size: Option<usize>,
+}
+
+/// Evaluate `struct_` over `data` at `offset`.
+///
+/// `structs` are used to resolve references to other structs.
+pub fn eval_struct(
+    struct_: &StructMetaItem,
+    structs: &[StructMetaItem],
+    data: &[u8],
+    offset: usize,
+) -> Evaluated {
+    let mut ev = Evaluator {
+        structs,
+        data,
+        rows: Vec::new(),
+        visited: HashSet::new(),
+    };
+    let mut scope = Scope::new();
+    let size = ev.fields(&struct_.fields, offset, 0, &mut scope);
+    Evaluated {
+        rows: ev.rows,
+        size,
+    }
+}
+
+/// Values of the integer fields evaluated so far, used for dynamic lengths and conditions
+type Scope = HashMap<String, i64>;
+
+struct Evaluator<'a> {
+    structs: &'a [StructMetaItem],
+    data: &'a [u8],
+    rows: Vec<EvalField>,
+    /// The (struct, offset) pairs pointers were already followed to.
+    ///
+    /// Linked structures often point back to each other, or share their targets.
+    visited: HashSet<(String, usize)>,
+}
+
+impl Evaluator<'_> {
+    /// Evaluate a list of fields, returning their total size
+    fn fields(
+        &mut self,
+        fields: &[StructField],
+        offset: usize,
+        depth: usize,
+        scope: &mut Scope,
+    ) -> Option<usize> {
+        let mut size = 0;
+        for field in fields {
+            let mut present = true;
+            for cond in &field.conds {
+                let Some(&lhs) = scope.get(&cond.field) else {
+                    self.error(
+                        &field.name,
+                        depth,
+                        offset + size,
+                        format!("Unknown field `{}`", cond.field),
+                    );
+                    return None;
+                };
+                present &= cond.op.eval(lhs, cond.value);
+            }
+            if !present {
+                continue;
+            }
+            size += self.ty(&field.name, &field.ty, offset + size, depth, scope)?;
+        }
+        Some(size)
+    }
+    /// Evaluate a value of type `ty`, returning its size.
+    ///
+    /// Returns `None` if the size couldn't be determined. An error row is pushed in that case.
+    fn ty(
+        &mut self,
+        name: &str,
+        ty: &StructTy,
+        offset: usize,
+        depth: usize,
+        scope: &mut Scope,
+    ) -> Option<usize> {
+        match ty {
+            StructTy::Primitive { ty, endian } => {
+                if let Some(value) = read_int(self.data, offset, *ty, *endian) {
+                    scope.insert(name.to_string(), value);
+                }
+                self.push(
+                    name,
+                    depth,
+                    offset,
+                    ty.size(),
+                    EvalKind::Primitive {
+                        ty: *ty,
+                        endian: *endian,
+                    },
+                );
+                Some(ty.size())
+            }
+            StructTy::Enum {
+                ty,
+                endian,
+                variants,
+                ..
+            } => {
+                let value = read_int(self.data, offset, *ty, *endian);
+                if let Some(value) = value {
+                    scope.insert(name.to_string(), value);
+                }
+                let variant = variants
+                    .iter()
+                    .find(|var| Some(var.value) == value)
+                    .map(|var| var.name.clone());
+                self.push(
+                    name,
+                    depth,
+                    offset,
+                    ty.size(),
+                    EvalKind::Enum {
+                        ty: *ty,
+                        endian: *endian,
+                        variant,
+                    },
+                );
+                Some(ty.size())
+            }
+            StructTy::Bits { ty, endian, fields } => {
+                let value = read_int(self.data, offset, *ty, *endian);
+                if let Some(value) = value {
+                    scope.insert(name.to_string(), value);
+                }
+                self.push(
+                    name,
+                    depth,
+                    offset,
+                    ty.size(),
+                    EvalKind::Primitive {
+                        ty: *ty,
+                        endian: *endian,
+                    },
+                );
+                if let Some(value) = value {
+                    let value = value.cast_unsigned();
+                    let mut shift = 0;
+                    for field in fields {
+                        let member = (value >> shift) & mask(field.width);
+                        shift += u32::from(field.width);
+                        scope.insert(format!("{name}.{}", field.name), member.cast_signed());
+                        self.push(
+                            &field.name,
+                            depth + 1,
+                            offset,
+                            ty.size(),
+                            EvalKind::Bits {
+                                value: member,
+                                width: field.width,
+                            },
+                        );
+                    }
+                }
+                Some(ty.size())
+            }
+            StructTy::Pointer { ty, endian, target } => {
+                let value = read_int(self.data, offset, *ty, *endian);
+                let target_off = value
+                    .and_then(|v| usize::try_from(v).ok())
+                    .filter(|&off| off < self.data.len());
+                self.push(
+                    name,
+                    depth,
+                    offset,
+                    ty.size(),
+                    EvalKind::Pointer {
+                        ty: *ty,
+                        endian: *endian,
+                        target: target_off,
+                    },
+                );
+                // A null pointer doesn't point to anything.
+                // The target doesn't affect the size, so there is no need to evaluate it
+                // if it wouldn't be shown.
+                if let Some(target_off) = target_off.filter(|&off| off != 0)
+                    && self.rows.len() < MAX_ROWS
+                    && self.visited.insert((target.clone(), target_off))
+                {
+                    self.struct_(target, target_off, depth + 1, &mut Scope::new());
+                }
+                Some(ty.size())
+            }
+            StructTy::Struct { name: struct_name } => {
+                let row_idx = self.rows.len();
+                self.push(name, depth, offset, 0, EvalKind::Group);
+                let mut inner_scope = Scope::new();
+                let size = self.struct_(struct_name, offset, depth + 1, &mut inner_scope)?;
+                for (k, v) in inner_scope {
+                    scope.insert(format!("{name}.{k}"), v);
+                }
+                if let Some(row) = self.rows.get_mut(row_idx) {
+                    row.size = size;
+                }
+                Some(size)
+            }
+            StructTy::Array { item_ty, len } => self.array(name, item_ty, *len, offset, depth),
+            StructTy::DynArray { item_ty, len_field } => {
+                let len = scope.get(len_field).map(|&len| usize::try_from(len));
+                match len {
+                    Some(Ok(len)) => self.array(name, item_ty, len, offset, depth),
+                    Some(Err(_)) => {
+                        self.error(name, depth, offset, format!("`{len_field}` is negative"));
+                        None
+                    }
+                    None => {
+                        self.error(name, depth, offset, format!("Unknown field `{len_field}`"));
+                        None
+                    }
+                }
+            }
+        }
+    }
+    fn array(
+        &mut self,
+        name: &str,
+        item_ty: &StructTy,
+        len: usize,
+        offset: usize,
+        depth: usize,
+    ) -> Option<usize> {
+        let row_idx = self.rows.len();
+        self.push(name, depth, offset, 0, EvalKind::Group);
+        let mut size = 0;
+        for i in 0..len {
+            // No point in going through all the items if they aren't shown anyway
+            if self.rows.len() >= MAX_ROWS
+                && let Some(item_size) = item_ty.size()
+            {
+                size += item_size.saturating_mul(len - i);
+                break;
+            }
+            if offset + size > self.data.len() {
+                self.error(
+                    name,
+                    depth + 1,
+                    offset + size,
+                    "Array extends past the end".into(),
+                );
+                return None;
+            }
+            // Items don't see each other's fields
+            let item_size = self.ty(
+                &format!("[{i}]"),
+                item_ty,
+                offset + size,
+                depth + 1,
+                &mut Scope::new(),
+            )?;
+            // The next item would be at the same offset, so it would be the same
+            if item_size == 0 {
+                break;
+            }
+            size += item_size;
+        }
+        if let Some(row) = self.rows.get_mut(row_idx) {
+            row.size = size;
+        }
+        Some(size)
+    }
+    /// Evaluate the struct named `name`
+    fn struct_(
+        &mut self,
+        name: &str,
+        offset: usize,
+        depth: usize,
+        scope: &mut Scope,
+    ) -> Option<usize> {
+        if depth > MAX_DEPTH {
+            self.error(name, depth, offset, "Nesting too deep".into());
+            return None;
+        }
+        let Some(struct_) = self.structs.iter().find(|s| s.name == name) else {
+            self.error(name, depth, offset, format!("Unknown struct `{name}`"));
+            return None;
+        };
+        self.fields(&struct_.fields, offset, depth, scope)
+    }
+    fn push(&mut self, name: &str, depth: usize, offset: usize, size: usize, kind: EvalKind) {
+        if self.rows.len() < MAX_ROWS {
+            self.rows.push(EvalField {
+                name: name.to_string(),
+                depth,
+                offset,
+                size,
+                kind,
+            });
+        }
+    }
+    fn error(&mut self, name: &str, depth: usize, offset: usize, msg: String) {
+        self.push(name, depth, offset, 0, EvalKind::Error(msg));
+    }
+}
+
+fn mask(width: u8) -> u64 {
+    u64::MAX >> (64 - u32::from(width))
+}
+
+/// Read an integer primitive. Returns `None` for floats and out of bounds reads.
+fn read_int(data: &[u8], offset: usize, ty: StructPrimitive, endian: Endian) -> Option<i64> {
+    match read_value(data, offset, ty, endian)? {
+        Value::Int(v) => Some(v),
+        Value::UInt(v) => Some(v.cast_signed()),
+        Value::Float(_) => None,
+    }
+}
+
+/// Value of a primitive
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub enum Value {
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(v) => v.fmt(f),
+            Self::UInt(v) => v.fmt(f),
+            Self::Float(v) => v.fmt(f),
+        }
+    }
+}
+
+/// Read a primitive. Returns `None` for out of bounds reads.
+pub fn read_value(
+    data: &[u8],
+    offset: usize,
+    ty: StructPrimitive,
+    endian: Endian,
+) -> Option<Value> {
+    let bytes = data.get(offset..offset.checked_add(ty.size())?)?;
+    macro_rules! read {
+        ($t:ty) => {{
+            let arr = bytes.try_into().ok()?;
+            match endian {
+                Endian::Le => <$t>::from_le_bytes(arr),
+                Endian::Be => <$t>::from_be_bytes(arr),
+            }
+        }};
+    }
+    Some(match ty {
+        StructPrimitive::I8 => Value::Int(read!(i8).into()),
+        StructPrimitive::U8 => Value::UInt(read!(u8).into()),
+        StructPrimitive::I16 => Value::Int(read!(i16).into()),
+        StructPrimitive::U16 => Value::UInt(read!(u16).into()),
+        StructPrimitive::I32 => Value::Int(read!(i32).into()),
+        StructPrimitive::U32 => Value::UInt(read!(u32).into()),
+        StructPrimitive::I64 => Value::Int(read!(i64)),
+        StructPrimitive::U64 => Value::UInt(read!(u64)),
+        StructPrimitive::F32 => Value::Float(read!(f32).into()),
+        StructPrimitive::F64 => Value::Float(read!(f64)),
+    })
+}
+
+#[test]
+fn test_eval() {
+    let header = StructMetaItem::parse(
+        "enum Kind: u8 { A = 1, B }
+         struct Header {
+             kind: Kind,
+             flags: bits<u8> { big: 1, rest: 7 },
+             len: u16be,
+             data: [u8; len],
+             if flags.big == 1 { extra: u32 }
+             next: ptr<u8> Header,
+         }",
+    )
+    .unwrap();
+    let outer = StructMetaItem::parse("struct Outer { hdr: Header, after: u8 }").unwrap();
+    #[rustfmt::skip]
+    let data = [
+        2, 0b11, 0, 2, 0xAA, 0xBB, 1, 0, 0, 0, 0,
+        0x42,
+    ];
+    let structs = [header, outer];
+    let Evaluated { rows, size } = eval_struct(&structs[1], &structs, &data, 0);
+    assert_eq!(size, Some(12));
+    let find = |name: &str| rows.iter().find(|r| r.name == name).unwrap();
+    assert!(matches!(&find("kind").kind, EvalKind::Enum { variant: Some(v), .. } if v == "B"));
+    assert!(matches!(
+        find("rest").kind,
+        EvalKind::Bits { value: 1, width: 7 }
+    ));
+    assert_eq!(find("data").size, 2);
+    assert_eq!(find("extra").offset, 6);
+    assert!(matches!(
+        find("next").kind,
+        EvalKind::Pointer {
+            target: Some(0),
+            ..
+        }
+    ));
+    assert_eq!(find("hdr").size, 11);
+    assert_eq!(find("after").offset, 11);
+    assert!(!rows.iter().any(|r| matches!(r.kind, EvalKind::Error(_))));
+}
+
+#[test]
+fn test_eval_bounded() {
+    let node = StructMetaItem::parse("struct Node { l: ptr<u8> Node, r: ptr<u8> Node }").unwrap();
+    let empty = StructMetaItem::parse("struct Empty {}").unwrap();
+    let list = StructMetaItem::parse("struct List { len: u32, items: [Empty; len] }").unwrap();
+    let structs = [node, empty, list];
+    // Every node points to the next one twice, which is exponential if followed blindly
+    let data: Vec<u8> = (1..=64).flat_map(|i| [i * 2, i * 2]).collect();
+    let Evaluated { rows, size } = eval_struct(&structs[0], &structs, &data, 0);
+    assert_eq!(size, Some(2));
+    assert!(rows.len() < 64 * 3);
+    // Zero sized items don't loop for `len` iterations
+    let Evaluated { size, .. } = eval_struct(&structs[2], &structs, &u32::MAX.to_le_bytes(), 0);
+    assert_eq!(size, Some(4));
+}
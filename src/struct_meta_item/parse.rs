@@ -0,0 +1,432 @@
+//! Parser for the struct template language
+//!
+//! ```text
+//! enum Kind: u8 { Empty, Data = 2, Link }
+//! struct Chunk {
+//!     kind: Kind,
+//!     flags: bits<u8> { compressed: 1, level: 3 },
+//!     len: u32be,
+//!     data: [u8; len],
+//!     if flags.compressed == 1 {
+//!         orig_len: u32,
+//!     }
+//!     next: ptr<u32> Chunk,
+//! }
+//! ```
+
+use {
+    super::{BitField, CmpOp, Cond, Endian, EnumVariant, StructField, StructPrimitive, StructTy},
+    anyhow::{Context as _, bail},
+    std::collections::HashMap,
+};
+
+pub struct Parsed {
+    pub name: String,
+    pub fields: Vec<StructField>,
+}
+
+pub fn parse(src: &str) -> anyhow::Result<Parsed> {
+    let mut parser = Parser {
+        tokens: lex(src)?,
+        pos: 0,
+        enums: HashMap::new(),
+    };
+    let mut parsed = None;
+    while let Some(tok) = parser.peek() {
+        match tok {
+            Tok::Ident("enum") => parser.enum_def()?,
+            Tok::Ident("struct") => {
+                if parsed.is_some() {
+                    parser.bail(
+                        "Only one struct per template. \
+                         Define other structs separately, and refer to them by name.",
+                    )?;
+                }
+                parsed = Some(parser.struct_def()?);
+            }
+            _ => parser.bail("Expected `enum` or `struct`")?,
+        }
+    }
+    parsed.context("No struct defined")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Tok<'src> {
+    Ident(&'src str),
+    Num(i64),
+    /// Punctuation, including two character operators like `==`
+    Punct(&'src str),
+}
+
+struct Token<'src> {
+    tok: Tok<'src>,
+    line: usize,
+}
+
+fn lex(src: &str) -> anyhow::Result<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut rest = src;
+    while let Some(c) = rest.chars().next() {
+        if c == '\n' {
+            line += 1;
+            rest = &rest[1..];
+        } else if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if rest.starts_with("//") {
+            rest = rest.find('\n').map_or("", |nl| &rest[nl..]);
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token {
+                tok: Tok::Ident(&rest[..end]),
+                line,
+            });
+            rest = &rest[end..];
+        } else if c.is_ascii_digit()
+            || (c == '-' && rest[1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            let end = rest[1..]
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .map_or(rest.len(), |end| end + 1);
+            let num =
+                parse_num(&rest[..end]).with_context(|| format!("line {line}: Invalid number"))?;
+            tokens.push(Token {
+                tok: Tok::Num(num),
+                line,
+            });
+            rest = &rest[end..];
+        } else {
+            let len = if ["==", "!=", "<=", ">="].iter().any(|op| rest.starts_with(op)) {
+                2
+            } else if "{}[]<>:;,=.&".contains(c) {
+                1
+            } else {
+                bail!("line {line}: Unexpected character '{c}'");
+            };
+            tokens.push(Token {
+                tok: Tok::Punct(&rest[..len]),
+                line,
+            });
+            rest = &rest[len..];
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_num(s: &str) -> anyhow::Result<i64> {
+    let (neg, s) = match s.strip_prefix('-') {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+    let s = s.replace('_', "");
+    let num = match s.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16)?,
+        None => s.parse()?,
+    };
+    Ok(if neg { -num } else { num })
+}
+
+struct Parser<'src> {
+    tokens: Vec<Token<'src>>,
+    pos: usize,
+    /// Enums defined so far, by name
+    enums: HashMap<&'src str, (StructPrimitive, Vec<EnumVariant>)>,
+}
+
+impl<'src> Parser<'src> {
+    fn peek(&self) -> Option<Tok<'src>> {
+        self.tokens.get(self.pos).map(|t| t.tok)
+    }
+    fn next(&mut self) -> anyhow::Result<Tok<'src>> {
+        let tok = self.peek().context("Unexpected end of template")?;
+        self.pos += 1;
+        Ok(tok)
+    }
+    fn bail<T>(&self, msg: &str) -> anyhow::Result<T> {
+        match self.tokens.get(self.pos) {
+            Some(tok) => bail!("line {}: {msg}, found {:?}", tok.line, tok.tok),
+            None => bail!("{msg}, found end of template"),
+        }
+    }
+    /// Consume `punct` if it's the next token
+    fn eat(&mut self, punct: &str) -> bool {
+        if self.peek() == Some(Tok::Punct(punct)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+    fn expect(&mut self, punct: &str) -> anyhow::Result<()> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            self.bail(&format!("Expected `{punct}`"))
+        }
+    }
+    fn ident(&mut self) -> anyhow::Result<&'src str> {
+        match self.peek() {
+            Some(Tok::Ident(ident)) => {
+                self.pos += 1;
+                Ok(ident)
+            }
+            _ => self.bail("Expected identifier"),
+        }
+    }
+    fn num(&mut self) -> anyhow::Result<i64> {
+        match self.peek() {
+            Some(Tok::Num(num)) => {
+                self.pos += 1;
+                Ok(num)
+            }
+            _ => self.bail("Expected number"),
+        }
+    }
+    /// Dotted path, like `flags.compressed`
+    fn path(&mut self) -> anyhow::Result<String> {
+        let mut path = self.ident()?.to_string();
+        while self.eat(".") {
+            path.push('.');
+            path.push_str(self.ident()?);
+        }
+        Ok(path)
+    }
+    fn enum_def(&mut self) -> anyhow::Result<()> {
+        self.next()?;
+        let name = self.ident()?;
+        let ty = if self.eat(":") {
+            self.primitive()?.0
+        } else {
+            StructPrimitive::U32
+        };
+        self.expect("{")?;
+        let mut variants = Vec::new();
+        // `None` once the previous value was the largest one
+        let mut next_value = Some(0);
+        while !self.eat("}") {
+            let name = self.ident()?.to_string();
+            let value = if self.eat("=") {
+                self.num()?
+            } else {
+                match next_value {
+                    Some(value) => value,
+                    None => return self.bail("Enum value out of range"),
+                }
+            };
+            next_value = value.checked_add(1);
+            variants.push(EnumVariant { name, value });
+            if !self.eat(",") {
+                self.expect("}")?;
+                break;
+            }
+        }
+        self.enums.insert(name, (ty, variants));
+        Ok(())
+    }
+    fn struct_def(&mut self) -> anyhow::Result<Parsed> {
+        self.next()?;
+        let name = self.ident()?.to_string();
+        self.expect("{")?;
+        let mut fields = Vec::new();
+        self.fields(&[], &mut fields)?;
+        Ok(Parsed { name, fields })
+    }
+    /// Parse fields until the closing `}`
+    fn fields(&mut self, conds: &[Cond], out: &mut Vec<StructField>) -> anyhow::Result<()> {
+        loop {
+            if self.eat("}") {
+                return Ok(());
+            }
+            if self.peek() == Some(Tok::Ident("if")) {
+                self.next()?;
+                let field = self.path()?;
+                let op = match self.next()? {
+                    Tok::Punct("==") => CmpOp::Eq,
+                    Tok::Punct("!=") => CmpOp::Ne,
+                    Tok::Punct("<") => CmpOp::Lt,
+                    Tok::Punct("<=") => CmpOp::Le,
+                    Tok::Punct(">") => CmpOp::Gt,
+                    Tok::Punct(">=") => CmpOp::Ge,
+                    Tok::Punct("&") => CmpOp::BitAnd,
+                    _ => {
+                        self.pos -= 1;
+                        return self.bail("Expected comparison operator");
+                    }
+                };
+                let value = self.num()?;
+                self.expect("{")?;
+                let mut inner_conds = conds.to_vec();
+                inner_conds.push(Cond { field, op, value });
+                self.fields(&inner_conds, out)?;
+                self.eat(",");
+                continue;
+            }
+            let name = self.ident()?.to_string();
+            self.expect(":")?;
+            let ty = self.ty()?;
+            out.push(StructField {
+                name,
+                ty,
+                conds: conds.to_vec(),
+            });
+            if !self.eat(",") {
+                self.expect("}")?;
+                return Ok(());
+            }
+        }
+    }
+    /// Primitive type name, with optional `le`/`be` suffix
+    fn primitive(&mut self) -> anyhow::Result<(StructPrimitive, Endian)> {
+        let ident = self.ident()?;
+        match parse_primitive(ident) {
+            Some(prim) => Ok(prim),
+            None => {
+                self.pos -= 1;
+                self.bail("Expected primitive type")
+            }
+        }
+    }
+    fn ty(&mut self) -> anyhow::Result<StructTy> {
+        if self.eat("[") {
+            let item_ty = Box::new(self.ty()?);
+            self.expect(";")?;
+            let ty = match self.peek() {
+                Some(Tok::Num(len)) => {
+                    self.pos += 1;
+                    let len: usize = len.try_into().context("Array length can't be negative")?;
+                    if let Some(item_size) = item_ty.size()
+                        && item_size.checked_mul(len).is_none()
+                    {
+                        return self.bail("Array is too large");
+                    }
+                    StructTy::Array { item_ty, len }
+                }
+                _ => StructTy::DynArray {
+                    item_ty,
+                    len_field: self.path()?,
+                },
+            };
+            self.expect("]")?;
+            return Ok(ty);
+        }
+        let ident = self.ident()?;
+        match ident {
+            "bits" => {
+                self.expect("<")?;
+                let (ty, endian) = self.primitive()?;
+                self.expect(">")?;
+                self.expect("{")?;
+                let mut fields = Vec::new();
+                while !self.eat("}") {
+                    let name = self.ident()?.to_string();
+                    self.expect(":")?;
+                    let width = self.num()?;
+                    let width = u8::try_from(width).ok().filter(|w| (1..=64).contains(w));
+                    let Some(width) = width else {
+                        self.pos -= 1;
+                        return self.bail("Bit width must be between 1 and 64");
+                    };
+                    fields.push(BitField { name, width });
+                    if !self.eat(",") {
+                        self.expect("}")?;
+                        break;
+                    }
+                }
+                let total: usize = fields.iter().map(|f| usize::from(f.width)).sum();
+                if total > ty.size() * 8 {
+                    self.bail(&format!("Bitfield is wider than {}", ty.label()))?;
+                }
+                Ok(StructTy::Bits { ty, endian, fields })
+            }
+            "ptr" => {
+                self.expect("<")?;
+                let (ty, endian) = self.primitive()?;
+                if ty.is_float() {
+                    self.pos -= 1;
+                    return self.bail("Pointer type must be an integer");
+                }
+                self.expect(">")?;
+                let target = self.ident()?.to_string();
+                Ok(StructTy::Pointer { ty, endian, target })
+            }
+            _ => {
+                if let Some((ty, endian)) = parse_primitive(ident) {
+                    Ok(StructTy::Primitive { ty, endian })
+                } else if let Some((ty, variants)) = self.enums.get(ident) {
+                    Ok(StructTy::Enum {
+                        name: ident.to_string(),
+                        ty: *ty,
+                        endian: Endian::Le,
+                        variants: variants.clone(),
+                    })
+                } else {
+                    // Resolved when the struct is evaluated, as it can refer to any struct in the
+                    // metafile
+                    Ok(StructTy::Struct {
+                        name: ident.to_string(),
+                    })
+                }
+            }
+        }
+    }
+}
+
+fn parse_primitive(ident: &str) -> Option<(StructPrimitive, Endian)> {
+    if let Some(prim) = StructPrimitive::from_label(ident) {
+        return Some((prim, Endian::Le));
+    }
+    let (prim, endian) = if let Some(prim) = ident.strip_suffix("le") {
+        (prim, Endian::Le)
+    } else {
+        (ident.strip_suffix("be")?, Endian::Be)
+    };
+    Some((StructPrimitive::from_label(prim)?, endian))
+}
+
+#[test]
+fn test_parse() {
+    let parsed = parse(
+        "enum Kind: u8 { A, B = 5, C }
+         struct Foo {
+             kind: Kind,
+             flags: bits<u16be> { a: 1, b: 3 },
+             len: u32,
+             data: [u8; len],
+             if flags.a == 1 { extra: [i16; 2] }
+             next: ptr<u32> Foo,
+         }",
+    )
+    .unwrap();
+    assert_eq!(parsed.name, "Foo");
+    let names: Vec<_> = parsed.fields.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["kind", "flags", "len", "data", "extra", "next"]);
+    assert!(matches!(
+        &parsed.fields[0].ty,
+        StructTy::Enum { variants, .. }
+            if variants.iter().map(|v| v.value).eq([0, 5, 6])
+    ));
+    assert!(matches!(
+        &parsed.fields[1].ty,
+        StructTy::Bits {
+            endian: Endian::Be,
+            ..
+        }
+    ));
+    assert!(
+        matches!(&parsed.fields[3].ty, StructTy::DynArray { len_field, .. } if len_field == "len")
+    );
+    assert_eq!(parsed.fields[4].conds.len(), 1);
+    assert!(parsed.fields[5].conds.is_empty());
+    // The old flat syntax still works
+    assert!(parse("struct Old { a: u8, b: [u16; 4] }").is_ok());
+}
+
+#[test]
+fn test_parse_overflow() {
+    assert!(parse("enum E: i64 { A = 9223372036854775807 } struct S { e: E }").is_ok());
+    assert!(parse("enum E: i64 { A = 9223372036854775807, B } struct S { e: E }").is_err());
+    assert!(parse("struct S { a: [u64; 9223372036854775807] }").is_err());
+    assert!(parse("struct S { a: [[u8; 4611686018427387904]; 4] }").is_err());
+}
@@ -1,7 +1,6 @@
 use {
     crate::{
         app::{App, interact_mode::InteractMode},
-        damage_region::DamageRegion,
         gui::{
             self, Gui,
             dialogs::JumpDialog,
@@ -126,13 +125,28 @@ fn handle_post_egui_events(
                 }
                 if button == mouse::Button::Left {
                     gui.context_menu = None;
-                    if let Some((off, _view_idx)) = app.byte_offset_at_pos(mp.x, mp.y) {
+                    let clicked_off = app.byte_offset_at_pos(mp.x, mp.y);
+                    if let Some((off, _view_idx)) = clicked_off {
                         app.hex_ui.lmb_drag_offset = Some(off);
                         app.edit_state.set_cursor(off);
                     }
                     if let Some(view_idx) = app.view_idx_at_pos(mp.x, mp.y) {
                         app.hex_ui.focused_view = Some(view_idx);
                         gui.win.views.selected = view_idx;
+                        let view = &mut app.meta_state.meta.views[view_idx].view;
+                        if clicked_off.is_some()
+                            && let Some(bit) = view.bit_at_pos(mp.x, mp.y)
+                            && let Some(edit_buf) = view.edit_buffer_mut()
+                        {
+                            // Put the bit cursor on the clicked bit, and flip it on ctrl+click
+                            edit_buf.reset();
+                            edit_buf.cursor = bit.into();
+                            if app.hex_ui.interact_mode == InteractMode::Edit
+                                && app.input.key_down(Key::LControl)
+                            {
+                                view.toggle_bit(app.edit_state.cursor, &mut app.data);
+                            }
+                        }
                     }
                 } else if button == mouse::Button::Right {
                     match app.view_at_pos(mp.x, mp.y) {
@@ -271,6 +285,11 @@ fn draw(
     for view_key in app.meta_state.meta.layouts[app.hex_ui.current_layout].iter() {
         view::View::draw(view_key, app, gui, window, vertex_buffer, font);
     }
+    if let Some(doc) = &app.compare {
+        for (view_key, view) in &doc.views {
+            view.draw_compare(*view_key, doc, app, gui, window, vertex_buffer, font);
+        }
+    }
 }
 
 /// Returns events that should be processed post-egui
@@ -374,14 +393,17 @@ fn handle_text_entered(app: &mut App, unicode: char, msg: &mut MessageDialog) {
             let Some(focused) = app.hex_ui.focused_view else {
                 return;
             };
-            let view = &mut app.meta_state.meta.views[focused].view;
-            view.handle_text_entered(
+            let splice = app.meta_state.meta.views[focused].view.handle_text_entered(
                 unicode,
                 &mut app.edit_state,
                 &app.preferences,
                 &mut app.data,
                 msg,
             );
+            if let Some(splice) = splice {
+                app.follow_splice(splice);
+            }
+            let view = &mut app.meta_state.meta.views[focused].view;
             keep_cursor_in_view(view, &app.meta_state.meta.low, app.edit_state.cursor);
         }
         InteractMode::View => {}
@@ -643,17 +665,35 @@ fn handle_key_pressed(
                 }
             }
         }
+        Key::Delete if app.edit_state.insert_mode => {
+            msg_if_fail(
+                app.delete_selection_or_cursor(),
+                "Failed to delete",
+                &mut gui.msg_dialog,
+            );
+        }
         Key::Delete => {
             let mut any = false;
             for sel in app.hex_ui.selected_regions() {
                 app.data.zero_fill_region(sel);
                 any = true;
             }
-            if !any && let Some(byte) = app.data.get_mut(app.edit_state.cursor) {
-                *byte = 0;
-                app.data.widen_dirty_region(DamageRegion::Single(app.edit_state.cursor));
+            if !any {
+                app.data.write(app.edit_state.cursor, &[0]);
             }
         }
+        Key::Backspace if app.edit_state.insert_mode && app.edit_state.cursor > 0 => {
+            app.hex_ui.clear_selections();
+            app.edit_state.step_cursor_back();
+            msg_if_fail(
+                app.delete_selection_or_cursor(),
+                "Failed to delete",
+                &mut gui.msg_dialog,
+            );
+        }
+        Key::Insert if app.edit_state.insert_mode || app.data.can_change_len() => {
+            app.edit_state.insert_mode ^= true;
+        }
         Key::F1 => app.hex_ui.interact_mode = InteractMode::View,
         Key::F2 => app.hex_ui.interact_mode = InteractMode::Edit,
         Key::F5 => gui.win.layouts.open.toggle(),
@@ -671,18 +711,23 @@ fn handle_key_pressed(
             app.hex_ui.clear_selections();
         }
         Key::Enter => {
-            if let Some(view_key) = app.hex_ui.focused_view {
-                app.meta_state.meta.views[view_key].view.finish_editing(
+            if let Some(view_key) = app.hex_ui.focused_view
+                && let Some(splice) = app.meta_state.meta.views[view_key].view.confirm_editing(
                     &mut app.edit_state,
                     &mut app.data,
                     &app.preferences,
                     &mut gui.msg_dialog,
-                );
+                )
+            {
+                app.follow_splice(splice);
             }
         }
         Key::A if key_mod.ctrl => {
             app.focused_view_select_all();
         }
+        Key::Z if key_mod.ctrl && key_mod.shift => app.redo(),
+        Key::Z if key_mod.ctrl => app.undo(),
+        Key::Y if key_mod.ctrl => app.redo(),
         Key::E if key_mod.ctrl => {
             gui.win.external_command.open.set(true);
         }
@@ -731,7 +776,7 @@ fn handle_key_pressed(
                 );
             }
         }
-        Key::W if key_mod.ctrl => app.close_file(),
+        Key::W if key_mod.ctrl => app.close_document(app.docs.active),
         Key::J if key_mod.ctrl => Gui::add_dialog(&mut gui.dialogs, JumpDialog::default()),
         Key::Num1 if key_mod.shift => {
             if !editing_text {
@@ -1,9 +1,9 @@
 use {
     crate::{
         app::{edit_state::EditState, presentation::Presentation},
-        damage_region::DamageRegion,
         data::Data,
         edit_buffer::EditBuffer,
+        edit_history::Splice,
         gui::message_dialog::{Icon, MessageDialog},
         hex_conv::merge_hex_halves,
         meta::{MetaLow, PerspectiveKey, PerspectiveMap, RegionMap, region::Region},
@@ -322,6 +322,10 @@ impl View {
         (self.col_w, self.row_h) = match &self.kind {
             ViewKind::Hex(hex) => (hex.font_size * 2 - 2, hex.font_size),
             ViewKind::Dec(dec) => (dec.font_size * 3 - 6, dec.font_size),
+            ViewKind::Bin(bin) => (
+                bit_glyph_w(bin.font_size).saturating_mul(8).saturating_add(6),
+                bin.font_size,
+            ),
             ViewKind::Text(data) => (data.font_size, data.line_spacing.max(1)),
             ViewKind::Block => (self.col_w, self.row_h),
         }
@@ -333,6 +337,7 @@ impl View {
         match &mut self.kind {
             ViewKind::Hex(HexData { edit_buf, .. })
             | ViewKind::Dec(HexData { edit_buf, .. })
+            | ViewKind::Bin(HexData { edit_buf, .. })
             | ViewKind::Text(TextData { edit_buf, .. }) => edit_buf.resize(glyph_count),
             _ => {}
         }
@@ -342,10 +347,14 @@ impl View {
         match self.kind {
             ViewKind::Hex(_) => 2,
             ViewKind::Dec(_) => 3,
+            ViewKind::Bin(_) => 8,
             ViewKind::Text { .. } => 1,
             ViewKind::Block => 1,
         }
     }
+    /// Handle text input for editing.
+    ///
+    /// Returns how the data was moved if a byte was inserted in insert mode.
     pub fn handle_text_entered(
         &mut self,
         unicode: char,
@@ -353,48 +362,70 @@ impl View {
         preferences: &SessionPrefs,
         data: &mut Data,
         msg: &mut MessageDialog,
-    ) {
-        if self.char_valid(unicode) {
-            match &mut self.kind {
-                ViewKind::Hex(hex) => {
-                    if !hex.edit_buf.dirty {
-                        let Some(byte) = data.get(edit_state.cursor) else {
-                            return;
-                        };
-                        let s = format!("{byte:02X}");
-                        hex.edit_buf.update_from_string(&s);
-                    }
-                    if hex.edit_buf.enter_byte(unicode.to_ascii_uppercase() as u8)
-                        || preferences.quick_edit
-                    {
-                        self.finish_editing(edit_state, data, preferences, msg);
-                    }
+    ) -> Option<Splice> {
+        if unicode == ' ' && matches!(self.kind, ViewKind::Bin(_)) {
+            self.toggle_bit(edit_state.cursor, data);
+            return None;
+        }
+        if !self.char_valid(unicode) {
+            return None;
+        }
+        // Quick edit doesn't make sense in insert mode, it would insert a byte per digit
+        let quick_edit = preferences.quick_edit && !edit_state.insert_mode;
+        match &mut self.kind {
+            ViewKind::Hex(hex) => {
+                if !hex.edit_buf.dirty {
+                    let byte = if edit_state.insert_mode {
+                        0
+                    } else {
+                        *data.get(edit_state.cursor)?
+                    };
+                    let s = format!("{byte:02X}");
+                    hex.edit_buf.update_from_string(&s);
                 }
-                ViewKind::Dec(dec) => {
-                    if !dec.edit_buf.dirty {
-                        let Some(byte) = data.get(edit_state.cursor) else {
-                            return;
-                        };
-                        let s = format!("{byte:03}");
-                        dec.edit_buf.update_from_string(&s);
-                    }
-                    if dec.edit_buf.enter_byte(unicode.to_ascii_uppercase() as u8)
-                        || preferences.quick_edit
-                    {
-                        self.finish_editing(edit_state, data, preferences, msg);
-                    }
+                if hex.edit_buf.enter_byte(unicode.to_ascii_uppercase() as u8) || quick_edit {
+                    return self.finish_editing(edit_state, data, preferences, msg);
                 }
-                ViewKind::Text(text) => {
-                    if text.edit_buf.enter_byte((unicode as u8).wrapping_add_signed(-text.offset))
-                        || preferences.quick_edit
-                    {
-                        self.finish_editing(edit_state, data, preferences, msg);
-                    }
+            }
+            ViewKind::Dec(dec) => {
+                if !dec.edit_buf.dirty {
+                    let byte = if edit_state.insert_mode {
+                        0
+                    } else {
+                        *data.get(edit_state.cursor)?
+                    };
+                    let s = format!("{byte:03}");
+                    dec.edit_buf.update_from_string(&s);
+                }
+                if dec.edit_buf.enter_byte(unicode.to_ascii_uppercase() as u8) || quick_edit {
+                    return self.finish_editing(edit_state, data, preferences, msg);
                 }
-                // Block doesn't do any text input
-                ViewKind::Block => {}
             }
+            ViewKind::Bin(bin) => {
+                if !bin.edit_buf.dirty {
+                    let byte = if edit_state.insert_mode {
+                        0
+                    } else {
+                        *data.get(edit_state.cursor)?
+                    };
+                    let s = format!("{byte:08b}");
+                    bin.edit_buf.update_from_string(&s);
+                }
+                if bin.edit_buf.enter_byte(unicode as u8) || quick_edit {
+                    return self.finish_editing(edit_state, data, preferences, msg);
+                }
+            }
+            ViewKind::Text(text) => {
+                if text.edit_buf.enter_byte((unicode as u8).wrapping_add_signed(-text.offset))
+                    || quick_edit
+                {
+                    return self.finish_editing(edit_state, data, preferences, msg);
+                }
+            }
+            // Block doesn't do any text input
+            ViewKind::Block => {}
         }
+        None
     }
 
     /// Returns the size needed by this view to display fully
@@ -418,6 +449,7 @@ impl View {
         match self.kind {
             ViewKind::Hex(_) => matches!(unicode, '0'..='9' | 'a'..='f'),
             ViewKind::Dec(_) => unicode.is_ascii_digit(),
+            ViewKind::Bin(_) => matches!(unicode, '0' | '1'),
             ViewKind::Text { .. } => {
                 unicode.is_ascii() && !unicode.is_control() && !matches!(unicode, '\t')
             }
@@ -425,49 +457,94 @@ impl View {
         }
     }
 
+    /// Apply the edit buffer to the data.
+    ///
+    /// Returns how the data was moved if a byte was inserted in insert mode.
     pub fn finish_editing(
         &mut self,
         edit_state: &mut EditState,
         data: &mut Data,
         preferences: &SessionPrefs,
         msg: &mut MessageDialog,
-    ) {
-        match &mut self.kind {
+    ) -> Option<Splice> {
+        let byte = match &mut self.kind {
             ViewKind::Hex(hex) => {
                 match merge_hex_halves(hex.edit_buf.buf[0], hex.edit_buf.buf[1]) {
-                    Some(merged) => {
-                        if let Some(byte) = data.get_mut(edit_state.cursor) {
-                            *byte = merged;
-                        }
+                    Some(merged) => Some(merged),
+                    None => {
+                        per!("finish_editing: Failed to merge hex halves");
+                        None
                     }
-                    None => per!("finish_editing: Failed to merge hex halves"),
                 }
-                data.widen_dirty_region(DamageRegion::Single(edit_state.cursor));
             }
             ViewKind::Dec(dec) => {
                 let s =
                     std::str::from_utf8(&dec.edit_buf.buf).expect("Invalid utf-8 in edit buffer");
                 match s.parse() {
-                    Ok(num) => {
-                        data[edit_state.cursor] = num;
-                        data.widen_dirty_region(DamageRegion::Single(edit_state.cursor));
+                    Ok(num) => Some(num),
+                    Err(e) => {
+                        msg.open(Icon::Error, "Invalid value", e.to_string());
+                        None
                     }
-                    Err(e) => msg.open(Icon::Error, "Invalid value", e.to_string()),
                 }
             }
-            ViewKind::Text(text) => {
-                let Some(byte) = data.get_mut(edit_state.cursor) else {
-                    return;
+            ViewKind::Bin(bin) => {
+                let s =
+                    std::str::from_utf8(&bin.edit_buf.buf).expect("Invalid utf-8 in edit buffer");
+                match u8::from_str_radix(s, 2) {
+                    Ok(num) => Some(num),
+                    Err(e) => {
+                        msg.open(Icon::Error, "Invalid value", e.to_string());
+                        None
+                    }
+                }
+            }
+            ViewKind::Text(text) => Some(text.edit_buf.buf[0]),
+            ViewKind::Block => None,
+        };
+        let splice = match byte {
+            Some(byte) if edit_state.insert_mode => {
+                let Some(splice) = data.insert(edit_state.cursor, &[byte]) else {
+                    msg.open(
+                        Icon::Error,
+                        "Insert error",
+                        "Inserting requires the data to be loaded into memory (not memory mapped), \
+                         from a source that can change size (not process memory)",
+                    );
+                    return None;
                 };
-                *byte = text.edit_buf.buf[0];
-                data.widen_dirty_region(DamageRegion::Single(edit_state.cursor));
+                Some(splice)
             }
-            ViewKind::Block => {}
-        }
+            Some(byte) => {
+                if !data.write(edit_state.cursor, &[byte]) {
+                    return None;
+                }
+                None
+            }
+            None => None,
+        };
         if edit_state.cursor + 1 < data.len() && !preferences.sticky_edit {
             edit_state.step_cursor_forward();
         }
         self.reset_edit_buf();
+        splice
+    }
+
+    /// Finish editing on the user's request (Enter key).
+    ///
+    /// In insert mode, nothing is inserted unless something was typed, since a clean
+    /// edit buffer only holds a stale value.
+    pub fn confirm_editing(
+        &mut self,
+        edit_state: &mut EditState,
+        data: &mut Data,
+        preferences: &SessionPrefs,
+        msg: &mut MessageDialog,
+    ) -> Option<Splice> {
+        if edit_state.insert_mode && !self.edit_buffer_mut().is_some_and(|buf| buf.dirty) {
+            return None;
+        }
+        self.finish_editing(edit_state, data, preferences, msg)
     }
 
     pub fn cancel_editing(&mut self) {
@@ -488,12 +565,51 @@ impl View {
 
     pub(crate) fn edit_buffer_mut(&mut self) -> Option<&mut EditBuffer> {
         match &mut self.kind {
-            ViewKind::Hex(data) | ViewKind::Dec(data) => Some(&mut data.edit_buf),
+            ViewKind::Hex(data) | ViewKind::Dec(data) | ViewKind::Bin(data) => {
+                Some(&mut data.edit_buf)
+            }
             ViewKind::Text(data) => Some(&mut data.edit_buf),
             ViewKind::Block => None,
         }
     }
 
+    /// The bit under the bit cursor of a binary view, counted from the most significant one.
+    ///
+    /// The bit cursor is the cursor of the edit buffer.
+    pub(crate) fn bit_cursor(&self) -> Option<u8> {
+        match &self.kind {
+            ViewKind::Bin(bin) => u8::try_from(bin.edit_buf.cursor).ok(),
+            _ => None,
+        }
+    }
+
+    /// The bit under a viewport position in a binary view, counted from the most significant one
+    pub(crate) fn bit_at_pos(&self, x: ViewportScalar, y: ViewportScalar) -> Option<u8> {
+        let ViewKind::Bin(bin) = &self.kind else {
+            return None;
+        };
+        let (x, _) = self.viewport_rect.relative_offset_of_pos(x, y)?;
+        let x_in_col =
+            (x + self.scroll_offset.pix_xoff).rem_euclid(i16::saturating_from(self.col_w));
+        let glyph_w = i16::saturating_from(bit_glyph_w(bin.font_size));
+        u8::try_from((x_in_col / glyph_w).min(7)).ok()
+    }
+
+    /// Flip the bit under the bit cursor of a binary view, in the byte at `offset`.
+    ///
+    /// If a value is being typed, the bit is flipped in that instead.
+    pub(crate) fn toggle_bit(&mut self, offset: usize, data: &mut Data) {
+        let ViewKind::Bin(bin) = &mut self.kind else {
+            return;
+        };
+        let glyph = usize::from(bin.edit_buf.cursor);
+        if bin.edit_buf.dirty {
+            bin.edit_buf.buf[glyph] ^= b'0' ^ b'1';
+        } else {
+            data.edit(offset..offset + 1, |bytes| bytes[0] ^= 0x80 >> glyph);
+        }
+    }
+
     pub(crate) fn contains_region(&self, reg: &Region, meta: &crate::meta::Meta) -> bool {
         meta.low.regions[meta.low.perspectives[self.perspective].region]
             .region
@@ -523,6 +639,13 @@ pub struct Offsets {
     pub byte: usize,
 }
 
+/// Width of one bit glyph in a binary view.
+///
+/// The column of a byte fits 8 of these, with some spacing after them.
+pub(crate) fn bit_glyph_w(font_size: u16) -> u16 {
+    font_size.saturating_sub(4).max(1)
+}
+
 /// When scrolling past 0 whole, allows unbounded negative pixel offset
 fn scroll_impl(whole: &mut usize, pixel: &mut i16, pixels_per_whole: i16, scroll_by: i16) {
     *pixel += scroll_by;
@@ -588,6 +711,58 @@ fn test_scroll_impl_negative() {
     assert_eq!((whole, pixel), (0, -320));
 }
 
+#[test]
+fn test_bin_view_toggle_bit() {
+    let mut view = View::new(
+        ViewKind::Bin(HexData::with_font_size(14)),
+        PerspectiveKey::default(),
+    );
+    let mut data = Data::clean_from_buf(vec![0b1010_0000]);
+    view.edit_buffer_mut().unwrap().cursor = 2;
+    assert_eq!(view.bit_cursor(), Some(2));
+    view.toggle_bit(0, &mut data);
+    assert_eq!(data[0], 0b1000_0000);
+    // While typing, the typed digit is flipped instead
+    view.edit_buffer_mut().unwrap().dirty = true;
+    view.edit_buffer_mut().unwrap().buf.copy_from_slice(b"10000000");
+    view.toggle_bit(0, &mut data);
+    assert_eq!(data[0], 0b1000_0000);
+    assert_eq!(view.edit_buffer_mut().unwrap().buf, b"10100000");
+}
+
+#[test]
+fn test_bin_view_small_font() {
+    for font_size in [0, 3, 4, 14] {
+        let mut view = View::new(
+            ViewKind::Bin(HexData::with_font_size(font_size)),
+            PerspectiveKey::default(),
+        );
+        view.adjust_block_size();
+        assert!(view.col_w >= bit_glyph_w(font_size) * 8);
+    }
+}
+
+#[test]
+fn test_confirm_editing_insert_mode() {
+    let mut view = View::new(
+        ViewKind::Hex(HexData::with_font_size(14)),
+        PerspectiveKey::default(),
+    );
+    let mut data = Data::clean_from_buf(vec![0xAA, 0xBB]);
+    let mut edit_state = EditState::default();
+    edit_state.insert_mode = true;
+    let prefs = SessionPrefs::default();
+    let mut msg = MessageDialog::default();
+    // A clean edit buffer doesn't insert anything
+    view.edit_buffer_mut().unwrap().buf.copy_from_slice(b"CC");
+    assert!(view.confirm_editing(&mut edit_state, &mut data, &prefs, &mut msg).is_none());
+    assert_eq!(&data[..], [0xAA, 0xBB]);
+    // A partially typed byte does
+    view.handle_text_entered('1', &mut edit_state, &prefs, &mut data, &mut msg);
+    assert!(view.confirm_editing(&mut edit_state, &mut data, &prefs, &mut msg).is_some());
+    assert_eq!(&data[..], [0x10, 0xAA, 0xBB]);
+}
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct ScrollOffset {
     /// What column we are at
@@ -656,6 +831,8 @@ impl TryFrom<(i32, i32)> for ViewportVec {
 pub enum ViewKind {
     Hex(HexData),
     Dec(HexData),
+    /// Each byte as 8 bits, most significant first
+    Bin(HexData),
     Text(TextData),
     Block,
 }
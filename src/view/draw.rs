@@ -1,13 +1,15 @@
 use {
-    super::View,
+    super::{View, bit_glyph_w},
     crate::{
-        app::{App, presentation::Presentation},
+        app::{App, compare::CompareDoc, presentation::Presentation},
+        binfmt,
         color::RgbColor,
         dec_conv,
         gui::Gui,
         hex_conv,
         hex_ui::HexUi,
-        meta::{PerspectiveMap, RegionMap, ViewKey, region::Region},
+        meta::{MetaLow, PerspectiveMap, RegionMap, ViewKey, region::Region},
+        source::SourceProvider,
         struct_meta_item::StructMetaItem,
         view::ViewKind,
     },
@@ -19,8 +21,14 @@ use {
     },
     either::Either,
     slotmap::Key as _,
+    std::ops::Range,
 };
 
+/// Color for highlighting bytes that differ from the compared document
+const DIFF_COLOR: Color = Color::rgba(200, 40, 40, 140);
+/// Color for greying out the unmapped gaps of encoded data
+const GAP_COLOR: Color = Color::rgba(30, 30, 30, 170);
+
 struct DrawArgs<'vert, 'data> {
     vertices: &'vert mut Vec<Vertex>,
     x: f32,
@@ -38,6 +46,8 @@ fn draw_view<'f>(
     app_regions: &RegionMap,
     app_structs: &[StructMetaItem],
     app_data: &[u8],
+    diff_against: Option<&[u8]>,
+    gaps: &[Range<usize>],
     app_hex_ui: &HexUi,
     app_ui: &Gui,
     vertex_buffer: &mut Vec<Vertex>,
@@ -104,19 +114,55 @@ fn draw_view<'f>(
                         .presentation
                         .color_method
                         .byte_color(data[0], view.presentation.invert_color);
+                    let differs = diff_against.is_some_and(|other| {
+                        other.get(idx..idx + view.bytes_per_block as usize) != Some(data)
+                    });
                     #[expect(
                         clippy::cast_precision_loss,
                         reason = "At this point, the viewport coordinates should be small enough to fit in viewport"
                     )]
+                    let (x, y) = (viewport_x as f32, viewport_y as f32);
+                    // Block views fill the whole block, so they get an outline instead
+                    if differs && !matches!(view.kind, ViewKind::Block) {
+                        draw_rect(
+                            vertex_buffer,
+                            x,
+                            y,
+                            f32::from(view.col_w),
+                            f32::from(view.row_h),
+                            DIFF_COLOR,
+                        );
+                    }
                     drawfn(DrawArgs {
                         vertices: vertex_buffer,
-                        x: viewport_x as f32,
-                        y: viewport_y as f32,
+                        x,
+                        y,
                         data,
                         idx,
                         color: c,
                         highlight: should_highlight(app_hex_ui.selected_regions(), idx, app_ui),
                     });
+                    if in_gap(gaps, idx) {
+                        draw_rect(
+                            vertex_buffer,
+                            x,
+                            y,
+                            f32::from(view.col_w),
+                            f32::from(view.row_h),
+                            GAP_COLOR,
+                        );
+                    }
+                    if differs && matches!(view.kind, ViewKind::Block) {
+                        draw_rect_outline(
+                            vertex_buffer,
+                            x,
+                            y,
+                            f32::from(view.col_w),
+                            f32::from(view.row_h),
+                            DIFF_COLOR,
+                            -1.0,
+                        );
+                    }
                     /*if gamedebug_core::enabled() {
                         #[expect(
                             clippy::cast_precision_loss,
@@ -165,6 +211,14 @@ fn draw_view<'f>(
                 };
                 let mut col = 0;
                 for (i, field) in struct_.fields.iter().enumerate() {
+                    // Conditional fields and fields of unknown size move later fields depending
+                    // on the data, so they can't be shown as columns
+                    if !field.conds.is_empty() {
+                        break;
+                    }
+                    let Some(size) = field.ty.size() else {
+                        break;
+                    };
                     // Draw field names if alt overlay is enabled
                     // TODO: Very hacky, needs proper support in the future
                     if app_hex_ui.show_alt_overlay
@@ -180,7 +234,7 @@ fn draw_view<'f>(
                         text.tf.position = [f32::from(x), f32::from(y) + y_off];
                         overlay_texts.push(text);
                     }
-                    col += field.ty.size();
+                    col += size;
                     let Some(line_x) = line_x(view, col) else {
                         continue;
                     };
@@ -447,21 +501,85 @@ impl View {
         vertex_buffer: &mut Vec<Vertex>,
         font: &Font,
     ) {
-        vertex_buffer.clear();
-        let mut rs = RenderStates::default();
         let Some(this) = app.meta_state.meta.views.get(key) else {
             return;
         };
+        let diff_against =
+            app.compare.as_ref().filter(|doc| doc.highlight_diff).map(|doc| &doc.data[..]);
+        let gaps = match app.source.as_ref().map(|src| &src.provider) {
+            Some(SourceProvider::Encoded { segments, .. }) => {
+                binfmt::gaps(segments, app.data.len())
+            }
+            _ => Vec::new(),
+        };
+        this.view.draw_data(
+            key,
+            &this.name,
+            &app.meta_state.meta.low,
+            &app.data,
+            diff_against,
+            &gaps,
+            app,
+            gui,
+            window,
+            vertex_buffer,
+            font,
+        );
+    }
+    /// Draw this view of the compared document. `key` is the view it's paired with.
+    pub fn draw_compare(
+        &self,
+        key: ViewKey,
+        doc: &CompareDoc,
+        app: &App,
+        gui: &Gui,
+        window: &mut RenderWindow,
+        vertex_buffer: &mut Vec<Vertex>,
+        font: &Font,
+    ) {
+        let name = app.meta_state.meta.views.get(key).map_or("", |view| view.name.as_str());
+        self.draw_data(
+            key,
+            name,
+            &doc.meta_low,
+            &doc.data,
+            doc.highlight_diff.then_some(&app.data[..]),
+            &[],
+            app,
+            gui,
+            window,
+            vertex_buffer,
+            font,
+        );
+    }
+    fn draw_data(
+        &self,
+        key: ViewKey,
+        name: &str,
+        low: &MetaLow,
+        data: &[u8],
+        diff_against: Option<&[u8]>,
+        gaps: &[Range<usize>],
+        app: &App,
+        gui: &Gui,
+        window: &mut RenderWindow,
+        vertex_buffer: &mut Vec<Vertex>,
+        font: &Font,
+    ) {
+        vertex_buffer.clear();
+        let mut rs = RenderStates::default();
         let mut overlay_texts = Vec::new();
-        match &this.view.kind {
+        match &self.kind {
             ViewKind::Hex(hex) => {
                 draw_view(
-                    &this.view,
+                    self,
                     key,
-                    &app.meta_state.meta.low.perspectives,
-                    &app.meta_state.meta.low.regions,
+                    &low.perspectives,
+                    &low.regions,
                     &app.meta_state.meta.structs,
-                    &app.data,
+                    data,
+                    diff_against,
+                    gaps,
                     &app.hex_ui,
                     gui,
                     vertex_buffer,
@@ -481,9 +599,9 @@ impl View {
                                 vertices,
                                 x,
                                 y,
-                                f32::from(this.view.col_w),
-                                f32::from(this.view.row_h),
-                                this.view.presentation.sel_color.into(),
+                                f32::from(self.col_w),
+                                f32::from(self.row_h),
+                                self.presentation.sel_color.into(),
                             );
                         }
                         let mut gx = x;
@@ -512,7 +630,7 @@ impl View {
                                 vertices,
                                 app.hex_ui.focused_view == Some(key),
                                 app.hex_ui.cursor_flash_timer(),
-                                &this.view.presentation,
+                                &self.presentation,
                                 hex.font_size,
                             );
                         }
@@ -522,12 +640,14 @@ impl View {
             }
             ViewKind::Dec(dec) => {
                 draw_view(
-                    &this.view,
+                    self,
                     key,
-                    &app.meta_state.meta.low.perspectives,
-                    &app.meta_state.meta.low.regions,
+                    &low.perspectives,
+                    &low.regions,
                     &app.meta_state.meta.structs,
-                    &app.data,
+                    data,
+                    diff_against,
+                    gaps,
                     &app.hex_ui,
                     gui,
                     vertex_buffer,
@@ -547,9 +667,9 @@ impl View {
                                 vertices,
                                 x,
                                 y,
-                                f32::from(this.view.col_w),
-                                f32::from(this.view.row_h),
-                                this.view.presentation.sel_color.into(),
+                                f32::from(self.col_w),
+                                f32::from(self.row_h),
+                                self.presentation.sel_color.into(),
                             );
                         }
                         let mut gx = x;
@@ -578,7 +698,7 @@ impl View {
                                 vertices,
                                 app.hex_ui.focused_view == Some(key),
                                 app.hex_ui.cursor_flash_timer(),
-                                &this.view.presentation,
+                                &self.presentation,
                                 dec.font_size,
                             );
                         }
@@ -586,14 +706,87 @@ impl View {
                 );
                 rs.texture = Some(font.texture(dec.font_size.into()));
             }
+            ViewKind::Bin(bin) => {
+                draw_view(
+                    self,
+                    key,
+                    &low.perspectives,
+                    &low.regions,
+                    &app.meta_state.meta.structs,
+                    data,
+                    diff_against,
+                    gaps,
+                    &app.hex_ui,
+                    gui,
+                    vertex_buffer,
+                    &mut overlay_texts,
+                    font,
+                    |DrawArgs {
+                         vertices,
+                         x,
+                         y,
+                         data,
+                         idx,
+                         color: c,
+                         highlight,
+                     }| {
+                        if highlight {
+                            draw_rect(
+                                vertices,
+                                x,
+                                y,
+                                f32::from(self.col_w),
+                                f32::from(self.row_h),
+                                self.presentation.sel_color.into(),
+                            );
+                        }
+                        let mut gx = x;
+                        for i in 0..8 {
+                            let mut d = if data[0] & (0x80 >> i) == 0 {
+                                b'0'
+                            } else {
+                                b'1'
+                            };
+                            if idx == app.edit_state.cursor && bin.edit_buf.dirty {
+                                d = bin.edit_buf.buf[i];
+                            }
+                            draw_glyph(
+                                font,
+                                bin.font_size.into(),
+                                vertices,
+                                gx,
+                                y,
+                                d.into(),
+                                c.into(),
+                            );
+                            gx += f32::from(bit_glyph_w(bin.font_size));
+                        }
+                        let extra_x = bin.edit_buf.cursor * bit_glyph_w(bin.font_size);
+                        if !app.preferences.hide_cursor && idx == app.edit_state.cursor {
+                            draw_text_cursor(
+                                x + f32::from(extra_x),
+                                y,
+                                vertices,
+                                app.hex_ui.focused_view == Some(key),
+                                app.hex_ui.cursor_flash_timer(),
+                                &self.presentation,
+                                bin.font_size,
+                            );
+                        }
+                    },
+                );
+                rs.texture = Some(font.texture(bin.font_size.into()));
+            }
             ViewKind::Text(text) => {
                 draw_view(
-                    &this.view,
+                    self,
                     key,
-                    &app.meta_state.meta.low.perspectives,
-                    &app.meta_state.meta.low.regions,
+                    &low.perspectives,
+                    &low.regions,
                     &app.meta_state.meta.structs,
-                    &app.data,
+                    data,
+                    diff_against,
+                    gaps,
                     &app.hex_ui,
                     gui,
                     vertex_buffer,
@@ -613,9 +806,9 @@ impl View {
                                 vertices,
                                 x,
                                 y,
-                                f32::from(this.view.col_w),
-                                f32::from(this.view.row_h),
-                                this.view.presentation.sel_color.into(),
+                                f32::from(self.col_w),
+                                f32::from(self.row_h),
+                                self.presentation.sel_color.into(),
                             );
                         }
                         let raw_data = match text.text_kind {
@@ -646,7 +839,7 @@ impl View {
                                 vertices,
                                 app.hex_ui.focused_view == Some(key),
                                 app.hex_ui.cursor_flash_timer(),
-                                &this.view.presentation,
+                                &self.presentation,
                                 text.font_size,
                             );
                         }
@@ -656,12 +849,14 @@ impl View {
             }
             ViewKind::Block => {
                 draw_view(
-                    &this.view,
+                    self,
                     key,
-                    &app.meta_state.meta.low.perspectives,
-                    &app.meta_state.meta.low.regions,
+                    &low.perspectives,
+                    &low.regions,
                     &app.meta_state.meta.structs,
-                    &app.data,
+                    data,
+                    diff_against,
+                    gaps,
                     &app.hex_ui,
                     gui,
                     vertex_buffer,
@@ -683,8 +878,8 @@ impl View {
                             vertices,
                             x,
                             y,
-                            f32::from(this.view.col_w),
-                            f32::from(this.view.row_h),
+                            f32::from(self.col_w),
+                            f32::from(self.row_h),
                             c.into(),
                         );
                         if !app.preferences.hide_cursor && idx == app.edit_state.cursor {
@@ -694,8 +889,8 @@ impl View {
                                 vertices,
                                 app.hex_ui.focused_view == Some(key),
                                 app.hex_ui.cursor_flash_timer(),
-                                &this.view.presentation,
-                                &this.view,
+                                &self.presentation,
+                                self,
                             );
                         }
                     },
@@ -704,10 +899,10 @@ impl View {
         }
         draw_rect_outline(
             vertex_buffer,
-            f32::from(this.view.viewport_rect.x - 2),
-            f32::from(this.view.viewport_rect.y - 2),
-            f32::from(this.view.viewport_rect.w + 3),
-            f32::from(this.view.viewport_rect.h + 3),
+            f32::from(self.viewport_rect.x - 2),
+            f32::from(self.viewport_rect.y - 2),
+            f32::from(self.viewport_rect.w + 3),
+            f32::from(self.viewport_rect.h + 3),
             if Some(key) == app.hex_ui.focused_view {
                 Color::rgb(255, 255, 150)
             } else {
@@ -725,30 +920,25 @@ impl View {
                 )]
                 let vh = window.size().y as i16;
                 let [x, y, w, h] = rect_to_gl_viewport(
-                    this.view.viewport_rect.x - 2,
-                    this.view.viewport_rect.y - 2,
-                    this.view.viewport_rect.w + 3,
-                    this.view.viewport_rect.h + 3,
+                    self.viewport_rect.x - 2,
+                    self.viewport_rect.y - 2,
+                    self.viewport_rect.w + 3,
+                    self.viewport_rect.h + 3,
                     vh,
                 );
                 glu_sys::glScissor(x, y, w, h);
             }
         }
         if app.hex_ui.show_alt_overlay {
-            let per = &app.meta_state.meta.low.perspectives[this.view.perspective];
+            let per = &low.perspectives[self.perspective];
             let mut text = Text::new(
-                format!(
-                    "{}\n{}x{}",
-                    this.name,
-                    per.n_rows(&app.meta_state.meta.low.regions),
-                    per.cols
-                ),
+                format!("{}\n{}x{}", name, per.n_rows(&low.regions), per.cols),
                 font,
                 16,
             );
             text.tf.position = [
-                f32::from(this.view.viewport_rect.x),
-                f32::from(this.view.viewport_rect.y),
+                f32::from(self.viewport_rect.x),
+                f32::from(self.viewport_rect.y),
             ];
             let text_bounds = text.global_bounds();
             draw_rect(
@@ -788,6 +978,20 @@ fn test_rect_to_gl() {
     );
 }
 
+#[test]
+fn test_in_gap() {
+    let gaps = [2..4, 8..9];
+    let in_gaps: Vec<_> = (0..10).filter(|&idx| in_gap(&gaps, idx)).collect();
+    assert_eq!(in_gaps, [2, 3, 8]);
+    assert!(!in_gap(&[], 0));
+}
+
+/// Whether `idx` is in one of the sorted `gaps`
+fn in_gap(gaps: &[Range<usize>], idx: usize) -> bool {
+    let i = gaps.partition_point(|gap| gap.end <= idx);
+    gaps.get(i).is_some_and(|gap| gap.contains(&idx))
+}
+
 fn should_highlight(
     mut app_selection: impl Iterator<Item = Region>,
     idx: usize,
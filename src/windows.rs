@@ -39,6 +39,7 @@ unsafe fn load_proc_memory_inner(
     line_spacing: u16,
 ) -> anyhow::Result<()> {
     unsafe { read_proc_memory(handle, &mut app.data, start, size) }?;
+    app.data.fix_len();
     app.source = Some(Source {
         attr: SourceAttributes {
             permissions: SourcePermissions { write: true },